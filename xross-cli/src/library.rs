@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use xross_core::{XrossBuffer, XrossString};
+use xross_metadata::XrossMetadataBundle;
+
+/// A compiled xross library: its exported symbols and the metadata embedded in it.
+pub struct XrossLibrary {
+    pub path: PathBuf,
+    /// Names of the exported functions, without the platform's symbol prefix.
+    pub exports: BTreeSet<String>,
+    pub bundle: XrossMetadataBundle,
+}
+
+impl XrossLibrary {
+    /// Reads the export table of `path`, then loads it to call `xross_metadata`.
+    /// Loading runs the library's initializers, so only open libraries you trust.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let exports = read_exports(path)?;
+        let bundle = read_bundle(path)?;
+        Ok(Self { path: path.to_path_buf(), exports, bundle })
+    }
+}
+
+fn read_exports(path: &Path) -> Result<BTreeSet<String>, String> {
+    use object::Object;
+
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let file = object::File::parse(&*data).map_err(|e| format!("{}: {}", path.display(), e))?;
+    // Mach-O prefixes C symbols with `_`.
+    let prefix = if file.format() == object::BinaryFormat::MachO { "_" } else { "" };
+    let mut exports = BTreeSet::new();
+    for export in file.exports().map_err(|e| format!("{}: {}", path.display(), e))? {
+        let name = String::from_utf8_lossy(export.name());
+        exports.insert(name.strip_prefix(prefix).unwrap_or(&name).to_string());
+    }
+    Ok(exports)
+}
+
+fn read_bundle(path: &Path) -> Result<XrossMetadataBundle, String> {
+    let error = |e: libloading::Error| format!("{}: {}", path.display(), e);
+    // SAFETY: both symbols are exported by xross-core with these signatures.
+    let json = unsafe {
+        let library = libloading::Library::new(path).map_err(error)?;
+        let metadata: libloading::Symbol<unsafe extern "C" fn(*mut XrossString)> =
+            library.get(b"xross_metadata").map_err(error)?;
+        let free: libloading::Symbol<unsafe extern "C" fn(XrossBuffer)> =
+            library.get(b"xross_free_buffer").map_err(error)?;
+        let mut out = std::mem::MaybeUninit::<XrossString>::uninit();
+        metadata(out.as_mut_ptr());
+        let out = out.assume_init();
+        // The buffer belongs to the library's allocator, so it is copied and handed back.
+        let json =
+            String::from_utf8_lossy(std::slice::from_raw_parts(out.ptr as *const u8, out.len))
+                .into_owned();
+        free(out);
+        json
+    };
+    XrossMetadataBundle::from_json(&json).map_err(|e| format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,250 @@
+//! Inspects a compiled xross library (`.so`, `.dylib` or `.dll`).
+//!
+//! Usage:
+//! - `xross dump <library>` prints the embedded metadata bundle as JSON.
+//! - `xross verify <library>` lists symbols described by the metadata that the library
+//!   does not export.
+//! - `xross orphans <library>` lists symbols exported under the prefix of a crate in the
+//!   metadata that no definition describes.
+//! - `xross header <library|bundle.json|metadata dir>` prints a C header declaring the
+//!   exported functions.
+//! - `xross abi-check <old> <new>` compares two metadata snapshots, each a library, a bundle
+//!   or a metadata directory, and prints a JSON compatibility report.
+//! - `xross schema` prints the JSON Schema of the metadata format, which is committed as
+//!   `xross-metadata/schema.json`.
+//!
+//! `verify` and `orphans` exit with 1 when they find anything, `abi-check` when a change
+//! breaks existing bindings, and every command exits with 2 if its input cannot be read.
+
+mod library;
+
+use library::XrossLibrary;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::ExitCode;
+use xross_metadata::{XROSS_RUNTIME_SYMBOLS, XrossCompatReport, XrossMetadataSet};
+
+const USAGE: &str = "usage: xross <dump|verify|orphans> <library>\n       xross header <library|bundle.json|metadata dir>\n       xross abi-check <old> <new>\n       xross schema";
+
+fn metadata_set(library: &XrossLibrary) -> Result<XrossMetadataSet, String> {
+    let mut set = XrossMetadataSet::new();
+    for def in library.bundle.definitions.iter().cloned() {
+        set.insert(def).map_err(|e| format!("{}: {}", library.path.display(), e))?;
+    }
+    Ok(set)
+}
+
+fn dump(library: &XrossLibrary) -> Result<bool, String> {
+    let json = serde_json::to_string_pretty(&library.bundle).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(true)
+}
+
+fn verify(library: &XrossLibrary) -> Result<bool, String> {
+    let set = metadata_set(library)?;
+    let symbols = set.exported_symbols();
+    let mut missing = 0;
+    for (signature, symbol) in &symbols {
+        if !library.exports.contains(&symbol.name) {
+            let member = symbol.member.as_deref().map(|m| format!(" {}", m)).unwrap_or_default();
+            println!("missing: {} ({}{})", symbol.name, signature, member);
+            missing += 1;
+        }
+    }
+    for name in XROSS_RUNTIME_SYMBOLS.iter().filter(|n| !library.exports.contains(**n)) {
+        println!("missing: {} (xross runtime)", name);
+        missing += 1;
+    }
+    eprintln!("{} of {} symbols missing", missing, symbols.len() + XROSS_RUNTIME_SYMBOLS.len());
+    Ok(missing == 0)
+}
+
+fn orphans(library: &XrossLibrary) -> Result<bool, String> {
+    let set = metadata_set(library)?;
+    let described: BTreeSet<String> =
+        set.exported_symbols().into_iter().map(|(_, s)| s.name).collect();
+    let prefixes: Vec<String> =
+        library.bundle.crates.iter().map(|c| format!("{}_", c.name.replace('-', "_"))).collect();
+    let orphans: Vec<&String> = library
+        .exports
+        .iter()
+        .filter(|name| prefixes.iter().any(|p| name.starts_with(p.as_str())))
+        .filter(|name| !described.contains(*name))
+        .collect();
+    for name in &orphans {
+        println!("{}", name);
+    }
+    eprintln!("{} orphan symbols", orphans.len());
+    Ok(orphans.is_empty())
+}
+
+/// Loads a metadata directory, a bundle written by `xross dump`, or the metadata embedded
+/// in a library.
+fn load_set(path: &Path) -> Result<XrossMetadataSet, String> {
+    let error = |e: xross_metadata::XrossMetadataError| format!("{}: {}", path.display(), e);
+    if path.is_dir() {
+        XrossMetadataSet::load_dir(path).map_err(error)
+    } else if path.extension().is_some_and(|ext| ext == "json") {
+        let json =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        XrossMetadataSet::from_bundle(&json).map_err(error)
+    } else {
+        metadata_set(&XrossLibrary::open(path)?)
+    }
+}
+
+/// Include guard for the header of `path`, e.g. `MYLIB_H` for `libmylib.so`.
+fn header_guard(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("xross");
+    let stem = stem.strip_prefix("lib").unwrap_or(stem);
+    stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .chain("_H".chars())
+        .collect()
+}
+
+fn header(path: &Path) -> Result<bool, String> {
+    let set = load_set(path)?;
+    print!("{}", xross_header::generate_header(&set, &header_guard(path)));
+    Ok(true)
+}
+
+fn compat_report(old: &Path, new: &Path) -> Result<XrossCompatReport, String> {
+    let load = |path| {
+        let mut set = load_set(path)?;
+        // Unresolvable names are compared as written; they are reported by `validate`, not here.
+        let _ = set.resolve();
+        Ok::<_, String>(set)
+    };
+    Ok(XrossCompatReport::between(&load(old)?, &load(new)?))
+}
+
+fn abi_check(old: &Path, new: &Path) -> Result<bool, String> {
+    let report = compat_report(old, new)?;
+    println!("{}", report.to_json());
+    for change in report.breaking_changes() {
+        let member = change.member.as_deref().map(|m| format!(" {}", m)).unwrap_or_default();
+        eprintln!("breaking: {}{}: {}", change.signature, member, change.detail);
+    }
+    Ok(!report.is_breaking())
+}
+
+fn schema() -> Result<bool, String> {
+    let json = serde_json::to_string_pretty(&xross_metadata::xross_metadata_schema())
+        .map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(true)
+}
+
+fn exit_code(result: Result<bool, String>) -> ExitCode {
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let run = match args.as_slice() {
+        [command, old, new] if command == "abi-check" => {
+            return exit_code(abi_check(Path::new(old), Path::new(new)));
+        }
+        [command] if command == "schema" => return exit_code(schema()),
+        [command, path] if command == "header" => return exit_code(header(Path::new(path))),
+        [command, _] if command == "dump" => dump,
+        [command, _] if command == "verify" => verify,
+        [command, _] if command == "orphans" => orphans,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    exit_code(XrossLibrary::open(Path::new(&args[1])).and_then(|library| run(&library)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use xross_metadata::{XrossChangeKind, XrossEndian, XrossMetadataBundle, XrossTarget};
+
+    fn answer(ret: &str) -> xross_metadata::XrossDefinition {
+        serde_json::from_value(serde_json::json!({
+            "kind": "function",
+            "signature": "demo.answer",
+            "symbol": "demo_answer",
+            "packageName": "demo",
+            "name": "answer",
+            "method": {
+                "name": "answer",
+                "symbol": "demo_answer",
+                "methodType": "Static",
+                "handleMode": { "kind": "normal" },
+                "isConstructor": false,
+                "isDefault": false,
+                "isAsync": false,
+                "args": [],
+                "ret": ret,
+                "docs": [],
+                "safety": "Lock",
+            },
+            "docs": [],
+        }))
+        .unwrap()
+    }
+
+    /// Writes a bundle holding `answer(ret)` to a fresh directory and returns its path.
+    fn write_bundle(name: &str, ret: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("xross-cli-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = XrossTarget {
+            triple: "x86_64-unknown-linux-gnu".to_string(),
+            pointer_width: 64,
+            endian: XrossEndian::Little,
+        };
+        let bundle = XrossMetadataBundle::new(vec![], target, vec![answer(ret)]);
+        let path = dir.join("bundle.json");
+        std::fs::write(&path, bundle.to_json()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_header_guard() {
+        assert_eq!(header_guard(Path::new("target/release/libmy-lib.so")), "MY_LIB_H");
+        assert_eq!(header_guard(Path::new("my_lib.dll")), "MY_LIB_H");
+        assert_eq!(header_guard(Path::new("target/xross")), "XROSS_H");
+    }
+
+    #[test]
+    fn test_load_set() {
+        let bundle = write_bundle("load", "I32");
+        let set = load_set(&bundle).unwrap();
+        assert!(set.get("demo.answer").is_some());
+
+        let dir = bundle.parent().unwrap().join("metadata");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("answer.json"), serde_json::to_string(&answer("I32")).unwrap())
+            .unwrap();
+        assert_eq!(load_set(&dir).unwrap().len(), 1);
+
+        let missing = bundle.with_file_name("missing.json");
+        let error = load_set(&missing).unwrap_err();
+        assert!(error.starts_with(&missing.display().to_string()), "{}", error);
+    }
+
+    #[test]
+    fn test_abi_check() {
+        let old = write_bundle("old", "I32");
+        let new = write_bundle("new", "I64");
+        assert!(abi_check(&old, &old).unwrap());
+        assert!(!abi_check(&old, &new).unwrap());
+
+        let report = compat_report(&old, &new).unwrap();
+        let kinds: Vec<XrossChangeKind> = report.changes.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, [XrossChangeKind::ReturnTypeChanged]);
+    }
+}
@@ -0,0 +1,5 @@
+fn main() {
+    // Recorded in the metadata bundle so tools can tell which platform a library targets.
+    let target = std::env::var("TARGET").unwrap_or_default();
+    println!("cargo:rustc-env=XROSS_TARGET={}", target);
+}
@@ -0,0 +1,411 @@
+//! The calling convention between generated exports and bindings (`XROSS_ABI_VERSION` 6).
+//!
+//! This module is the normative description of the ABI. The macros do not spell out
+//! encodings themselves. Generated code calls the encoders and decoders below, so a change
+//! here is a change to the ABI and must bump `XROSS_ABI_VERSION`.
+//!
+//! # Arguments
+//!
+//! | `XrossType` | C parameters | Decoded by |
+//! | :--- | :--- | :--- |
+//! | scalars, `Pointer` | the value | - |
+//! | `String` | `ptr, len, encoding` | `XrossStringView::to_string_lossy` |
+//! | `Slice(T)`, `Vec(T)` | `ptr, len` of `T` elements (copied for `Vec`) | - |
+//! | `Object` | `void*`. Owned values are moved out with `ptr::read`; the caller keeps the memory | [`XrossOwned`] |
+//! | owned by-value struct | the struct itself, as its C layout | [`XrossOwned`] |
+//! | `Option(T)` | `void*`, null for `None`; points to the value of a scalar, or to an `XrossStringView` | [`XrossArgPtr`] |
+//! | `Result { ok, .. }` | `XrossResult`; `Ok` as in `Option`, `Err` points to an `XrossStringView` | [`result_from_arg`] |
+//! | `CancelToken` | `const AtomicBool*`, null for never cancelled | `XrossCancelToken::from_raw` |
+//!
+//! # Returns
+//!
+//! | `XrossType` | Direct export | `panicable` export |
+//! | :--- | :--- | :--- |
+//! | `Void`, scalars | the value | `out XrossResult`, [`XrossPtr`] |
+//! | `String`, `Slice`, `Vec` | `out XrossBuffer`, written unaligned | `out XrossResult` holding a boxed `XrossBuffer` |
+//! | `Object` | `void*` owning a `Box<T>`; borrowed for references | `out XrossResult`, the same pointer |
+//! | owned by-value struct | the struct itself, as its C layout | `out XrossResult` holding a `Box<T>` |
+//! | `Option(T)` | `void*`, null for `None`; a scalar is boxed, a string is a boxed `XrossBuffer`, [`XrossPtr::into_xross_option_ptr`] | `out XrossResult` holding that pointer |
+//! | `Result { ok, err }` | `XrossResult` by value, both sides [`XrossPtr`] | the same `XrossResult` |
+//!
+//! `async` and `blocking` exports return `out XrossTask`. Polling it returns
+//! `XrossPoll::Pending` while the call runs, then writes the `XrossResult` of [`ok`] to `out`
+//! and returns `XrossPoll::Ready` once. Iterators and streams yield every
+//! item as [`ok`]; a stream's producer waits while an item is left unpolled. Any [`XrossPtr`] value can complete a task; a `Result` cannot. A panic,
+//! rejection or timeout is an `Err` holding a boxed `XrossBuffer` with the message, see
+//! [`error`]. The binding calls `drop_fn` once it has the result or gives up on it. For a
+//! `blocking` call that has already started, `drop_fn` returns only when the call has
+//! finished, so the binding must keep the receiver and its locks until `drop_fn` returns.
+//!
+//! A by-value struct is a `#[repr(C)]` `Copy` struct whose fields are all scalar
+//! `#[xross_field]`s, marked `byValue` in its definition. Only owned values directly in an
+//! argument or return are passed as the struct; constructors, options, results, vectors,
+//! tasks, fields and `clone` still use a `Box<T>` handle like any other object.
+//!
+//! Field getters encode `Option` fields like a returned `Option`, and `Result` fields with
+//! [`result`].
+//! Raw exports pass their declared C types and are outside this specification.
+//!
+//! # Buffers
+//!
+//! `cap` and `len` of an `XrossBuffer` count elements. The binding releases a buffer once it
+//! has copied it, with the function of its element width named by
+//! `XrossType::buffer_release_symbol`: `xross_free_buffer` for strings and 1-byte elements,
+//! `xross_free_buffer_16`, `_32`, `_64`, and `_ptr` for `isize`, `usize` and object handles.
+//! Boxes around a buffer (in an `XrossResult`) are not released. The boxed scalar of a
+//! `Some` is released with `xross_free_scalar(ptr, size)` once it has been read.
+//!
+//! Methods declared `contiguous` return a `Vec` or slice of objects as the elements
+//! themselves ([`buffer_from_contiguous`]): element `i` is at `ptr + i * size`, with `size`
+//! from the type's layout. The elements are views that stay valid until the binding releases
+//! the whole buffer with `{prefix}_drop_vec` of the element type, which drops every element.
+//!
+//! # Pointer-sized slots
+//!
+//! A value encoded in a single pointer (an `XrossResult`, a task result, an iterator or
+//! stream item) holds scalars directly. Where pointers are narrower than 64 bits, `i64`,
+//! `u64` and `f64` do not fit and are boxed like the `Some` of a scalar instead, released
+//! with `xross_free_scalar(ptr, 8)` once read. Bindings know the pointer width from the
+//! bundle's target.
+//!
+//! The conformance suite in `xross-test/fixture/tests/conformance.rs` calls one export per
+//! type and handle mode through `xross-test` and checks that every value round-trips.
+
+use crate::{XrossBuffer, XrossClass, XrossResult, XrossString, XrossStringView};
+use std::ffi::c_void;
+
+/// Encoding of a value in a single pointer: the `ptr` of an `XrossResult`, or a returned
+/// `Option`. Encoding hands ownership to the caller; decoding takes it back.
+pub trait XrossPtr: Sized {
+    fn into_xross_ptr(self) -> *mut c_void;
+
+    /// Decodes a pointer produced by `into_xross_ptr`, as a binding does.
+    /// # Safety
+    /// `ptr` must come from `into_xross_ptr` of the same type and not be decoded twice.
+    unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self;
+
+    /// Encoding of `Some(self)` when an export returns `Option<Self>` directly.
+    fn into_xross_option_ptr(self) -> *mut c_void {
+        self.into_xross_ptr()
+    }
+
+    /// Decodes a non-null pointer produced by `into_xross_option_ptr`.
+    /// # Safety
+    /// Same as `from_xross_ptr`.
+    unsafe fn from_xross_option_ptr(ptr: *mut c_void) -> Self {
+        unsafe { Self::from_xross_ptr(ptr) }
+    }
+}
+
+/// Encoding of the value of an `Option` argument, or of the `Ok` of a `Result` argument.
+/// The caller keeps ownership of the pointee.
+pub trait XrossArgPtr: Sized {
+    /// # Safety
+    /// `ptr` must be non-null and encode a value of this type.
+    unsafe fn from_xross_arg(ptr: *mut c_void) -> Self;
+}
+
+/// A `Some` of a scalar is a `Box` of the value, so that zero is not read back as `None`.
+/// The binding releases it with `xross_free_scalar`.
+macro_rules! boxed_option {
+    ($ty:ty) => {
+        fn into_xross_option_ptr(self) -> *mut c_void {
+            Box::into_raw(Box::new(self)) as *mut c_void
+        }
+
+        unsafe fn from_xross_option_ptr(ptr: *mut c_void) -> Self {
+            *unsafe { Box::from_raw(ptr as *mut $ty) }
+        }
+    };
+}
+
+/// Scalar arguments behind a pointer (the `Some` of an `Option`, the `Ok` of a `Result`)
+/// point to the value, which the caller keeps.
+macro_rules! impl_scalar_arg {
+    ($($ty:ty),*) => {$(
+        impl XrossArgPtr for $ty {
+            unsafe fn from_xross_arg(ptr: *mut c_void) -> Self {
+                unsafe { std::ptr::read_unaligned(ptr as *const $ty) }
+            }
+        }
+    )*};
+}
+
+impl_scalar_arg!(bool, i8, u8, i16, u16, i32, u32, i64, u64, isize, usize, f32, f64);
+
+macro_rules! impl_scalar {
+    ($($ty:ty),*) => {$(
+        impl XrossPtr for $ty {
+            fn into_xross_ptr(self) -> *mut c_void {
+                self as usize as *mut c_void
+            }
+
+            unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+                ptr as usize as $ty
+            }
+
+            boxed_option!($ty);
+        }
+    )*};
+}
+
+impl_scalar!(i8, u8, i16, u16, i32, u32, isize, usize);
+
+/// 64-bit integers fit a pointer only on 64-bit targets; elsewhere they are boxed.
+macro_rules! impl_wide_scalar {
+    ($($ty:ty),*) => {$(
+        impl XrossPtr for $ty {
+            fn into_xross_ptr(self) -> *mut c_void {
+                if cfg!(target_pointer_width = "64") {
+                    self as usize as *mut c_void
+                } else {
+                    Box::into_raw(Box::new(self)) as *mut c_void
+                }
+            }
+
+            unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+                if cfg!(target_pointer_width = "64") {
+                    ptr as usize as $ty
+                } else {
+                    *unsafe { Box::from_raw(ptr as *mut $ty) }
+                }
+            }
+
+            boxed_option!($ty);
+        }
+    )*};
+}
+
+impl_wide_scalar!(i64, u64);
+
+macro_rules! impl_scalar_vec {
+    ($($ty:ty),*) => {$(
+        /// A boxed `XrossBuffer` of the elements.
+        impl XrossPtr for Vec<$ty> {
+            fn into_xross_ptr(self) -> *mut c_void {
+                XrossBuffer::from(self).into_xross_ptr()
+            }
+
+            unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+                unsafe { XrossBuffer::from_xross_ptr(ptr).into_vec() }
+            }
+        }
+    )*};
+}
+
+impl_scalar_vec!(bool, i8, u8, i16, u16, i32, u32, i64, u64, isize, usize, f32, f64);
+
+macro_rules! impl_float {
+    ($($ty:ty => $bits:ty),*) => {$(
+        /// Floats are bit-cast into the pointer, or boxed when they do not fit.
+        impl XrossPtr for $ty {
+            fn into_xross_ptr(self) -> *mut c_void {
+                self.to_bits().into_xross_ptr()
+            }
+
+            unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+                <$ty>::from_bits(unsafe { <$bits>::from_xross_ptr(ptr) })
+            }
+
+            boxed_option!($ty);
+        }
+    )*};
+}
+
+impl_float!(f32 => u32, f64 => u64);
+
+impl XrossPtr for () {
+    fn into_xross_ptr(self) -> *mut c_void {
+        std::ptr::null_mut()
+    }
+
+    unsafe fn from_xross_ptr(_: *mut c_void) -> Self {}
+}
+
+impl XrossPtr for bool {
+    fn into_xross_ptr(self) -> *mut c_void {
+        self as usize as *mut c_void
+    }
+
+    unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+        !ptr.is_null()
+    }
+
+    boxed_option!(bool);
+}
+
+/// A boxed `XrossBuffer`, also for the `Some` of a returned `Option<String>`.
+impl XrossPtr for String {
+    fn into_xross_ptr(self) -> *mut c_void {
+        XrossString::from(self).into_xross_ptr()
+    }
+
+    unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+        unsafe { XrossString::from_xross_ptr(ptr).into_string() }
+    }
+}
+
+/// A pointer to an `XrossStringView`, the encoding of direct `String` arguments.
+impl XrossArgPtr for String {
+    unsafe fn from_xross_arg(ptr: *mut c_void) -> Self {
+        unsafe { &*(ptr as *const XrossStringView) }.to_string_lossy()
+    }
+}
+
+/// A `Box<T>`, released by the binding through the type's `drop` export.
+impl<T: XrossClass> XrossPtr for T {
+    fn into_xross_ptr(self) -> *mut c_void {
+        Box::into_raw(Box::new(self)) as *mut c_void
+    }
+
+    unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+        *unsafe { Box::from_raw(ptr as *mut T) }
+    }
+}
+
+/// The value is moved out with `ptr::read`, so memory owned by the binding is not freed.
+impl<T: XrossClass> XrossArgPtr for T {
+    unsafe fn from_xross_arg(ptr: *mut c_void) -> Self {
+        unsafe { std::ptr::read(ptr as *const T) }
+    }
+}
+
+/// How an owned object crosses the ABI directly as an argument or return. Implemented by the
+/// macros for every class: a `void*` handle ([`XrossPtr`] / [`XrossArgPtr`]), or the value
+/// itself for by-value structs.
+pub trait XrossOwned: Sized {
+    /// The C type of the argument or return.
+    type Ffi;
+
+    fn into_ffi(self) -> Self::Ffi;
+
+    /// # Safety
+    /// `ffi` must encode a value of this type, as an argument does.
+    unsafe fn from_ffi(ffi: Self::Ffi) -> Self;
+}
+
+/// The pointer of a returned `Option`, so options can complete tasks.
+impl<T: XrossPtr> XrossPtr for Option<T> {
+    fn into_xross_ptr(self) -> *mut c_void {
+        option_into_ptr(self)
+    }
+
+    unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+        unsafe { option_from_ptr(ptr) }
+    }
+}
+
+/// Strings and sequences wrapped for a direct return are boxed again inside an `XrossResult`.
+impl XrossPtr for XrossBuffer {
+    fn into_xross_ptr(self) -> *mut c_void {
+        Box::into_raw(Box::new(self)) as *mut c_void
+    }
+
+    unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+        *unsafe { Box::from_raw(ptr as *mut XrossBuffer) }
+    }
+}
+
+/// Already-encoded objects and options are passed through.
+impl XrossPtr for *mut c_void {
+    fn into_xross_ptr(self) -> *mut c_void {
+        self
+    }
+
+    unsafe fn from_xross_ptr(ptr: *mut c_void) -> Self {
+        ptr
+    }
+}
+
+/// The successful `XrossResult` of a value.
+pub fn ok<T: XrossPtr>(val: T) -> XrossResult {
+    XrossResult { is_ok: true, ptr: val.into_xross_ptr() }
+}
+
+/// An `Err` holding a boxed `XrossBuffer` with `message`.
+pub fn error(message: String) -> XrossResult {
+    XrossResult { is_ok: false, ptr: XrossString::from(message).into_xross_ptr() }
+}
+
+/// The `XrossResult` reporting a caught panic. `fallback` is used when the payload is not a
+/// string.
+pub fn panicked(payload: &(dyn std::any::Any + Send), fallback: &str) -> XrossResult {
+    let message = if payload.is::<&str>() || payload.is::<String>() {
+        crate::xross_panic_message(payload)
+    } else {
+        fallback.to_string()
+    };
+    error(message)
+}
+
+/// The `XrossResult` of a returned `Result`.
+pub fn result<T: XrossPtr, E: XrossPtr>(res: Result<T, E>) -> XrossResult {
+    match res {
+        Ok(val) => ok(val),
+        Err(e) => XrossResult { is_ok: false, ptr: e.into_xross_ptr() },
+    }
+}
+
+/// Decodes an `XrossResult` produced by [`result`], as a binding does.
+/// # Safety
+/// `res` must come from [`result`] with the same types.
+pub unsafe fn result_into<T: XrossPtr, E: XrossPtr>(res: XrossResult) -> Result<T, E> {
+    unsafe {
+        if res.is_ok { Ok(T::from_xross_ptr(res.ptr)) } else { Err(E::from_xross_ptr(res.ptr)) }
+    }
+}
+
+/// The pointer of a directly returned `Option`.
+pub fn option_into_ptr<T: XrossPtr>(opt: Option<T>) -> *mut c_void {
+    opt.map_or(std::ptr::null_mut(), T::into_xross_option_ptr)
+}
+
+/// Decodes the pointer of a directly returned `Option`, as a binding does.
+/// # Safety
+/// `ptr` must come from [`option_into_ptr`] with the same type.
+pub unsafe fn option_from_ptr<T: XrossPtr>(ptr: *mut c_void) -> Option<T> {
+    (!ptr.is_null()).then(|| unsafe { T::from_xross_option_ptr(ptr) })
+}
+
+/// Decodes an `Option` argument.
+/// # Safety
+/// `ptr` must be null or encode a value of `T`.
+pub unsafe fn option_from_arg<T: XrossArgPtr>(ptr: *mut c_void) -> Option<T> {
+    (!ptr.is_null()).then(|| unsafe { T::from_xross_arg(ptr) })
+}
+
+/// Decodes a `Result` argument. The `Err` points to an `XrossStringView`; null reads as
+/// `"Unknown Error"`.
+/// # Safety
+/// `res.ptr` must encode a value of `T` when `is_ok`, and be null or point to an
+/// `XrossStringView` otherwise.
+pub unsafe fn result_from_arg<T: XrossArgPtr>(res: XrossResult) -> Result<T, String> {
+    unsafe {
+        if res.is_ok {
+            Ok(T::from_xross_arg(res.ptr))
+        } else if res.ptr.is_null() {
+            Err("Unknown Error".to_string())
+        } else {
+            Err(String::from_xross_arg(res.ptr))
+        }
+    }
+}
+
+/// The `XrossBuffer` of a returned slice or `Vec` of scalars. The elements are copied.
+pub fn buffer_from_slice<T: Clone>(items: &[T]) -> XrossBuffer {
+    XrossBuffer::from(items.to_vec())
+}
+
+/// The `XrossBuffer` of a returned slice or `Vec` of objects: one owning pointer per
+/// element, each released through the type's `drop` export.
+pub fn buffer_from_objects<T: XrossClass + Clone>(items: &[T]) -> XrossBuffer {
+    let ptrs: Vec<*mut c_void> = items.iter().map(|item| item.clone().into_xross_ptr()).collect();
+    XrossBuffer::from(ptrs)
+}
+
+/// The `XrossBuffer` of a `contiguous` return: the elements themselves, `size_of::<T>()`
+/// apart, released as a whole through the type's `drop_vec` export. A `Vec` is handed out
+/// as is; a slice is cloned.
+pub fn buffer_from_contiguous<T: XrossClass>(items: impl Into<Vec<T>>) -> XrossBuffer {
+    XrossBuffer::from(items.into())
+}
@@ -1,5 +1,9 @@
+pub mod abi;
+
 use std::ffi::c_void;
 
+#[doc(hidden)]
+pub use xross_macros::{__xross_definition, __xross_methods_impl};
 pub use xross_macros::{
     XrossClass, xross_class, xross_function, xross_function_dsl, xross_methods, xross_raw_function,
     xross_raw_method,
@@ -22,6 +26,8 @@ unsafe impl Send for XrossResult {}
 unsafe impl Sync for XrossResult {}
 
 /// Represent a Rust Buffer (Vec<u8> or similar) passed to the JVM.
+/// `cap` and `len` count elements. Release it with the function of its element type,
+/// see `XrossType::buffer_release_symbol`.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct XrossBuffer {
@@ -99,16 +105,129 @@ impl XrossStringView {
     }
 }
 
+/// Cooperative cancellation flag shared with the caller.
+/// The caller owns the flag and may set it from another thread; Rust code polls `is_cancelled()`.
+/// A token built from a null pointer is never cancelled.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy)]
+pub struct XrossCancelToken(*const std::sync::atomic::AtomicBool);
+
+unsafe impl Send for XrossCancelToken {}
+unsafe impl Sync for XrossCancelToken {}
+
+impl XrossCancelToken {
+    /// # Safety
+    /// `flag` must be null or stay valid for as long as the token is used.
+    pub unsafe fn from_raw(flag: *const std::sync::atomic::AtomicBool) -> Self {
+        Self(flag)
+    }
+
+    /// A token that is never cancelled.
+    pub const fn none() -> Self {
+        Self(std::ptr::null())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        !self.0.is_null() && unsafe { &*self.0 }.load(std::sync::atomic::Ordering::Acquire)
+    }
+}
+
+impl Default for XrossCancelToken {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// Handle to a running `async` or `blocking` call.
+/// `poll_fn` never blocks; it writes the result to `out` and returns `XrossPoll::Ready` once,
+/// and returns `XrossPoll::Pending` before that. `drop_fn` must be called exactly once; it
+/// cancels a call that has not started and, for `blocking` calls, waits for a running one.
 #[repr(C)]
 pub struct XrossTask {
     pub task_ptr: *mut c_void,
-    pub poll_fn: unsafe extern "C" fn(*mut c_void) -> XrossResult,
+    pub poll_fn: unsafe extern "C" fn(*mut c_void, *mut XrossResult) -> XrossPoll,
     pub drop_fn: unsafe extern "C" fn(*mut c_void),
 }
 
 unsafe impl Send for XrossTask {}
 unsafe impl Sync for XrossTask {}
 
+/// State reported by `XrossTask::poll_fn` and `XrossStream::poll_next_fn`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XrossPoll {
+    /// No item is available yet. Poll again later.
+    Pending = 0,
+    /// An item was written to the `out` result.
+    Ready = 1,
+    /// The stream is exhausted. No item was written. Tasks never report it.
+    Done = 2,
+}
+
+/// Owned handle to a synchronous sequence (`impl Iterator<Item = T>`).
+/// `next_fn` writes the next item to `out` and returns false once the iterator is exhausted.
+/// `close_fn` must be called exactly once to release the iterator.
+#[repr(C)]
+pub struct XrossIterator {
+    pub iter_ptr: *mut c_void,
+    pub next_fn: unsafe extern "C" fn(*mut c_void, *mut XrossResult) -> bool,
+    pub close_fn: unsafe extern "C" fn(*mut c_void),
+}
+
+unsafe impl Send for XrossIterator {}
+unsafe impl Sync for XrossIterator {}
+
+impl XrossIterator {
+    /// Wraps an iterator into an owned handle. Each item is converted with `mapper` when pulled.
+    /// The handle outlives the call and may be pulled from any thread, so the iterator must own
+    /// its data and be `Send`.
+    pub fn new<I, T>(iter: I, mapper: fn(T) -> XrossResult) -> Self
+    where
+        I: Iterator<Item = T> + Send + 'static,
+        T: 'static,
+    {
+        type Erased = Box<dyn Iterator<Item = XrossResult> + Send>;
+
+        unsafe extern "C" fn next_iter(ptr: *mut c_void, out: *mut XrossResult) -> bool {
+            let iter = unsafe { &mut *(ptr as *mut Erased) };
+            match iter.next() {
+                Some(res) => {
+                    unsafe { std::ptr::write(out, res) };
+                    true
+                }
+                None => false,
+            }
+        }
+
+        unsafe extern "C" fn close_iter(ptr: *mut c_void) {
+            let _ = unsafe { Box::from_raw(ptr as *mut Erased) };
+        }
+
+        let erased: Erased = Box::new(iter.map(mapper));
+        XrossIterator {
+            iter_ptr: Box::into_raw(Box::new(erased)) as *mut c_void,
+            next_fn: next_iter,
+            close_fn: close_iter,
+        }
+    }
+}
+
+/// Owned handle to an asynchronous sequence (`impl Stream<Item = T>` or `mpsc::Receiver<T>`).
+/// `poll_next_fn` never blocks; it writes an item to `out` only when it returns `XrossPoll::Ready`.
+/// `close_fn` must be called exactly once; it stops the producer and releases the handle.
+#[repr(C)]
+pub struct XrossStream {
+    pub stream_ptr: *mut c_void,
+    pub poll_next_fn: unsafe extern "C" fn(*mut c_void, *mut XrossResult) -> XrossPoll,
+    pub close_fn: unsafe extern "C" fn(*mut c_void),
+}
+
+unsafe impl Send for XrossStream {}
+unsafe impl Sync for XrossStream {}
+
+#[cfg(feature = "tokio")]
+pub use futures::Stream;
+
 #[cfg(feature = "tokio")]
 use std::sync::LazyLock;
 #[cfg(feature = "tokio")]
@@ -124,24 +243,66 @@ pub fn xross_spawn_task<F, T>(future: F, mapper: fn(T) -> XrossResult) -> XrossT
 where
     F: Future<Output = T> + Send + 'static,
     T: Send + 'static,
+{
+    spawn_result_task(async move { mapper(future.await) })
+}
+
+/// Spawns `future`, optionally bounded by `limiter` and aborted after `timeout`.
+/// The timeout covers the whole call, including time spent waiting for a limiter slot.
+/// An expired deadline drops the inner future and yields `XrossTask::timed_out_result`.
+#[cfg(feature = "tokio")]
+pub fn xross_spawn_task_ext<F, T>(
+    future: F,
+    mapper: fn(T) -> XrossResult,
+    limiter: Option<&'static XrossLimiter>,
+    timeout: Option<std::time::Duration>,
+) -> XrossTask
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let slot = match limiter {
+        Some(limiter) => match limiter.try_enter() {
+            Some(slot) => Some(slot),
+            None => return XrossTask::rejected(),
+        },
+        None => None,
+    };
+
+    let call = async move {
+        let _permit = match &slot {
+            Some(slot) => Some(slot.0.semaphore.acquire().await.expect("limiter semaphore closed")),
+            None => None,
+        };
+        mapper(future.await)
+    };
+
+    match timeout {
+        Some(timeout) => spawn_result_task(async move {
+            match tokio::time::timeout(timeout, call).await {
+                Ok(res) => res,
+                Err(_) => XrossTask::timed_out_result(timeout),
+            }
+        }),
+        None => spawn_result_task(call),
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn spawn_result_task<F>(future: F) -> XrossTask
+where
+    F: Future<Output = XrossResult> + Send + 'static,
 {
     let rt = &RUNTIME;
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
     rt.spawn(async move {
-        let res = future.await;
-        let _ = tx.send(mapper(res));
+        let _ = tx.send(future.await);
     });
 
-    unsafe extern "C" fn poll_task(ptr: *mut c_void) -> XrossResult {
+    unsafe extern "C" fn poll_task(ptr: *mut c_void, out: *mut XrossResult) -> XrossPoll {
         let rx = unsafe { &mut *(ptr as *mut tokio::sync::mpsc::UnboundedReceiver<XrossResult>) };
-        match rx.try_recv() {
-            Ok(res) => res,
-            Err(tokio::sync::mpsc::error::TryRecvError::Empty) => {
-                XrossResult { is_ok: true, ptr: std::ptr::null_mut() }
-            }
-            Err(_) => XrossResult { is_ok: false, ptr: std::ptr::null_mut() },
-        }
+        unsafe { poll_task_result(rx, out) }
     }
 
     unsafe extern "C" fn drop_task(ptr: *mut c_void) {
@@ -156,18 +317,539 @@ where
     }
 }
 
+/// Writes the result of a task to `out` once it has been sent. A task whose sender is gone
+/// without a result (its future panicked) completes with an error.
+/// # Safety
+/// `out` must be valid for writes.
+#[cfg(feature = "tokio")]
+unsafe fn poll_task_result(
+    rx: &mut tokio::sync::mpsc::UnboundedReceiver<XrossResult>,
+    out: *mut XrossResult,
+) -> XrossPoll {
+    let res = match rx.try_recv() {
+        Ok(res) => res,
+        Err(tokio::sync::mpsc::error::TryRecvError::Empty) => return XrossPoll::Pending,
+        Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
+            abi::error("xross: task ended without a result".to_string())
+        }
+    };
+    unsafe { std::ptr::write(out, res) };
+    XrossPoll::Ready
+}
+
+/// Bounds the number of in-flight calls of one async export.
+/// Up to `max` calls run at once and up to `queue` more wait for a slot (`None` means unbounded).
+#[cfg(feature = "tokio")]
+pub struct XrossLimiter {
+    semaphore: tokio::sync::Semaphore,
+    in_flight: std::sync::atomic::AtomicUsize,
+    max: usize,
+    queue: Option<usize>,
+}
+
+#[cfg(feature = "tokio")]
+impl XrossLimiter {
+    pub const fn new(max: usize, queue: Option<usize>) -> Self {
+        Self {
+            semaphore: tokio::sync::Semaphore::const_new(max),
+            in_flight: std::sync::atomic::AtomicUsize::new(0),
+            max,
+            queue,
+        }
+    }
+
+    /// Number of calls currently running or waiting for a slot.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    fn try_enter(&'static self) -> Option<XrossLimiterSlot> {
+        use std::sync::atomic::Ordering;
+        match self.queue {
+            Some(queue) => {
+                let capacity = self.max + queue;
+                self.in_flight
+                    .try_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                        (n < capacity).then_some(n + 1)
+                    })
+                    .ok()?;
+            }
+            None => {
+                self.in_flight.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+        Some(XrossLimiterSlot(self))
+    }
+}
+
+#[cfg(feature = "tokio")]
+struct XrossLimiterSlot(&'static XrossLimiter);
+
+#[cfg(feature = "tokio")]
+impl Drop for XrossLimiterSlot {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, std::sync::atomic::Ordering::AcqRel);
+    }
+}
+
+impl XrossTask {
+    /// Task returned when a limited export is saturated. Nothing was spawned.
+    /// `task_ptr` is null so callers can tell it apart from a running call,
+    /// and polling it yields `XrossResult { is_ok: false, ptr: Box<XrossString> }`.
+    pub fn rejected() -> Self {
+        unsafe extern "C" fn poll_rejected(_: *mut c_void, out: *mut XrossResult) -> XrossPoll {
+            let res = abi::error("xross: call rejected, export is busy".to_string());
+            unsafe { std::ptr::write(out, res) };
+            XrossPoll::Ready
+        }
+
+        unsafe extern "C" fn drop_rejected(_: *mut c_void) {}
+
+        XrossTask { task_ptr: std::ptr::null_mut(), poll_fn: poll_rejected, drop_fn: drop_rejected }
+    }
+
+    /// Error result produced when a call exceeds its deadline.
+    /// `ptr` holds a `Box<XrossString>` starting with `xross: timed out`.
+    pub fn timed_out_result(timeout: std::time::Duration) -> XrossResult {
+        abi::error(format!("xross: timed out after {} ms", timeout.as_millis()))
+    }
+
+    /// Whether this task was rejected by a concurrency limit.
+    pub fn is_rejected(&self) -> bool {
+        self.task_ptr.is_null()
+    }
+}
+
+/// Like `xross_spawn_task`, but bounded by `limiter`.
+/// Returns `XrossTask::rejected()` instead of spawning when the limiter's queue is full.
+#[cfg(feature = "tokio")]
+pub fn xross_spawn_limited_task<F, T>(
+    limiter: &'static XrossLimiter,
+    future: F,
+    mapper: fn(T) -> XrossResult,
+) -> XrossTask
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    xross_spawn_task_ext(future, mapper, Some(limiter), None)
+}
+
+/// Moves a call that borrows JVM-owned memory onto a worker thread.
+#[doc(hidden)]
+pub struct XrossSendJob<F>(F);
+
+unsafe impl<F> Send for XrossSendJob<F> {}
+
+impl<F: FnOnce() -> T, T> XrossSendJob<F> {
+    /// # Safety
+    /// Everything captured by `f` must stay valid and be safe to use from another thread
+    /// until the job has run. Generated code runs it through `xross_spawn_blocking_task`, whose
+    /// `drop_fn` does not return while the job runs, and the bindings hold the receiver and its
+    /// locks until `drop_fn` returns.
+    pub unsafe fn new(f: F) -> Self {
+        Self(f)
+    }
+
+    pub fn run(self) -> T {
+        (self.0)()
+    }
+}
+
+/// Extracts the message of a caught panic payload.
+pub fn xross_panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+/// Runs a synchronous function on the blocking worker pool and exposes it as an `XrossTask`.
+/// A panic is reported as `XrossResult { is_ok: false, ptr: Box<XrossString> }`.
+/// Dropping the task before the worker picks it up cancels the call; dropping it once the
+/// worker has started blocks until the call returns, so whatever it borrows from the caller
+/// stays valid and locked for as long as it runs.
+#[cfg(feature = "tokio")]
+pub fn xross_spawn_blocking_task<F, T>(f: F, mapper: fn(T) -> XrossResult) -> XrossTask
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    xross_spawn_blocking_task_ext(f, mapper, None)
+}
+
+/// Like [`xross_spawn_blocking_task`], giving up after `timeout`. The timeout is checked when a
+/// worker picks the call up: a call still queued by then is not run and yields
+/// `XrossTask::timed_out_result`. A call that has started runs to completion.
+#[cfg(feature = "tokio")]
+pub fn xross_spawn_blocking_task_ext<F, T>(
+    f: F,
+    mapper: fn(T) -> XrossResult,
+    timeout: Option<std::time::Duration>,
+) -> XrossTask
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    use std::sync::{Arc, Condvar, Mutex};
+
+    #[derive(PartialEq)]
+    enum Phase {
+        Queued,
+        Running,
+        Finished,
+        Cancelled,
+    }
+
+    struct Progress {
+        phase: Mutex<Phase>,
+        finished: Condvar,
+    }
+
+    struct BlockingTask {
+        rx: tokio::sync::mpsc::UnboundedReceiver<XrossResult>,
+        progress: Arc<Progress>,
+    }
+
+    let rt = &RUNTIME;
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let progress =
+        Arc::new(Progress { phase: Mutex::new(Phase::Queued), finished: Condvar::new() });
+    let worker = progress.clone();
+    let queued_at = std::time::Instant::now();
+
+    rt.spawn_blocking(move || {
+        {
+            let mut phase = worker.phase.lock().unwrap();
+            if *phase == Phase::Cancelled {
+                return;
+            }
+            if let Some(timeout) = timeout.filter(|t| queued_at.elapsed() >= *t) {
+                let _ = tx.send(XrossTask::timed_out_result(timeout));
+                *phase = Phase::Finished;
+                return;
+            }
+            *phase = Phase::Running;
+        }
+        let res = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+            Ok(val) => mapper(val),
+            Err(payload) => abi::error(xross_panic_message(&*payload)),
+        };
+        let _ = tx.send(res);
+        *worker.phase.lock().unwrap() = Phase::Finished;
+        worker.finished.notify_all();
+    });
+
+    unsafe extern "C" fn poll_task(ptr: *mut c_void, out: *mut XrossResult) -> XrossPoll {
+        let task = unsafe { &mut *(ptr as *mut BlockingTask) };
+        unsafe { poll_task_result(&mut task.rx, out) }
+    }
+
+    unsafe extern "C" fn drop_task(ptr: *mut c_void) {
+        let task = unsafe { Box::from_raw(ptr as *mut BlockingTask) };
+        let mut phase = task.progress.phase.lock().unwrap();
+        match *phase {
+            Phase::Queued => *phase = Phase::Cancelled,
+            Phase::Running => {
+                let _unused = task.progress.finished.wait_while(phase, |p| *p == Phase::Running);
+            }
+            Phase::Finished | Phase::Cancelled => {}
+        }
+    }
+
+    XrossTask {
+        task_ptr: Box::into_raw(Box::new(BlockingTask { rx, progress })) as *mut c_void,
+        poll_fn: poll_task,
+        drop_fn: drop_task,
+    }
+}
+
+/// Number of items a stream producer may run ahead of the consumer.
+/// The producer waits once this many items are mapped but not yet pulled.
+#[cfg(feature = "tokio")]
+pub const XROSS_STREAM_BUFFER: usize = 1;
+
+/// Drives `future` to obtain a stream, then forwards every item through `mapper`.
+/// The producer runs at most [`XROSS_STREAM_BUFFER`] items ahead of `poll_next_fn`
+/// and stops as soon as the returned handle is closed.
+#[cfg(feature = "tokio")]
+pub fn xross_spawn_stream<F, S, T>(future: F, mapper: fn(T) -> XrossResult) -> XrossStream
+where
+    F: Future<Output = S> + Send + 'static,
+    S: Stream<Item = T> + Send + 'static,
+    T: Send + 'static,
+{
+    use futures::StreamExt;
+
+    let rt = &RUNTIME;
+    let (tx, rx) = tokio::sync::mpsc::channel(XROSS_STREAM_BUFFER);
+
+    rt.spawn(async move {
+        let forward = async {
+            let stream = future.await;
+            futures::pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                if tx.send(mapper(item)).await.is_err() {
+                    break;
+                }
+            }
+        };
+        tokio::select! {
+            _ = forward => {}
+            _ = tx.closed() => {}
+        }
+    });
+
+    unsafe extern "C" fn poll_next(ptr: *mut c_void, out: *mut XrossResult) -> XrossPoll {
+        let rx = unsafe { &mut *(ptr as *mut tokio::sync::mpsc::Receiver<XrossResult>) };
+        match rx.try_recv() {
+            Ok(res) => {
+                unsafe { std::ptr::write(out, res) };
+                XrossPoll::Ready
+            }
+            Err(tokio::sync::mpsc::error::TryRecvError::Empty) => XrossPoll::Pending,
+            Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => XrossPoll::Done,
+        }
+    }
+
+    unsafe extern "C" fn close_stream(ptr: *mut c_void) {
+        let _ = unsafe { Box::from_raw(ptr as *mut tokio::sync::mpsc::Receiver<XrossResult>) };
+    }
+
+    XrossStream {
+        stream_ptr: Box::into_raw(Box::new(rx)) as *mut c_void,
+        poll_next_fn: poll_next,
+        close_fn: close_stream,
+    }
+}
+
+/// Adapts a bounded tokio receiver into a `Stream` so it can be exported with `xross_spawn_stream`.
+#[cfg(feature = "tokio")]
+pub fn xross_receiver_stream<T>(mut rx: tokio::sync::mpsc::Receiver<T>) -> impl Stream<Item = T> {
+    futures::stream::poll_fn(move |cx| rx.poll_recv(cx))
+}
+
+/// Implemented by `#[derive(XrossClass)]` and `xross_class!` for every bridged type.
+/// Lets other macros and the embedded metadata refer to a type through the type system
+/// instead of looking it up by name.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a xross class",
+    label = "used as a class in a xross export",
+    note = "derive `XrossClass` for it, or use the underlying type if this is a type alias"
+)]
 pub trait XrossClass {
+    /// Full signature (`package.Name`, or `Name` without a package).
+    const SIGNATURE: &'static str;
+    /// Prefix of every exported symbol of this type.
+    const SYMBOL_PREFIX: &'static str;
+    /// Package the type is declared in. Empty for the root package.
+    const PACKAGE: &'static str;
+    /// Crate that defines the type, with `-` replaced by `_`.
+    const CRATE: &'static str;
+    /// `XrossDefinition::layout_fingerprint` of the type, exported as
+    /// `{prefix}_layout_fingerprint` so bindings can check they match the library.
+    const LAYOUT_FINGERPRINT: u64;
+
     fn xross_layout() -> String;
+    /// Structured layout of the type, exported as JSON by `{prefix}_layout_descriptor`
+    /// and embedded in the metadata bundle.
+    fn xross_layout_descriptor() -> XrossLayout;
+}
+
+#[doc(hidden)]
+pub use linkme;
+pub use xross_metadata::{XrossFieldLayout, XrossLayout, XrossTagLayout, XrossVariantLayout};
+
+/// A definition embedded by the xross macros.
+#[doc(hidden)]
+pub struct XrossMetadataFragment {
+    /// JSON-encoded `XrossDefinition`.
+    pub json: &'static str,
+    /// Bare type names used in `json`, paired with a lookup of their `XrossClass` constants.
+    pub type_refs: &'static [XrossTypeRef],
+    /// Layout of the class declaring the definition. `None` for methods and functions.
+    pub layout: Option<fn() -> XrossLayout>,
+    /// Package name of the crate that declared the definition.
+    pub crate_name: &'static str,
+    /// Package version of the crate that declared the definition.
+    pub crate_version: &'static str,
+}
+
+#[doc(hidden)]
+pub type XrossTypeRef = (&'static str, fn() -> XrossClassInfo);
+
+/// The `XrossClass` constants of a type, as recorded in `XrossType::Object`.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct XrossClassInfo {
+    pub signature: &'static str,
+    pub symbol_prefix: &'static str,
+    pub crate_name: &'static str,
+}
+
+impl XrossClassInfo {
+    pub const fn of<T: XrossClass>() -> Self {
+        Self { signature: T::SIGNATURE, symbol_prefix: T::SYMBOL_PREFIX, crate_name: T::CRATE }
+    }
+}
+
+/// Definitions embedded by the xross macros.
+/// A type may appear several times (e.g. once from its derive and once from `#[xross_methods]`).
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static XROSS_METADATA: [XrossMetadataFragment];
+
+/// Returns every definition embedded in this binary, merged by signature.
+/// Type references are resolved through `XrossClass`, which also records the owning crate and
+/// symbol prefix, so classes of dependency crates resolve the same way as local ones.
+pub fn xross_embedded_definitions() -> Vec<xross_metadata::XrossDefinition> {
+    xross_embedded_metadata_set().into_definitions()
+}
+
+/// Returns the embedded definitions as a `XrossMetadataSet`, e.g. to validate them.
+pub fn xross_embedded_metadata_set() -> xross_metadata::XrossMetadataSet {
+    let mut set = xross_metadata::XrossMetadataSet::new();
+    for fragment in XROSS_METADATA {
+        let mut def: xross_metadata::XrossDefinition =
+            serde_json::from_str(fragment.json).expect("corrupt embedded xross metadata");
+        def.visit_types_mut(&mut |ty| {
+            if let xross_metadata::XrossType::Object {
+                signature, crate_name, symbol_prefix, ..
+            } = ty
+                && let Some((_, lookup)) = fragment.type_refs.iter().find(|(n, _)| n == signature)
+            {
+                let info = lookup();
+                *signature = info.signature.to_string();
+                *crate_name = Some(info.crate_name.to_string());
+                *symbol_prefix = Some(info.symbol_prefix.to_string());
+            }
+        });
+        if let (Some(layout), Some(slot)) = (fragment.layout, def.layout_mut()) {
+            *slot = Some(layout());
+        }
+        // Signatures are checked for conflicts at compile time; a mismatch keeps the first.
+        let _ = set.insert(def);
+    }
+    set
+}
+
+/// Returns the embedded definitions together with the crates they come from and the target
+/// this library was compiled for.
+pub fn xross_embedded_bundle() -> xross_metadata::XrossMetadataBundle {
+    let crates: std::collections::BTreeSet<xross_metadata::XrossCrateInfo> = XROSS_METADATA
+        .iter()
+        .map(|f| xross_metadata::XrossCrateInfo {
+            name: f.crate_name.to_string(),
+            version: f.crate_version.to_string(),
+        })
+        .collect();
+    xross_metadata::XrossMetadataBundle::new(
+        crates.into_iter().collect(),
+        xross_target(),
+        xross_embedded_definitions(),
+    )
+}
+
+/// Returns the target this library was compiled for.
+pub fn xross_target() -> xross_metadata::XrossTarget {
+    xross_metadata::XrossTarget {
+        triple: env!("XROSS_TARGET").to_string(),
+        pointer_width: usize::BITS,
+        endian: if cfg!(target_endian = "little") {
+            xross_metadata::XrossEndian::Little
+        } else {
+            xross_metadata::XrossEndian::Big
+        },
+    }
+}
+
+/// Writes the embedded metadata bundle (`XrossMetadataBundle` as JSON) to `out`.
+/// The string must be released with `xross_free_buffer`.
+/// # Safety
+/// `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xross_metadata(out: *mut XrossString) {
+    let json = xross_embedded_bundle().to_json();
+    unsafe { std::ptr::write(out, XrossString::from(json)) };
+}
+
+/// Version of the calling convention between the generated exports and bindings.
+/// Bumped whenever bindings generated for an older version would call the library incorrectly.
+pub const XROSS_ABI_VERSION: u32 = 6;
+
+/// Returns `XROSS_ABI_VERSION`, so bindings can refuse a library built with another ABI.
+#[unsafe(no_mangle)]
+pub extern "C" fn xross_abi_version() -> u32 {
+    XROSS_ABI_VERSION
+}
+
+/// Returns `XrossMetadataSet::metadata_hash` of the embedded definitions.
+/// Tools compare it with the hash of the metadata bindings were generated from.
+#[unsafe(no_mangle)]
+pub extern "C" fn xross_metadata_hash() -> u64 {
+    static HASH: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    *HASH.get_or_init(|| xross_embedded_metadata_set().metadata_hash())
+}
+
+/// Serializes a layout for `{prefix}_layout_descriptor`.
+#[doc(hidden)]
+pub fn xross_layout_json(layout: &XrossLayout) -> XrossString {
+    XrossString::from(serde_json::to_string(layout).expect("Failed to serialize xross layout"))
 }
 
-/// Frees an array (or string) allocated by Rust that was passed to the JVM.
-/// ptr, len, and cap must correspond to a Vec<u8> or String.
+/// Frees a `String` or a `Vec` of 1-byte elements (`u8`, `i8`, `bool`) returned by Rust.
+/// Wider elements must be released with the function named by
+/// `XrossType::buffer_release_symbol`, which frees the allocation with the element's layout.
 /// # Safety
-/// Please call from generated funcs
-/// Do not call from your funcs
+/// `xb` must be such a buffer, returned by this library and not released before.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn xross_free_buffer(xb: XrossBuffer) {
     if !xb.ptr.is_null() && xb.cap > 0 {
-        drop(unsafe { Vec::from_raw_parts(xb.ptr as *mut u8, xb.len, xb.cap) });
+        drop(unsafe { xb.into_vec::<u8>() });
+    }
+}
+
+macro_rules! typed_free_buffer {
+    ($($name:ident => $elem:ty, $what:literal;)*) => {$(
+        #[doc = concat!("Frees a `Vec` of ", $what, " returned by Rust.")]
+        /// # Safety
+        /// `xb` must be such a buffer, returned by this library and not released before.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn $name(xb: XrossBuffer) {
+            if !xb.ptr.is_null() && xb.cap > 0 {
+                drop(unsafe { xb.into_vec::<$elem>() });
+            }
+        }
+    )*};
+}
+
+typed_free_buffer! {
+    xross_free_buffer_16 => u16, "`i16` or `u16`";
+    xross_free_buffer_32 => u32, "`i32`, `u32` or `f32`";
+    xross_free_buffer_64 => u64, "`i64`, `u64` or `f64`";
+    xross_free_buffer_ptr => usize, "`isize`, `usize`, pointers or object handles";
+}
+
+/// Frees the boxed scalar of a returned `Some` (see `abi`). `size` is the scalar's size in
+/// bytes, so the box is freed with the layout it was made with.
+/// # Safety
+/// `ptr` must be such a box of a `size`-byte scalar, returned by this library and not
+/// released before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xross_free_scalar(ptr: *mut c_void, size: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe {
+        match size {
+            1 => drop(Box::from_raw(ptr as *mut u8)),
+            2 => drop(Box::from_raw(ptr as *mut u16)),
+            4 => drop(Box::from_raw(ptr as *mut u32)),
+            8 => drop(Box::from_raw(ptr as *mut u64)),
+            _ => {}
+        }
     }
 }
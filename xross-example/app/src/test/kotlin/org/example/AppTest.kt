@@ -4,6 +4,15 @@ import org.example.complex.ComplexStruct
 import org.example.external.ExternalStruct
 import org.example.standalone.*
 import org.example.test.test2.MyService2
+import kotlinx.coroutines.Dispatchers
+import kotlinx.coroutines.async
+import kotlinx.coroutines.awaitAll
+import kotlinx.coroutines.cancelAndJoin
+import kotlinx.coroutines.delay
+import kotlinx.coroutines.launch
+import kotlinx.coroutines.runBlocking
+import kotlinx.coroutines.withTimeout
+import org.example.xross.runtime.XrossRejectedException
 import kotlin.test.*
 
 class AppTest {
@@ -108,6 +117,42 @@ class AppTest {
         assertEquals(20, GlobalMultiply.globalMultiply(4, 5))
     }
 
+    @Test
+    fun testCancelTokenFollowsInterrupt() {
+        assertEquals(25L, CountPrimes.countPrimes(100L))
+        Thread.currentThread().interrupt()
+        try {
+            // The flag is already set, so the export stops at its first check (n = 1024)
+            assertEquals(172L, CountPrimes.countPrimes(50_000_000L))
+        } finally {
+            Thread.interrupted()
+        }
+    }
+
+    @Test
+    fun testBlockingCancelTokenFollowsCancellation() = runBlocking {
+        val job = launch(Dispatchers.Default) { CountPrimesBlocking.countPrimesBlocking(50_000_000L) }
+        delay(100)
+        // Cancelling waits for the running job, which stops at its next check of the token
+        withTimeout(5_000) { job.cancelAndJoin() }
+    }
+
+    @Test
+    fun testBusyExportIsRejected() = runBlocking {
+        // Two calls run and one waits; the others find the export busy.
+        val results = (1..5).map { id ->
+            async(Dispatchers.Default) {
+                try {
+                    ThrottledLookup.throttledLookup(id)
+                } catch (e: XrossRejectedException) {
+                    null
+                }
+            }
+        }.awaitAll()
+        assertTrue(results.contains(null), "expected a rejected call in $results")
+        results.withIndex().filter { it.value != null }.forEach { assertEquals((it.index + 1) * 10, it.value) }
+    }
+
     @Test
     fun testPanicHandling() {
         val service = MyService()
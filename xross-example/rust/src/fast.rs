@@ -2,6 +2,7 @@ use xross_core::{XrossClass, xross_methods};
 
 #[derive(XrossClass, Clone, Copy, Debug)]
 #[xross_package("fast")]
+#[xross(copy)]
 #[repr(C)]
 pub struct Point {
     #[xross_field]
@@ -32,6 +33,24 @@ impl FastStruct {
         s.chars().count() as i32
     }
 
+    #[xross_method]
+    #[cfg(feature = "experimental")]
+    pub fn scaled_data(&self, factor: i32) -> i32 {
+        self.data * factor
+    }
+
+    #[xross_method]
+    #[cfg(not(feature = "experimental"))]
+    pub fn origin(&self) -> Point {
+        Point { x: self.data, y: 0 }
+    }
+
+    /// `Point` is `#[repr(C)]` and `Copy`, so it is passed and returned in registers.
+    #[xross_method]
+    pub fn offset(&self, p: Point) -> Point {
+        Point { x: p.x + self.data, y: p.y + self.data }
+    }
+
     #[xross_raw_method {
         sig = (a: i32, b: i32) -> i32;
         import = |a, b| { (a, b) };
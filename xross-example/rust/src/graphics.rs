@@ -587,6 +587,12 @@ impl Path2D {
         self.buffer = output_buffer;
     }
 
+    /// Every point of every segment in drawing order, returned as one allocation.
+    #[xross_method(contiguous)]
+    pub fn points(&self) -> Vec<PointData> {
+        self.segments.iter().flat_map(|s| s.points.iter().copied()).collect()
+    }
+
     #[xross_method(critical)]
     pub fn get_buffer_ptr(&self) -> *const f32 {
         self.buffer.as_ptr()
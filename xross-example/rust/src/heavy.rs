@@ -154,4 +154,10 @@ impl AdvancedResult {
             max_range_dist: m_d,
         }
     }
+
+    /// Horizontal distance reached when firing at `low_pitch`, solved on the blocking pool.
+    #[xross_method(blocking)]
+    pub fn simulate_low_arc(&self, power: f32, drag: f32, grav: f32, max_s: i32) -> f32 {
+        simulate_max_dist(power, self.low_pitch, self.target_pos_y, max_s, drag, grav)
+    }
 }
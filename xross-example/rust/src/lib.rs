@@ -49,4 +49,450 @@ mod tests {
         assert_eq!(ext.value, 20);
         assert_eq!(ext.greet("Hi".to_string()), "Hi Test");
     }
+
+    #[test]
+    fn test_iterator_handle() {
+        let mut handle = std::mem::MaybeUninit::<xross_core::XrossIterator>::uninit();
+        let handle = unsafe {
+            xross_example_standalone_count_up(handle.as_mut_ptr(), 5, 3);
+            handle.assume_init()
+        };
+        let mut items = Vec::new();
+        let mut out = std::mem::MaybeUninit::<xross_core::XrossResult>::uninit();
+        while unsafe { (handle.next_fn)(handle.iter_ptr, out.as_mut_ptr()) } {
+            let res = unsafe { out.assume_init_read() };
+            assert!(res.is_ok);
+            items.push(res.ptr as usize as i32);
+        }
+        unsafe { (handle.close_fn)(handle.iter_ptr) };
+        assert_eq!(items, vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn test_stream_backpressure() {
+        use futures::StreamExt;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = produced.clone();
+        let items = futures::stream::iter(0..100_000).inspect(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        let stream =
+            xross_core::xross_spawn_stream(async move { items }, xross_core::abi::ok::<i32>);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        // Unpulled items are not buffered beyond the channel capacity.
+        assert!(produced.load(Ordering::Relaxed) <= xross_core::XROSS_STREAM_BUFFER + 2);
+
+        let mut out = std::mem::MaybeUninit::<xross_core::XrossResult>::uninit();
+        let mut pulled = 0;
+        while pulled < 10 {
+            match unsafe { (stream.poll_next_fn)(stream.stream_ptr, out.as_mut_ptr()) } {
+                xross_core::XrossPoll::Ready => pulled += 1,
+                xross_core::XrossPoll::Pending => std::thread::yield_now(),
+                xross_core::XrossPoll::Done => panic!("stream ended early"),
+            }
+        }
+        unsafe { (stream.close_fn)(stream.stream_ptr) };
+        assert!(produced.load(Ordering::Relaxed) < 100);
+    }
+
+    /// Polls `task` until it is ready.
+    unsafe fn wait_task(task: &xross_core::XrossTask) -> xross_core::XrossResult {
+        let mut res = std::mem::MaybeUninit::uninit();
+        while unsafe { (task.poll_fn)(task.task_ptr, res.as_mut_ptr()) }
+            != xross_core::XrossPoll::Ready
+        {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        unsafe { res.assume_init() }
+    }
+
+    #[test]
+    fn test_blocking_task() {
+        let mut task = std::mem::MaybeUninit::<xross_core::XrossTask>::uninit();
+        let task = unsafe {
+            xross_example_standalone_blocking_prime_factorization(task.as_mut_ptr(), 360);
+            task.assume_init()
+        };
+        let res = unsafe { wait_task(&task) };
+        unsafe { (task.drop_fn)(task.task_ptr) };
+        assert!(res.is_ok);
+        assert_eq!(res.ptr as usize, 6);
+    }
+
+    #[test]
+    fn test_blocking_task_drop_waits() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let (started, finished) =
+            (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let (s, f) = (started.clone(), finished.clone());
+        let task = xross_core::xross_spawn_blocking_task(
+            move || {
+                s.store(true, Ordering::Release);
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                f.store(true, Ordering::Release);
+            },
+            xross_core::abi::ok,
+        );
+        while !started.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+        // Dropping a started call must not return while it still runs.
+        unsafe { (task.drop_fn)(task.task_ptr) };
+        assert!(finished.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_blocking_timeout() {
+        let task = unsafe {
+            let mut task = std::mem::MaybeUninit::<xross_core::XrossTask>::uninit();
+            xross_example_standalone_blocking_square(task.as_mut_ptr(), 7, 0);
+            task.assume_init()
+        };
+        let res = unsafe { wait_task(&task) };
+        unsafe { (task.drop_fn)(task.task_ptr) };
+        assert!(res.is_ok);
+        assert_eq!(res.ptr as usize, 49);
+
+        // An expired timeout is noticed before the worker starts the call.
+        let ran = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = ran.clone();
+        let task = xross_core::xross_spawn_blocking_task_ext(
+            move || flag.store(true, std::sync::atomic::Ordering::Release),
+            xross_core::abi::ok,
+            Some(std::time::Duration::ZERO),
+        );
+        let res = unsafe { wait_task(&task) };
+        unsafe { (task.drop_fn)(task.task_ptr) };
+        assert!(!res.is_ok);
+        let msg = unsafe { Box::from_raw(res.ptr as *mut xross_core::XrossString).into_string() };
+        assert!(msg.starts_with("xross: timed out"));
+        assert!(!ran.load(std::sync::atomic::Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_concurrency_limit_rejects() {
+        let spawn = |id| unsafe {
+            let mut task = std::mem::MaybeUninit::<xross_core::XrossTask>::uninit();
+            xross_example_standalone_throttled_lookup(task.as_mut_ptr(), id);
+            task.assume_init()
+        };
+        let tasks: Vec<_> = (0..4).map(spawn).collect();
+        assert!(tasks[..3].iter().all(|t| !t.is_rejected()));
+        assert!(tasks[3].is_rejected());
+
+        let res = unsafe { wait_task(&tasks[3]) };
+        assert!(!res.is_ok);
+        drop(unsafe { Box::from_raw(res.ptr as *mut xross_core::XrossString) });
+        for task in tasks {
+            unsafe { (task.drop_fn)(task.task_ptr) };
+        }
+    }
+
+    #[test]
+    fn test_timeout() {
+        let wait = |timeout_ms| unsafe {
+            let mut task = std::mem::MaybeUninit::<xross_core::XrossTask>::uninit();
+            xross_example_standalone_delayed_echo(task.as_mut_ptr(), 7, 200, timeout_ms);
+            let task = task.assume_init();
+            let res = wait_task(&task);
+            (task.drop_fn)(task.task_ptr);
+            res
+        };
+
+        let res = wait(0);
+        assert!(!res.is_ok);
+        let msg = unsafe { Box::from_raw(res.ptr as *mut xross_core::XrossString).into_string() };
+        assert!(msg.starts_with("xross: timed out"));
+
+        let res = wait(2_000);
+        assert!(res.is_ok);
+        assert_eq!(res.ptr as usize, 7);
+    }
+
+    #[test]
+    fn test_cancel_token() {
+        let flag = std::sync::atomic::AtomicBool::new(false);
+        assert_eq!(unsafe { xross_example_standalone_count_primes(100, &flag) }, 25);
+        assert_eq!(unsafe { xross_example_standalone_count_primes(100, std::ptr::null()) }, 25);
+
+        flag.store(true, std::sync::atomic::Ordering::Release);
+        assert!(unsafe { xross_example_standalone_count_primes(1_000_000, &flag) } < 200);
+    }
+
+    fn object_signatures(def: &xross_metadata::XrossDefinition) -> Vec<String> {
+        let mut def = def.clone();
+        let mut signatures = Vec::new();
+        def.visit_types_mut(&mut |ty| {
+            if let xross_metadata::XrossType::Object { signature, .. } = ty {
+                signatures.push(signature.clone());
+            }
+        });
+        signatures
+    }
+
+    #[test]
+    fn test_embedded_metadata() {
+        let mut out = std::mem::MaybeUninit::<xross_core::XrossString>::uninit();
+        let json = unsafe {
+            xross_core::xross_metadata(out.as_mut_ptr());
+            out.assume_init().into_string()
+        };
+        assert!(json.contains("\"symbol\":\"xross_example_standalone_count_primes\""));
+
+        let defs = xross_core::xross_embedded_definitions();
+        let service = defs.iter().find(|d| d.name() == "MyService").expect("MyService embedded");
+        let xross_metadata::XrossDefinition::Struct(service) = service else {
+            panic!("MyService should be a struct");
+        };
+        assert!(service.methods.iter().any(|m| m.name == "async_execute"));
+
+        // `FastStruct` refers to `Point` by name; the embedded copy resolves it via `XrossClass`.
+        let fast = defs.iter().find(|d| d.signature() == "fast.FastStruct").unwrap();
+        let signatures = object_signatures(fast);
+        assert!(signatures.iter().any(|s| s == "fast.Point"));
+        assert!(!signatures.iter().any(|s| s == "Point"));
+    }
+
+    #[test]
+    fn test_cfg_gated_metadata() {
+        let defs = xross_core::xross_embedded_definitions();
+        let fast = defs.iter().find(|d| d.signature() == "fast.FastStruct").unwrap();
+        let has_method = |name: &str| fast.methods().iter().any(|m| m.name == name);
+        assert_eq!(has_method("scaled_data"), cfg!(feature = "experimental"));
+        assert_eq!(has_method("origin"), !cfg!(feature = "experimental"));
+        assert!(has_method("count_chars"));
+        assert_eq!(
+            defs.iter().any(|d| d.signature() == "standalone.experimental_mul"),
+            cfg!(feature = "experimental")
+        );
+
+        // The gated method is embedded on its own; its `Point` is still resolved.
+        if let Some(origin) = fast.methods().iter().find(|m| m.name == "origin") {
+            let xross_metadata::XrossType::Object { signature, .. } = &origin.ret else {
+                panic!("origin should return an object");
+            };
+            assert_eq!(signature, "fast.Point");
+        }
+
+        // The metadata directory agrees with the compiled library.
+        let dir =
+            option_env!("XROSS_METADATA_DIR").map(std::path::PathBuf::from).unwrap_or_else(|| {
+                std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("target/xross")
+            });
+        let json =
+            std::fs::read_to_string(dir.join("xross_example/lib/fast.FastStruct.json")).unwrap();
+        assert_eq!(json.contains("\"scaled_data\""), cfg!(feature = "experimental"));
+        assert_eq!(json.contains("\"origin\""), !cfg!(feature = "experimental"));
+        assert!(json.contains("\"count_chars\""));
+    }
+
+    #[test]
+    fn test_exported_symbols() {
+        use xross_metadata::XrossSymbolKind;
+        let set = xross_core::xross_embedded_metadata_set();
+        let symbols = set.exported_symbols();
+        let unique: std::collections::HashSet<&str> =
+            symbols.iter().map(|(_, s)| s.name.as_str()).collect();
+        assert_eq!(unique.len(), symbols.len());
+
+        let kind_of =
+            |name: &str| symbols.iter().find(|(_, s)| s.name == name).map(|(_, s)| s.kind);
+        assert_eq!(
+            kind_of("xross_example_fast_fast_struct_property_name_str_get"),
+            Some(XrossSymbolKind::PropertyGetter)
+        );
+        assert_eq!(
+            kind_of("xross_example_fast_fast_struct_count_chars"),
+            Some(XrossSymbolKind::Method)
+        );
+        assert_eq!(
+            kind_of("xross_example_xross_test_enum_new_B"),
+            Some(XrossSymbolKind::VariantConstructor)
+        );
+        assert_eq!(
+            kind_of("xross_example_xross_test_enum_get_tag"),
+            Some(XrossSymbolKind::Support)
+        );
+        assert_eq!(kind_of("xross_example_standalone_global_add"), Some(XrossSymbolKind::Method));
+    }
+
+    #[test]
+    fn test_c_header() {
+        let set = xross_core::xross_embedded_metadata_set();
+        let header = xross_header::generate_header(&set, "XROSS_EXAMPLE_H");
+        assert!(header.contains("#ifndef XROSS_EXAMPLE_H"));
+        // Every export of the built library is declared.
+        for (_, symbol) in set.exported_symbols() {
+            assert!(header.contains(&symbol.name), "{} is not declared", symbol.name);
+        }
+    }
+
+    #[test]
+    fn test_metadata_bundle() {
+        let mut out = std::mem::MaybeUninit::<xross_core::XrossString>::uninit();
+        let json = unsafe {
+            xross_core::xross_metadata(out.as_mut_ptr());
+            out.assume_init().into_string()
+        };
+        let bundle = xross_metadata::XrossMetadataBundle::from_json(&json).unwrap();
+        assert_eq!(bundle.format_version, xross_metadata::XROSS_METADATA_FORMAT_VERSION);
+        assert!(!bundle.target.triple.is_empty());
+        assert_eq!(bundle.target.pointer_width, usize::BITS);
+        let crates: Vec<&str> = bundle.crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(crates, ["xross-example", "xross-example-shared"]);
+    }
+
+    #[test]
+    fn test_metadata_set() {
+        let set = xross_core::xross_embedded_metadata_set();
+        if let Err(errors) = set.validate() {
+            panic!("{}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"));
+        }
+        assert!(set.by_package("fast").any(|d| d.signature() == "fast.FastStruct"));
+
+        let order: Vec<&str> = set.topological_order().iter().map(|d| d.signature()).collect();
+        let position = |sig: &str| order.iter().position(|s| *s == sig).unwrap();
+        assert!(position("fast.Point") < position("fast.FastStruct"));
+    }
+
+    #[test]
+    fn test_abi_pointer_encoding() {
+        use xross_core::abi::{self, XrossPtr};
+
+        assert_eq!(unsafe { i32::from_xross_ptr((-7i32).into_xross_ptr()) }, -7);
+        assert_eq!(unsafe { f32::from_xross_ptr(1.5f32.into_xross_ptr()) }, 1.5);
+        assert_eq!(unsafe { i64::from_xross_ptr(i64::MIN.into_xross_ptr()) }, i64::MIN);
+        assert_eq!(unsafe { f64::from_xross_ptr((-2.5f64).into_xross_ptr()) }, -2.5);
+        assert_eq!(
+            unsafe { String::from_xross_ptr("héllo".to_string().into_xross_ptr()) },
+            "héllo"
+        );
+        assert_eq!(unsafe { Vec::<u16>::from_xross_ptr(vec![1u16, 2].into_xross_ptr()) }, [1, 2]);
+
+        let ptr = abi::option_into_ptr(Some("text".to_string()));
+        // `Option<String>` returns are boxed buffers, like every other returned string.
+        assert_eq!(unsafe { (*(ptr as *const xross_core::XrossBuffer)).len }, 4);
+        assert_eq!(unsafe { abi::option_from_ptr::<String>(ptr) }.as_deref(), Some("text"));
+        // Scalars are boxed, so a `Some` of zero is not read back as `None`.
+        assert_eq!(unsafe { abi::option_from_ptr::<i32>(abi::option_into_ptr(Some(0))) }, Some(0));
+        assert_eq!(
+            unsafe { abi::option_from_ptr::<bool>(abi::option_into_ptr(Some(false))) },
+            Some(false)
+        );
+        assert_eq!(unsafe { abi::option_from_ptr::<i32>(abi::option_into_ptr::<i32>(None)) }, None);
+
+        let res = abi::result::<fast::Point, String>(Ok(fast::Point { x: 1, y: 2 }));
+        let point = unsafe { abi::result_into::<fast::Point, String>(res) }.unwrap();
+        assert_eq!((point.x, point.y), (1, 2));
+        let res = abi::result::<i32, String>(Err("bad".to_string()));
+        assert_eq!(unsafe { abi::result_into::<i32, String>(res) }, Err("bad".to_string()));
+    }
+
+    #[test]
+    fn test_typed_buffer_release() {
+        use xross_metadata::XrossType;
+
+        // Every returned buffer has a release function for its element width.
+        for def in xross_core::xross_embedded_definitions() {
+            for m in def.methods() {
+                if matches!(m.ret, XrossType::String | XrossType::Vec(_) | XrossType::Slice(_)) {
+                    let symbol = m.ret.buffer_release_symbol();
+                    assert!(symbol.is_some(), "{} has no buffer release", m.symbol);
+                    assert!(xross_metadata::XROSS_RUNTIME_SYMBOLS.contains(&symbol.unwrap()));
+                }
+            }
+        }
+
+        let vec_of = |ty| XrossType::Vec(Box::new(ty));
+        assert_eq!(vec_of(XrossType::F64).buffer_release_symbol(), Some("xross_free_buffer_64"));
+        assert_eq!(vec_of(XrossType::U16).buffer_release_symbol(), Some("xross_free_buffer_16"));
+        assert_eq!(vec_of(XrossType::Bool).buffer_release_symbol(), Some("xross_free_buffer"));
+
+        let doubles = xross_core::XrossBuffer::from(vec![1.0f64, 2.0]);
+        unsafe { xross_core::xross_free_buffer_64(doubles) };
+        let handles = xross_core::XrossBuffer::from(vec![std::ptr::null_mut::<u8>(); 3]);
+        unsafe { xross_core::xross_free_buffer_ptr(handles) };
+    }
+
+    #[test]
+    fn test_abi_handshake() {
+        assert_eq!(xross_core::xross_abi_version(), xross_core::XROSS_ABI_VERSION);
+        let set = xross_core::xross_embedded_metadata_set();
+        assert_eq!(xross_core::xross_metadata_hash(), set.metadata_hash());
+
+        let point = set.get("fast.Point").unwrap();
+        assert_eq!(xross_example_fast_point_layout_fingerprint(), point.layout_fingerprint());
+        assert_eq!(point.layout_text(), "x:I32;y:I32");
+    }
+
+    #[test]
+    fn test_layout_descriptor() {
+        let mut out = std::mem::MaybeUninit::<xross_core::XrossString>::uninit();
+        let json = unsafe {
+            xross_example_fast_point_layout_descriptor(out.as_mut_ptr());
+            out.assume_init().into_string()
+        };
+        let layout: xross_core::XrossLayout = serde_json::from_str(&json).unwrap();
+        assert_eq!((layout.size, layout.align), (8, 4));
+        let y = layout.fields.iter().find(|f| f.name == "y").unwrap();
+        assert_eq!((y.offset, y.size, y.align, y.type_name.as_str()), (4, 4, 4, "i32"));
+
+        // The same layout is embedded in the metadata bundle.
+        let set = xross_core::xross_embedded_metadata_set();
+        assert_eq!(set.get("fast.Point").unwrap().layout(), Some(&layout));
+
+        let simple = set.iter().find(|d| d.name() == "XrossSimpleEnum").unwrap();
+        let simple = simple.layout().unwrap();
+        let discriminants: Vec<Option<i64>> =
+            simple.variants.iter().map(|v| v.discriminant).collect();
+        assert_eq!(discriminants, [Some(0), Some(1), Some(2), Some(3), Some(4)]);
+
+        let test_enum = set.iter().find(|d| d.name() == "XrossTestEnum").unwrap();
+        let b = &test_enum.layout().unwrap().variants[1];
+        assert_eq!((b.name.as_str(), b.fields[0].name.as_str()), ("B", "i"));
+        assert!(b.discriminant.is_none());
+    }
+
+    #[test]
+    fn test_cross_crate_reference() {
+        let defs = xross_core::xross_embedded_definitions();
+        // The dependency's class is embedded into this cdylib alongside its own classes.
+        assert!(defs.iter().any(|d| d.signature() == "shared.Tag"));
+
+        let func = defs.iter().find(|d| d.signature() == "standalone.tag_weight").unwrap();
+        let xross_metadata::XrossDefinition::Function(func) = func else {
+            panic!("tag_weight should be a function");
+        };
+        let xross_metadata::XrossType::Object { signature, crate_name, symbol_prefix, .. } =
+            &func.method.args[0].ty
+        else {
+            panic!("tag should be an object");
+        };
+        assert_eq!(signature, "shared.Tag");
+        assert_eq!(crate_name.as_deref(), Some("xross_example_shared"));
+        assert_eq!(symbol_prefix.as_deref(), Some("xross_example_shared_shared_tag"));
+
+        // `Option<Self>` and `std::primitive::i32` resolve without relying on bare names.
+        let tag_def = defs.iter().find(|d| d.signature() == "shared.Tag").unwrap();
+        let xross_metadata::XrossDefinition::Struct(tag_def) = tag_def else {
+            panic!("Tag should be a struct");
+        };
+        let lighter = tag_def.methods.iter().find(|m| m.name == "lighter").unwrap();
+        assert_eq!(lighter.args[0].ty, xross_metadata::XrossType::I32);
+        let xross_metadata::XrossType::Option(inner) = &lighter.ret else {
+            panic!("lighter should return an Option");
+        };
+        assert!(
+            matches!(&**inner, xross_metadata::XrossType::Object { signature, .. } if signature == "shared.Tag")
+        );
+
+        let tag = unsafe { xross_example_shared::xross_example_shared_shared_tag_new(7) };
+        assert_eq!(unsafe { xross_example_standalone_tag_weight(tag) }, 7);
+        unsafe { xross_example_shared::xross_example_shared_shared_tag_drop(tag.cast()) };
+    }
 }
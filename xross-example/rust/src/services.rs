@@ -87,6 +87,12 @@ impl MyService {
         rand::random_range(x..y + 1) as i32
     }
 
+    #[xross_method]
+    pub fn boxes_page(&self, offset: usize, limit: usize) -> impl Iterator<Item = i32> + 'static {
+        let page: Vec<i32> = self._boxes.iter().skip(offset).take(limit).copied().collect();
+        page.into_iter()
+    }
+
     #[xross_method]
     pub fn get_option_enum(&self, should_some: bool) -> Option<XrossSimpleEnum> {
         if should_some { Some(XrossSimpleEnum::V) } else { None }
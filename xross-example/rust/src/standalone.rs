@@ -1,4 +1,5 @@
-use xross_core::{xross_function, xross_function_dsl};
+use futures::StreamExt;
+use xross_core::{Stream, XrossCancelToken, xross_function, xross_function_dsl};
 
 #[xross_function(package = "standalone")]
 pub async fn async_add(a: i32, b: i32) -> i32 {
@@ -12,6 +13,20 @@ pub async fn async_greet(name: String) -> String {
     format!("Async Hello, {}!", name)
 }
 
+/// At most two lookups run at once; one more may wait, further calls are rejected.
+#[xross_function(package = "standalone", max_concurrency = 2, queue = 1)]
+pub async fn throttled_lookup(id: i32) -> i32 {
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    id * 10
+}
+
+/// Gives up after 50 ms unless the caller passes a longer per-call timeout.
+#[xross_function(package = "standalone", timeout_ms = 50)]
+pub async fn delayed_echo(value: i32, delay_ms: u64) -> i32 {
+    tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+    value
+}
+
 #[xross_function(package = "standalone", critical)]
 pub fn global_add(a: i32, b: i32) -> i32 {
     a + b
@@ -57,6 +72,12 @@ pub fn raw_global_panic(should_panic: u8) -> *mut xross_core::XrossString {
     Box::into_raw(Box::new(xross_core::XrossString::from("Safe".to_string())))
 }
 
+#[xross_function(package = "standalone")]
+#[cfg(feature = "experimental")]
+pub fn experimental_mul(a: i32, b: i32) -> i32 {
+    a * b
+}
+
 #[xross_function(package = "standalone")]
 pub fn test_unsigned(a: u8, b: u32, c: u64) -> u64 {
     (a as u64) + (b as u64) + c
@@ -102,6 +123,38 @@ pub fn heavy_prime_factorization(mut n: u64) -> u32 {
     count
 }
 
+#[xross_function(package = "standalone", blocking)]
+pub fn blocking_prime_factorization(n: u64) -> u32 {
+    heavy_prime_factorization(n)
+}
+
+/// Not run at all unless a worker picks it up within a second, or the per-call timeout.
+#[xross_function(package = "standalone", blocking, timeout_ms = 1000)]
+pub fn blocking_square(n: u64) -> u64 {
+    n * n
+}
+
+/// Counts primes below `limit`, stopping early once `token` is cancelled.
+#[xross_function(package = "standalone")]
+pub fn count_primes(limit: u64, token: XrossCancelToken) -> u64 {
+    let mut count = 0;
+    for n in 2..limit {
+        if n % 1024 == 0 && token.is_cancelled() {
+            break;
+        }
+        if (2..).take_while(|d| d * d <= n).all(|d| n % d != 0) {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// `count_primes` on the blocking pool; cancelling the awaiting coroutine cancels `token`.
+#[xross_function(package = "standalone", blocking)]
+pub fn count_primes_blocking(limit: u64, token: XrossCancelToken) -> u64 {
+    count_primes(limit, token)
+}
+
 #[xross_function(package = "standalone")]
 pub fn heavy_matrix_multiplication(size: usize) -> f64 {
     let a = vec![1.1f64; size * size];
@@ -158,3 +211,39 @@ pub fn batch_heavy_matrix_multiplication(size: usize, repeat: u32) -> f64 {
     }
     last_res
 }
+
+/// Yields `count` consecutive integers starting at `start`.
+#[xross_function(package = "standalone")]
+pub fn count_up(start: i32, count: i32) -> impl Iterator<Item = i32> {
+    (start..).take(count.max(0) as usize)
+}
+
+/// Emits `count` sensor samples, one every 10 ms.
+#[xross_function(package = "standalone")]
+pub async fn sample_stream(count: u32) -> impl Stream<Item = f64> {
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    futures::stream::iter(0..count).then(|i| async move {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        i as f64 * 0.5
+    })
+}
+
+/// Streams `lines` log lines produced by a background thread.
+#[xross_function(package = "standalone")]
+pub fn log_tail(lines: u32) -> tokio::sync::mpsc::Receiver<String> {
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    std::thread::spawn(move || {
+        for i in 0..lines {
+            if tx.blocking_send(format!("log line {}", i)).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Takes a class defined by the `xross-example-shared` crate.
+#[xross_function(package = "standalone")]
+pub fn tag_weight(tag: &xross_example_shared::Tag) -> i32 {
+    tag.weight
+}
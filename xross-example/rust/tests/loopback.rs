@@ -0,0 +1,205 @@
+//! Calls the exports of the built cdylib through `xross-test`, with the JVM's calling convention.
+
+mod common;
+
+use common::library;
+use xross_test::{XrossCancelFlag, XrossInvokeError, XrossStructValue, XrossValue};
+
+#[test]
+fn test_functions() {
+    let lib = library();
+    let call = |name: &str, args: &[XrossValue]| {
+        lib.invoke(&format!("standalone.{}", name), name, args).unwrap()
+    };
+
+    assert_eq!(call("global_add", &[2i32.into(), 3i32.into()]), XrossValue::I32(5));
+    assert_eq!(call("raw_global_add", &[2i32.into(), 3i32.into()]), XrossValue::I32(5));
+    assert_eq!(
+        call("test_unsigned", &[200u8.into(), 4_000_000_000u32.into(), 1u64.into()]),
+        XrossValue::U64(4_000_000_201)
+    );
+    assert_eq!(call("global_greet", &["Xross".into()]), XrossValue::String("Hello, Xross!".into()));
+    // Characters outside Latin-1 are passed as UTF-16.
+    assert_eq!(call("global_greet", &["世界".into()]), XrossValue::String("Hello, 世界!".into()));
+    assert_eq!(
+        call("get_large_array", &[4usize.into()]),
+        XrossValue::Vec((0..4).map(XrossValue::I32).collect())
+    );
+    assert_eq!(
+        call("count_up", &[5i32.into(), 3i32.into()]),
+        XrossValue::Vec((5..8).map(XrossValue::I32).collect())
+    );
+    assert_eq!(
+        call("log_tail", &[2u32.into()]),
+        XrossValue::Vec(vec!["log line 0".into(), "log line 1".into()])
+    );
+
+    let cancelled = XrossCancelFlag::new();
+    cancelled.cancel();
+    let token = |flag| XrossValue::CancelToken(flag);
+    assert_eq!(call("count_primes", &[100u64.into(), token(None)]), XrossValue::U64(25));
+    assert_eq!(
+        call("count_primes", &[5000u64.into(), token(Some(cancelled))]),
+        XrossValue::U64(172)
+    );
+}
+
+#[test]
+fn test_tasks() {
+    let lib = library();
+    let call =
+        |name: &str, args: &[XrossValue]| lib.invoke(&format!("standalone.{}", name), name, args);
+
+    assert_eq!(call("async_add", &[2i32.into(), 3i32.into()]), Ok(XrossValue::I32(5)));
+    // A zero result is only told apart from a pending task once the channel closes.
+    assert_eq!(call("async_add", &[0i32.into(), 0i32.into()]), Ok(XrossValue::I32(0)));
+    assert_eq!(
+        call("async_greet", &["Xross".into()]),
+        Ok(XrossValue::String("Async Hello, Xross!".into()))
+    );
+    assert_eq!(call("blocking_prime_factorization", &[360u64.into()]), Ok(XrossValue::U32(6)));
+    assert_eq!(
+        call("delayed_echo", &[7i32.into(), 0u64.into(), 0u64.into()]),
+        Ok(XrossValue::I32(7))
+    );
+    match call("delayed_echo", &[7i32.into(), 500u64.into(), 20u64.into()]) {
+        Err(XrossInvokeError::Task(message)) => assert!(message.starts_with("xross: timed out")),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_objects_and_panics() {
+    let lib = library();
+    let service = lib.invoke("MyService", "new", &[]).unwrap();
+    let obj = service.as_object().unwrap();
+
+    assert_eq!(
+        lib.invoke("MyService", "cause_panic", &[service.clone(), 0u8.into()]),
+        Ok(XrossValue::String("No panic today".into()))
+    );
+    assert_eq!(
+        lib.invoke("MyService", "cause_panic", &[service.clone(), 1u8.into()]),
+        Err(XrossInvokeError::Panic("Intentional panic from Rust!".into()))
+    );
+    assert_eq!(
+        lib.invoke("MyService", "async_execute", &[service.clone(), 21i32.into()]),
+        Ok(XrossValue::I32(42))
+    );
+    assert_eq!(
+        lib.invoke("MyService", "boxes_page", &[service.clone(), 0usize.into(), 2usize.into()]),
+        Ok(XrossValue::Vec(vec![XrossValue::I32(0), XrossValue::I32(0)]))
+    );
+
+    let Ok(XrossValue::Option(Some(simple))) =
+        lib.invoke("MyService", "get_option_enum", &[service.clone(), true.into()])
+    else {
+        panic!("expected Some");
+    };
+    let simple = simple.as_object().unwrap();
+    assert_eq!(lib.variant_name(simple).unwrap(), "V");
+    lib.drop(simple).unwrap();
+    assert_eq!(
+        lib.invoke("MyService", "get_option_enum", &[service.clone(), false.into()]),
+        Ok(XrossValue::Option(None))
+    );
+
+    let Ok(XrossValue::Result(Ok(inner))) =
+        lib.invoke("MyService", "get_result_struct", &[service.clone(), true.into()])
+    else {
+        panic!("expected Ok");
+    };
+    let inner = inner.as_object().unwrap();
+    assert_eq!(lib.get(inner, "val"), Ok(XrossValue::I32(1)));
+    let copy = lib.invoke("test.test2.MyService2", "clone", &[inner.clone().into()]).unwrap();
+    lib.drop(copy.as_object().unwrap()).unwrap();
+    lib.drop(inner).unwrap();
+    assert_eq!(
+        lib.invoke("MyService", "get_result_struct", &[service.clone(), false.into()]),
+        Ok(XrossValue::Result(Err(Box::new("Error".into()))))
+    );
+
+    lib.drop(obj).unwrap();
+}
+
+#[test]
+fn test_properties_and_variants() {
+    let lib = library();
+    let fast = lib.invoke("fast.FastStruct", "new", &[3i32.into(), "fast".into()]).unwrap();
+    let obj = fast.as_object().unwrap();
+    assert_eq!(lib.get(obj, "name"), Ok(XrossValue::String("fast".into())));
+    lib.set(obj, "name", &"renamed".into()).unwrap();
+    assert_eq!(lib.get(obj, "name"), Ok(XrossValue::String("renamed".into())));
+    lib.set(obj, "data", &XrossValue::I32(9)).unwrap();
+    assert_eq!(lib.get(obj, "data"), Ok(XrossValue::I32(9)));
+    assert_eq!(
+        lib.invoke("fast.FastStruct", "count_chars", &[fast.clone(), "héllo".into()]),
+        Ok(XrossValue::I32(5))
+    );
+    // `fast.Point` is passed and returned by value.
+    let point = |x: i32, y: i32| {
+        XrossValue::Struct(XrossStructValue {
+            signature: "fast.Point".into(),
+            fields: vec![("x".into(), x.into()), ("y".into(), y.into())],
+        })
+    };
+    // The cdylib is built with the test's features, which swap `origin` for `scaled_data`.
+    if cfg!(feature = "experimental") {
+        assert_eq!(
+            lib.invoke("fast.FastStruct", "scaled_data", &[fast.clone(), XrossValue::I32(3)]),
+            Ok(XrossValue::I32(27))
+        );
+        assert!(matches!(
+            lib.invoke("fast.FastStruct", "origin", std::slice::from_ref(&fast)),
+            Err(XrossInvokeError::UnknownMember { .. })
+        ));
+    } else {
+        assert_eq!(
+            lib.invoke("fast.FastStruct", "origin", std::slice::from_ref(&fast)),
+            Ok(point(9, 0))
+        );
+    }
+    assert_eq!(
+        lib.invoke("fast.FastStruct", "offset", &[fast.clone(), point(1, 2)]),
+        Ok(point(10, 11))
+    );
+    lib.drop(obj).unwrap();
+
+    let b = lib.variant("XrossTestEnum", "B", &[XrossValue::I32(4)]).unwrap();
+    assert_eq!(lib.variant_name(&b).unwrap(), "B");
+    lib.drop(&b).unwrap();
+
+    assert!(matches!(
+        lib.invoke("fast.FastStruct", "missing", &[]),
+        Err(XrossInvokeError::UnknownMember { .. })
+    ));
+    assert!(matches!(
+        lib.invoke(
+            "standalone.global_add",
+            "global_add",
+            &[XrossValue::I64(1), XrossValue::I32(2)]
+        ),
+        Err(XrossInvokeError::Arguments { .. })
+    ));
+}
+
+#[test]
+fn test_contiguous_points() {
+    let lib = library();
+    let path = lib.invoke("graphics.Path2D", "new", &[]).unwrap();
+    let xy = |x: f64, y: f64| [path.clone(), x.into(), y.into()];
+    lib.invoke("graphics.Path2D", "move_to", &xy(0.0, 0.0)).unwrap();
+    lib.invoke("graphics.Path2D", "line_to", &xy(3.0, 4.0)).unwrap();
+    lib.invoke("graphics.Path2D", "line_to", &xy(6.0, 8.0)).unwrap();
+
+    let points = lib.invoke("graphics.Path2D", "points", std::slice::from_ref(&path)).unwrap();
+    let points = points.as_collection().unwrap();
+    let coords: Vec<(XrossValue, XrossValue)> =
+        points.items.iter().map(|p| (lib.get(p, "x").unwrap(), lib.get(p, "y").unwrap())).collect();
+    let expected = [(0.0, 0.0), (3.0, 4.0), (6.0, 8.0)];
+    let expected: Vec<_> =
+        expected.iter().map(|&(x, y)| (XrossValue::F64(x), XrossValue::F64(y))).collect();
+    assert_eq!(coords, expected);
+    lib.drop_collection(points).unwrap();
+    lib.drop(path.as_object().unwrap()).unwrap();
+}
@@ -0,0 +1,30 @@
+//! Classes defined outside the example cdylib.
+//! `xross-example` uses them as argument types and re-exports them.
+
+use xross_core::{XrossClass, xross_methods};
+
+#[derive(XrossClass, Clone, Debug, Default)]
+#[xross_package("shared")]
+#[xross(clonable)]
+pub struct Tag {
+    #[xross_field]
+    pub weight: i32,
+}
+
+#[xross_methods]
+impl Tag {
+    #[xross_new]
+    pub fn new(weight: i32) -> Self {
+        Self { weight }
+    }
+
+    #[xross_method]
+    pub fn heavier(&self, by: i32) -> Self {
+        Self { weight: self.weight + by }
+    }
+
+    #[xross_method]
+    pub fn lighter(&self, by: std::primitive::i32) -> Option<Self> {
+        self.weight.checked_sub(by).filter(|w| *w >= 0).map(|weight| Self { weight })
+    }
+}
@@ -0,0 +1,355 @@
+//! Generates a C header declaring the `extern "C"` surface described by xross metadata,
+//! for C/C++ callers and FFI libraries such as ctypes or cffi.
+
+mod signature;
+
+use std::fmt::Write;
+use xross_metadata::{XrossDefinition, XrossField, XrossMetadataSet, XrossSymbolKind};
+
+const PRELUDE: &str = r#"#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * A `String` or `Vec` allocated by Rust. `cap` and `len` count elements. Release it with
+ * the `xross_free_buffer` function of its element width.
+ */
+typedef struct XrossBuffer {
+    uintptr_t cap;
+    uintptr_t len;
+    void *ptr;
+} XrossBuffer;
+
+/** A borrowed string. `encoding` is 0 for Latin-1 and 1 for UTF-16. */
+typedef struct XrossStringView {
+    const uint8_t *ptr;
+    uintptr_t len;
+    uint8_t encoding;
+} XrossStringView;
+
+/**
+ * Outcome of a fallible or panicable call. On a panic `is_ok` is false and `ptr` holds a
+ * boxed `XrossBuffer` with the message.
+ */
+typedef struct XrossResult {
+    bool is_ok;
+    void *ptr;
+} XrossResult;
+
+/** State returned by `XrossTask.poll_fn` and `XrossStream.poll_next_fn`. */
+typedef enum XrossPoll {
+    XROSS_POLL_PENDING = 0,
+    XROSS_POLL_READY = 1,
+    XROSS_POLL_DONE = 2,
+} XrossPoll;
+
+/**
+ * A running async or blocking call. `poll_fn(task_ptr, out)` returns `XROSS_POLL_PENDING`
+ * while the call runs, then writes the result to `out` and returns `XROSS_POLL_READY`.
+ * `drop_fn(task_ptr)` must be called once; for a blocking call that has started, it waits
+ * until the call returns. A null `task_ptr` means the call was rejected by its concurrency
+ * limit.
+ */
+typedef struct XrossTask {
+    void *task_ptr;
+    XrossPoll (*poll_fn)(void *, XrossResult *);
+    void (*drop_fn)(void *);
+} XrossTask;
+
+/** A returned iterator. `next_fn` returns false when exhausted; call `close_fn` once. */
+typedef struct XrossIterator {
+    void *iter_ptr;
+    bool (*next_fn)(void *, XrossResult *);
+    void (*close_fn)(void *);
+} XrossIterator;
+
+/** A returned stream. `poll_next_fn` never blocks; call `close_fn` once. */
+typedef struct XrossStream {
+    void *stream_ptr;
+    XrossPoll (*poll_next_fn)(void *, XrossResult *);
+    void (*close_fn)(void *);
+} XrossStream;
+
+/** Writes the embedded metadata bundle as JSON. */
+void xross_metadata(XrossBuffer *out);
+uint32_t xross_abi_version(void);
+uint64_t xross_metadata_hash(void);
+/** Releases a string, or a vector of 1-byte elements, returned by this library. */
+void xross_free_buffer(XrossBuffer buf);
+/** Releases a vector of 16-bit elements. */
+void xross_free_buffer_16(XrossBuffer buf);
+/** Releases a vector of 32-bit elements (including `float`). */
+void xross_free_buffer_32(XrossBuffer buf);
+/** Releases a vector of 64-bit elements (including `double`). */
+void xross_free_buffer_64(XrossBuffer buf);
+/** Releases a vector of pointer-sized elements or object handles. */
+void xross_free_buffer_ptr(XrossBuffer buf);
+/** Releases the boxed scalar of a returned `Some`; `size` is the scalar's size in bytes. */
+void xross_free_scalar(void *ptr, uintptr_t size);
+"#;
+
+/// Writes `docs` as a `/** */` comment.
+fn write_docs(out: &mut String, docs: &[String]) {
+    let lines: Vec<String> = docs
+        .iter()
+        .flat_map(|d| d.lines())
+        .map(|l| l.trim().replace("*/", "* /"))
+        .filter(|l| !l.is_empty())
+        .collect();
+    match lines.as_slice() {
+        [] => {}
+        [line] => {
+            let _ = writeln!(out, "/** {} */", line);
+        }
+        lines => {
+            out.push_str("/**\n");
+            for line in lines {
+                let _ = writeln!(out, " * {}", line);
+            }
+            out.push_str(" */\n");
+        }
+    }
+}
+
+fn definition_docs(def: &XrossDefinition) -> &[String] {
+    match def {
+        XrossDefinition::Struct(s) => &s.docs,
+        XrossDefinition::Enum(e) => &e.docs,
+        XrossDefinition::Opaque(o) => &o.docs,
+        XrossDefinition::Function(f) => &f.docs,
+    }
+}
+
+fn fields(def: &XrossDefinition) -> &[XrossField] {
+    match def {
+        XrossDefinition::Struct(s) => &s.fields,
+        XrossDefinition::Opaque(o) => &o.fields,
+        _ => &[],
+    }
+}
+
+/// Declares the C struct of every by-value struct, ahead of the functions taking them.
+fn write_by_value_structs(out: &mut String, set: &XrossMetadataSet) {
+    for def in set {
+        let XrossDefinition::Struct(s) = def else { continue };
+        if !s.by_value {
+            continue;
+        }
+        let name = signature::struct_name(s);
+        let _ = writeln!(out, "\n/** `{}`, passed by value. */", s.signature);
+        let _ = writeln!(out, "typedef struct {} {{", name);
+        for field in &s.fields {
+            let ty = signature::scalar(&field.ty).expect("by-value fields are scalars");
+            let _ = writeln!(out, "    {} {};", ty, signature::c_ident(&field.name));
+        }
+        let _ = writeln!(out, "}} {};", name);
+    }
+}
+
+fn write_definition(out: &mut String, set: &XrossMetadataSet, def: &XrossDefinition) {
+    let _ = writeln!(out, "\n/* {} */", def.signature());
+    let prefix = def.symbol_prefix();
+    for symbol in def.exported_symbols() {
+        let member = symbol.member.as_deref().unwrap_or_default();
+        let (docs, declaration): (&[String], _) = match symbol.kind {
+            XrossSymbolKind::Method => {
+                let m = def.methods().iter().find(|m| m.symbol == symbol.name).unwrap();
+                let declaration = if m.symbol == format!("{}_drop", prefix) {
+                    Some(signature::drop_or_clone(m, false))
+                } else if m.symbol == format!("{}_clone", prefix) {
+                    Some(signature::drop_or_clone(m, true))
+                } else {
+                    signature::method(set, m)
+                };
+                let docs = if matches!(def, XrossDefinition::Function(_)) {
+                    definition_docs(def)
+                } else {
+                    &m.docs
+                };
+                (docs, declaration)
+            }
+            XrossSymbolKind::PropertyGetter | XrossSymbolKind::PropertySetter => {
+                let field = fields(def).iter().find(|f| f.name == member).unwrap();
+                let declaration = if symbol.kind == XrossSymbolKind::PropertyGetter {
+                    signature::property_getter(&symbol.name, field)
+                } else {
+                    signature::property_setter(&symbol.name, field)
+                };
+                (&field.docs, Some(declaration))
+            }
+            XrossSymbolKind::VariantConstructor => {
+                let XrossDefinition::Enum(e) = def else { unreachable!() };
+                let variant = e.variants.iter().find(|v| v.name == member).unwrap();
+                let declaration =
+                    signature::variant_constructor(set, &symbol.name, &variant.fields);
+                (&variant.docs, Some(declaration))
+            }
+            XrossSymbolKind::Support => (&[], signature::support(prefix, &symbol.name)),
+        };
+        write_docs(out, docs);
+        match declaration {
+            Some(declaration) => {
+                let _ = writeln!(out, "{}", declaration);
+            }
+            None => {
+                let _ = writeln!(out, "/* {}: raw signature has no C equivalent */", symbol.name);
+            }
+        }
+    }
+}
+
+/// Generates a header declaring the runtime types and functions, and every symbol of `set`
+/// (see `XrossDefinition::exported_symbols`) with the parameters the macros flatten it to.
+/// `guard` names the include guard, e.g. `MYLIB_H`.
+pub fn generate_header(set: &XrossMetadataSet, guard: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "/* Generated from xross metadata. Do not edit. */");
+    let _ = writeln!(out, "#ifndef {}\n#define {}\n", guard, guard);
+    out.push_str(PRELUDE);
+    write_by_value_structs(&mut out, set);
+    for def in set {
+        write_docs(&mut out, definition_docs(def));
+        write_definition(&mut out, set, def);
+    }
+    let _ = writeln!(out, "\n#ifdef __cplusplus\n}}\n#endif\n\n#endif /* {} */", guard);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xross_metadata::{
+        HandleMode, Ownership, ThreadSafety, XrossEnum, XrossMethod, XrossMethodType, XrossStruct,
+        XrossType, XrossVariant,
+    };
+
+    fn field(name: &str, ty: XrossType) -> XrossField {
+        XrossField { name: name.to_string(), ty, docs: vec![], safety: ThreadSafety::Lock }
+    }
+
+    fn method(symbol: &str, args: Vec<XrossField>, ret: XrossType) -> XrossMethod {
+        XrossMethod {
+            name: symbol.rsplit('_').next().unwrap().to_string(),
+            symbol: symbol.to_string(),
+            method_type: XrossMethodType::ConstInstance,
+            handle_mode: HandleMode::Normal,
+            is_constructor: false,
+            is_default: false,
+            is_raw: false,
+            is_async: false,
+            concurrency: None,
+            timeout_ms: None,
+            contiguous: false,
+            args,
+            ret,
+            docs: vec![],
+            safety: ThreadSafety::Lock,
+        }
+    }
+
+    fn class(name: &str, fields: Vec<XrossField>, methods: Vec<XrossMethod>) -> XrossStruct {
+        XrossStruct {
+            signature: format!("demo.{}", name),
+            symbol_prefix: format!("demo_{}", name.to_lowercase()),
+            package_name: "demo".to_string(),
+            name: name.to_string(),
+            fields,
+            methods,
+            docs: vec![],
+            is_copy: false,
+            by_value: false,
+            layout: None,
+        }
+    }
+
+    /// A by-value `Point`, a `Label` with a string property taking and returning points,
+    /// and a `Shape` enum.
+    fn demo_set() -> XrossMetadataSet {
+        let point = XrossStruct {
+            is_copy: true,
+            by_value: true,
+            ..class("Point", vec![field("x", XrossType::I32), field("y", XrossType::I32)], vec![])
+        };
+        let point_ty = || XrossType::object("demo.Point", Ownership::Owned);
+        let mut label = class(
+            "Label",
+            vec![field("text", XrossType::String)],
+            vec![method("demo_label_offset", vec![field("p", point_ty())], point_ty())],
+        );
+        label.docs = vec!["A text label.".to_string()];
+        let shape = XrossEnum {
+            signature: "demo.Shape".to_string(),
+            symbol_prefix: "demo_shape".to_string(),
+            package_name: "demo".to_string(),
+            name: "Shape".to_string(),
+            variants: vec![XrossVariant {
+                name: "Circle".to_string(),
+                fields: vec![field("radius", XrossType::F32)],
+                docs: vec![],
+            }],
+            methods: vec![],
+            docs: vec![],
+            is_copy: false,
+            layout: None,
+        };
+        let mut set = XrossMetadataSet::new();
+        for def in [
+            XrossDefinition::Struct(point),
+            XrossDefinition::Struct(label),
+            XrossDefinition::Enum(shape),
+        ] {
+            set.insert(def).unwrap();
+        }
+        set
+    }
+
+    #[test]
+    fn test_header() {
+        let set = demo_set();
+        let header = generate_header(&set, "DEMO_H");
+        for (_, symbol) in set.exported_symbols() {
+            assert!(header.contains(&symbol.name), "{} is not declared", symbol.name);
+        }
+        assert!(header.contains("} XrossTask;"));
+        assert!(header.contains("void xross_free_buffer_64(XrossBuffer buf);"));
+        assert!(header.contains("/** A text label. */\n\n/* demo.Label */"));
+        assert!(header.contains(
+            "void demo_label_property_text_str_set(void *self, const uint8_t *val_ptr, \
+             uintptr_t val_len, uint8_t val_enc);"
+        ));
+        assert!(header.contains("void *demo_shape_new_Circle(float radius);"));
+        assert!(header.contains("int32_t demo_shape_get_tag(const void *self);"));
+        assert!(header.contains("void demo_point_drop_vec(XrossBuffer items);"));
+
+        // `Point` is declared as a C struct before the functions passing it by value.
+        let typedef = "typedef struct demo_point {\n    int32_t x;\n    int32_t y;\n} demo_point;";
+        let offset = "demo_point demo_label_offset(void *self, demo_point p);";
+        assert!(header.find(typedef).unwrap() < header.find(offset).unwrap());
+    }
+
+    #[test]
+    fn test_docs() {
+        let mut out = String::new();
+        write_docs(&mut out, &[" Adds two numbers. ".to_string()]);
+        assert_eq!(out, "/** Adds two numbers. */\n");
+
+        out.clear();
+        write_docs(&mut out, &["First line.\n\nEnds a comment: */".to_string()]);
+        assert_eq!(out, "/**\n * First line.\n * Ends a comment: * /\n */\n");
+
+        out.clear();
+        write_docs(&mut out, &[String::new()]);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_guard() {
+        let header = generate_header(&XrossMetadataSet::new(), "DEMO_H");
+        assert!(header.contains("#ifndef DEMO_H\n#define DEMO_H\n"));
+        assert!(header.trim_end().ends_with("#endif /* DEMO_H */"));
+        assert!(header.contains("void xross_free_scalar(void *ptr, uintptr_t size);"));
+    }
+}
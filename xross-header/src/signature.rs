@@ -0,0 +1,304 @@
+use xross_metadata::{
+    HandleMode, XrossField, XrossMetadataSet, XrossMethod, XrossMethodType, XrossStruct, XrossType,
+};
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "bool", "char", "const", "default", "double", "enum", "extern", "float", "goto", "int",
+    "long", "register", "restrict", "short", "signed", "sizeof", "static", "struct", "switch",
+    "typedef", "union", "unsigned", "void", "volatile",
+];
+
+/// Returns `name` usable as a C parameter name.
+pub(crate) fn c_ident(name: &str) -> String {
+    if C_KEYWORDS.contains(&name) { format!("{}_", name) } else { name.to_string() }
+}
+
+/// Joins a type and a name, keeping `void *name` rather than `void * name`.
+fn decl(ty: &str, name: &str) -> String {
+    if ty.ends_with('*') { format!("{}{}", ty, name) } else { format!("{} {}", ty, name) }
+}
+
+/// C type of a value passed as is.
+pub(crate) fn scalar(ty: &XrossType) -> Option<&'static str> {
+    Some(match ty {
+        XrossType::Bool => "bool",
+        XrossType::I8 => "int8_t",
+        XrossType::U8 => "uint8_t",
+        XrossType::I16 => "int16_t",
+        XrossType::U16 => "uint16_t",
+        XrossType::I32 => "int32_t",
+        XrossType::U32 => "uint32_t",
+        XrossType::I64 => "int64_t",
+        XrossType::U64 => "uint64_t",
+        XrossType::ISize => "intptr_t",
+        XrossType::USize => "uintptr_t",
+        XrossType::F32 => "float",
+        XrossType::F64 => "double",
+        XrossType::Pointer => "void *",
+        _ => return None,
+    })
+}
+
+/// Name of the C struct declared for a by-value struct.
+pub(crate) fn struct_name(def: &XrossStruct) -> &str {
+    &def.symbol_prefix
+}
+
+/// Parameters of one argument, flattened as specified in `xross_core::abi`:
+/// strings become `ptr, len, enc` and slices `ptr, len`.
+fn params(name: &str, ty: &XrossType) -> Vec<String> {
+    let name = c_ident(name);
+    match ty {
+        XrossType::String => vec![
+            format!("const uint8_t *{}_ptr", name),
+            format!("uintptr_t {}_len", name),
+            format!("uint8_t {}_enc", name),
+        ],
+        XrossType::Slice(inner) | XrossType::Vec(inner) => {
+            let elem = match &**inner {
+                XrossType::Object { .. } => "void *const *".to_string(),
+                inner => format!("const {} *", scalar(inner).unwrap_or("void")),
+            };
+            vec![decl(&elem, &format!("{}_ptr", name)), format!("uintptr_t {}_len", name)]
+        }
+        XrossType::Result { .. } => vec![decl("XrossResult", &name)],
+        // Points to an atomic flag owned by the caller; null is never cancelled.
+        XrossType::CancelToken => vec![decl("const bool *", &name)],
+        ty => vec![decl(scalar(ty).unwrap_or("void *"), &name)],
+    }
+}
+
+/// Parameters of method and variant constructor arguments, which pass owned by-value structs
+/// as the struct itself.
+fn field_params(set: &XrossMetadataSet, fields: &[XrossField]) -> Vec<String> {
+    fields
+        .iter()
+        .flat_map(|f| match set.by_value_struct(&f.ty) {
+            Some(def) => vec![decl(struct_name(def), &c_ident(&f.name))],
+            None => params(&f.name, &f.ty),
+        })
+        .collect()
+}
+
+fn function(ret: &str, symbol: &str, params: Vec<String>) -> String {
+    let params = if params.is_empty() { "void".to_string() } else { params.join(", ") };
+    format!("{}({});", decl(ret, symbol), params)
+}
+
+/// Declaration of a function that writes its result to a leading `out` parameter.
+fn out_function(out: &str, symbol: &str, mut rest: Vec<String>) -> String {
+    rest.insert(0, format!("{} *out", out));
+    function("void", symbol, rest)
+}
+
+/// Declares a method export, following `write_ffi_function`: sequences, tasks, panicable calls
+/// and buffers are written to `out`; everything else is returned.
+/// Returns `None` for raw exports whose Rust signature has no C equivalent.
+pub(crate) fn method(set: &XrossMetadataSet, m: &XrossMethod) -> Option<String> {
+    let mut rest = Vec::new();
+    if m.method_type != XrossMethodType::Static {
+        rest.push("void *self".to_string());
+    }
+
+    if m.is_raw {
+        // Raw exports take and return the declared Rust types unchanged.
+        let ret = match &m.ret {
+            XrossType::Void => "void",
+            ty => scalar(ty)?,
+        };
+        for arg in &m.args {
+            rest.push(decl(scalar(&arg.ty)?, &c_ident(&arg.name)));
+        }
+        return Some(if m.handle_mode == HandleMode::Panicable {
+            out_function("XrossResult", &m.symbol, rest)
+        } else {
+            function(ret, &m.symbol, rest)
+        });
+    }
+
+    rest.extend(field_params(set, &m.args));
+    Some(match &m.ret {
+        XrossType::Iterator(_) => out_function("XrossIterator", &m.symbol, rest),
+        XrossType::Stream(_) => out_function("XrossStream", &m.symbol, rest),
+        _ if m.is_async => out_function("XrossTask", &m.symbol, rest),
+        _ if m.handle_mode == HandleMode::Panicable => out_function("XrossResult", &m.symbol, rest),
+        XrossType::String | XrossType::Vec(_) | XrossType::Slice(_) => {
+            out_function("XrossBuffer", &m.symbol, rest)
+        }
+        // Constructors return a handle even for by-value structs.
+        ret => match set.by_value_struct(ret).filter(|_| !m.is_constructor) {
+            Some(def) => function(struct_name(def), &m.symbol, rest),
+            None => function(return_type(ret), &m.symbol, rest),
+        },
+    })
+}
+
+/// C type returned directly, as specified in `xross_core::abi`.
+fn return_type(ty: &XrossType) -> &'static str {
+    match ty {
+        XrossType::Void => "void",
+        XrossType::Option(inner)
+            if matches!(**inner, XrossType::String | XrossType::Vec(_) | XrossType::Slice(_)) =>
+        {
+            "XrossBuffer *"
+        }
+        XrossType::Result { .. } => "XrossResult",
+        ty => scalar(ty).unwrap_or("void *"),
+    }
+}
+
+/// `_drop` and `_clone` return an `XrossResult` directly when panicable.
+pub(crate) fn drop_or_clone(m: &XrossMethod, is_clone: bool) -> String {
+    let receiver = if is_clone { "const void *self" } else { "void *self" };
+    let ret = match (m.handle_mode == HandleMode::Panicable, is_clone) {
+        (true, _) => "XrossResult",
+        (false, true) => "void *",
+        (false, false) => "void",
+    };
+    function(ret, &m.symbol, vec![receiver.to_string()])
+}
+
+/// Suffix and C types of a field's accessors, matching `generate_property_accessors`.
+fn property_types(ty: &XrossType) -> (&'static str, Vec<String>) {
+    match ty {
+        XrossType::String => ("XrossBuffer", params("val", ty)),
+        XrossType::Result { .. } => ("XrossResult", vec!["XrossResult val".to_string()]),
+        ty => {
+            let ty = scalar(ty).unwrap_or("void *");
+            (ty, vec![decl(ty, "val")])
+        }
+    }
+}
+
+pub(crate) fn property_getter(symbol: &str, field: &XrossField) -> String {
+    function(property_types(&field.ty).0, symbol, vec!["void *self".to_string()])
+}
+
+pub(crate) fn property_setter(symbol: &str, field: &XrossField) -> String {
+    let mut rest = vec!["void *self".to_string()];
+    rest.extend(property_types(&field.ty).1);
+    function("void", symbol, rest)
+}
+
+/// `{prefix}_new_{Variant}` takes the variant's fields and returns an owned object.
+pub(crate) fn variant_constructor(
+    set: &XrossMetadataSet,
+    symbol: &str,
+    fields: &[XrossField],
+) -> String {
+    function("void *", symbol, field_params(set, fields))
+}
+
+/// Declarations of the functions every class gets, keyed by symbol suffix.
+pub(crate) fn support(prefix: &str, symbol: &str) -> Option<String> {
+    let suffix = symbol.strip_prefix(prefix)?.strip_prefix('_')?;
+    Some(match suffix {
+        "drop_vec" => function("void", symbol, vec!["XrossBuffer items".to_string()]),
+        "layout" | "layout_descriptor" => out_function("XrossBuffer", symbol, vec![]),
+        "layout_fingerprint" => function("uint64_t", symbol, vec![]),
+        "get_tag" => function("int32_t", symbol, vec!["const void *self".to_string()]),
+        "get_variant_name" => {
+            out_function("XrossBuffer", symbol, vec!["const void *self".to_string()])
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xross_metadata::{Ownership, ThreadSafety};
+
+    fn arg(name: &str, ty: XrossType) -> XrossField {
+        XrossField { name: name.to_string(), ty, docs: vec![], safety: ThreadSafety::Lock }
+    }
+
+    fn export(args: Vec<XrossField>, ret: XrossType) -> XrossMethod {
+        XrossMethod {
+            name: "run".to_string(),
+            symbol: "demo_Service_run".to_string(),
+            method_type: XrossMethodType::ConstInstance,
+            handle_mode: HandleMode::Normal,
+            is_constructor: false,
+            is_default: false,
+            is_raw: false,
+            is_async: false,
+            concurrency: None,
+            timeout_ms: None,
+            contiguous: false,
+            args,
+            ret,
+            docs: vec![],
+            safety: ThreadSafety::Lock,
+        }
+    }
+
+    fn declare(m: &XrossMethod) -> String {
+        method(&XrossMetadataSet::new(), m).unwrap()
+    }
+
+    #[test]
+    fn test_flattened_arguments() {
+        let m = export(
+            vec![
+                arg("name", XrossType::String),
+                arg("values", XrossType::Slice(Box::new(XrossType::I32))),
+                arg(
+                    "items",
+                    XrossType::Vec(Box::new(XrossType::object("demo.Item", Ownership::Ref))),
+                ),
+                arg("token", XrossType::CancelToken),
+                arg("default", XrossType::U8),
+            ],
+            XrossType::I64,
+        );
+        assert_eq!(
+            declare(&m),
+            "int64_t demo_Service_run(void *self, const uint8_t *name_ptr, uintptr_t name_len, \
+             uint8_t name_enc, const int32_t *values_ptr, uintptr_t values_len, \
+             void *const *items_ptr, uintptr_t items_len, const bool *token, uint8_t default_);"
+        );
+    }
+
+    #[test]
+    fn test_returns() {
+        let mut m = export(vec![], XrossType::String);
+        m.method_type = XrossMethodType::Static;
+        assert_eq!(declare(&m), "void demo_Service_run(XrossBuffer *out);");
+
+        m.ret = XrossType::Option(Box::new(XrossType::String));
+        assert_eq!(declare(&m), "XrossBuffer *demo_Service_run(void);");
+        m.ret = XrossType::object("demo.Service", Ownership::Owned);
+        assert_eq!(declare(&m), "void *demo_Service_run(void);");
+        m.ret = XrossType::Stream(Box::new(XrossType::I32));
+        assert_eq!(declare(&m), "void demo_Service_run(XrossStream *out);");
+
+        m.ret = XrossType::I32;
+        m.handle_mode = HandleMode::Panicable;
+        assert_eq!(declare(&m), "void demo_Service_run(XrossResult *out);");
+        m.is_async = true;
+        assert_eq!(declare(&m), "void demo_Service_run(XrossTask *out);");
+    }
+
+    #[test]
+    fn test_raw() {
+        let mut m = export(vec![arg("x", XrossType::F64)], XrossType::F64);
+        m.is_raw = true;
+        assert_eq!(declare(&m), "double demo_Service_run(void *self, double x);");
+        m.args[0].ty = XrossType::String;
+        assert_eq!(method(&XrossMetadataSet::new(), &m), None);
+    }
+
+    #[test]
+    fn test_support() {
+        let prefix = "demo_Service";
+        let decl = |suffix: &str| support(prefix, &format!("{}_{}", prefix, suffix));
+        assert_eq!(
+            decl("layout_fingerprint").unwrap(),
+            "uint64_t demo_Service_layout_fingerprint(void);"
+        );
+        assert_eq!(decl("get_tag").unwrap(), "int32_t demo_Service_get_tag(const void *self);");
+        assert_eq!(decl("unknown"), None);
+        assert_eq!(support(prefix, "other_layout"), None);
+    }
+}
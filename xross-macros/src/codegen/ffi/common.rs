@@ -1,26 +1,111 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use xross_metadata::HandleMode;
+use xross_metadata::{HandleMode, XrossDefinition};
 
-/// Generates common FFI functions (drop, clone, layout).
+/// Generates the `XrossClass` impl of a type and its companion macro `__xross_class_{Name}`.
+/// The companion macro hands the definition to `#[xross_methods]`, so the methods macro
+/// never has to read it back from the metadata directory. It is re-exported under the type's
+/// own name in the macro namespace, so any path or `use` that names the type also names it.
+pub fn generate_class_trait(
+    name: &syn::Ident,
+    def: &XrossDefinition,
+    layout_logic: TokenStream,
+    layout_descriptor: TokenStream,
+) -> TokenStream {
+    let (signature, symbol_prefix, package) = match def {
+        XrossDefinition::Struct(s) => (&s.signature, &s.symbol_prefix, &s.package_name),
+        XrossDefinition::Enum(e) => (&e.signature, &e.symbol_prefix, &e.package_name),
+        XrossDefinition::Opaque(o) => (&o.signature, &o.symbol_prefix, &o.package_name),
+        XrossDefinition::Function(_) => unreachable!("Functions cannot implement XrossClass"),
+    };
+    let crate_name = crate::utils::get_crate_name();
+    let companion = companion_macro_ident(name);
+    let json = serde_json::to_string(def).expect("Failed to serialize xross definition");
+    let fingerprint = def.layout_fingerprint();
+    let by_value = matches!(def, XrossDefinition::Struct(s) if s.by_value);
+    // Bindings copy by-value structs freely, which is only sound for `Copy` types.
+    let copy_check = if by_value {
+        quote! {
+            const _: () = {
+                fn assert_copy<T: Copy>() {}
+                let _ = assert_copy::<#name>;
+            };
+        }
+    } else {
+        quote! {}
+    };
+    let owned = if by_value {
+        quote! {
+            type Ffi = Self;
+
+            fn into_ffi(self) -> Self { self }
+
+            unsafe fn from_ffi(ffi: Self) -> Self { ffi }
+        }
+    } else {
+        quote! {
+            type Ffi = *mut std::ffi::c_void;
+
+            fn into_ffi(self) -> *mut std::ffi::c_void {
+                xross_core::abi::XrossPtr::into_xross_ptr(self)
+            }
+
+            unsafe fn from_ffi(ffi: *mut std::ffi::c_void) -> Self {
+                unsafe { xross_core::abi::XrossArgPtr::from_xross_arg(ffi) }
+            }
+        }
+    };
+
+    quote! {
+        impl xross_core::XrossClass for #name {
+            const SIGNATURE: &'static str = #signature;
+            const SYMBOL_PREFIX: &'static str = #symbol_prefix;
+            const PACKAGE: &'static str = #package;
+            const CRATE: &'static str = #crate_name;
+            const LAYOUT_FINGERPRINT: u64 = #fingerprint;
+
+            fn xross_layout() -> String { #layout_logic }
+
+            fn xross_layout_descriptor() -> xross_core::XrossLayout { #layout_descriptor }
+        }
+
+        impl xross_core::abi::XrossOwned for #name { #owned }
+        #copy_check
+
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #companion {
+            ($($callback:ident)::+ { $($input:tt)* }) => {
+                $($callback)::+! { #json; $($input)* }
+            };
+        }
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #companion as #name;
+    }
+}
+
+/// Name of the companion macro generated next to a type.
+pub fn companion_macro_ident(name: &syn::Ident) -> syn::Ident {
+    format_ident!("__xross_class_{}", name)
+}
+
+/// Generates common FFI functions (drop, drop_vec, clone, layout, layout descriptor and
+/// fingerprint).
 pub fn generate_common_ffi(
     name: &syn::Ident,
     base: &str,
-    layout_logic: TokenStream,
     toks: &mut Vec<TokenStream>,
     is_clonable: bool,
     clone_mode: HandleMode,
     drop_mode: HandleMode,
 ) {
     let drop_id = format_ident!("{}_drop", base);
+    let drop_vec_id = format_ident!("{}_drop_vec", base);
     let clone_id = format_ident!("{}_clone", base);
     let layout_id = format_ident!("{}_layout", base);
-    let trait_name = format_ident!("Xross{}Class", name);
-
-    toks.push(quote! {
-        pub trait #trait_name { fn xross_layout() -> String; }
-        impl #trait_name for #name { fn xross_layout() -> String { #layout_logic } }
-    });
+    let fingerprint_id = format_ident!("{}_layout_fingerprint", base);
+    let descriptor_id = format_ident!("{}_layout_descriptor", base);
 
     let drop_inner = quote! {
         if !ptr.is_null() { drop(unsafe { Box::from_raw(ptr) }); }
@@ -35,7 +120,7 @@ pub fn generate_common_ffi(
                     #drop_inner
                 }));
                 match result {
-                    Ok(_) => xross_core::XrossResult { is_ok: true, ptr: std::ptr::null_mut() },
+                    Ok(_) => xross_core::abi::ok(()),
                     #error_arm
                 }
             }
@@ -68,7 +153,7 @@ pub fn generate_common_ffi(
                         #clone_inner
                     }));
                     match result {
-                        Ok(p) => xross_core::XrossResult { is_ok: true, ptr: p as *mut std::ffi::c_void },
+                        Ok(p) => xross_core::abi::ok(p as *mut std::ffi::c_void),
                         #error_arm
                     }
                 }
@@ -84,11 +169,29 @@ pub fn generate_common_ffi(
     }
 
     toks.push(quote! {
+        /// Drops the elements of a `contiguous` return and frees their allocation.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn #drop_vec_id(items: xross_core::XrossBuffer) {
+            if !items.ptr.is_null() { drop(unsafe { items.into_vec::<#name>() }); }
+        }
+
         #[unsafe(no_mangle)]
         pub unsafe extern "C" fn #layout_id(out: *mut xross_core::XrossString) {
-            let s = <#name as #trait_name>::xross_layout();
+            let s = <#name as xross_core::XrossClass>::xross_layout();
             unsafe { std::ptr::write_unaligned(out, xross_core::XrossString::from(s)) };
         }
+
+        /// Writes the layout as JSON (`XrossLayout`), released with `xross_free_buffer`.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn #descriptor_id(out: *mut xross_core::XrossString) {
+            let layout = <#name as xross_core::XrossClass>::xross_layout_descriptor();
+            unsafe { std::ptr::write_unaligned(out, xross_core::xross_layout_json(&layout)) };
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn #fingerprint_id() -> u64 {
+            <#name as xross_core::XrossClass>::LAYOUT_FINGERPRINT
+        }
     });
 }
 
@@ -121,26 +224,23 @@ pub fn generate_enum_aux_ffi(
     });
 }
 
+/// Puts generated items behind `cfgs`, the `cfg` attributes of the item they were generated for.
+pub fn gate_items(cfgs: &[syn::Attribute], items: Vec<TokenStream>) -> TokenStream {
+    if cfgs.is_empty() {
+        return quote! { #(#items)* };
+    }
+    quote! {
+        #(#cfgs)*
+        const _: () = { #(#items)* };
+    }
+}
+
 pub fn gen_panic_error_arm(context: &str) -> TokenStream {
     let msg_prefix =
         if context.is_empty() { "".to_string() } else { format!(" during {}", context) };
     let default_msg = format!("Unknown panic{}", msg_prefix);
 
     quote! {
-        Err(panic_err) => {
-            let msg = if let Some(s) = panic_err.downcast_ref::<&str>() {
-                s.to_string()
-            } else if let Some(s) = panic_err.downcast_ref::<String>() {
-                s.clone()
-            } else {
-                #default_msg .to_string()
-            };
-
-            let xs = xross_core::XrossString::from(msg);
-            xross_core::XrossResult {
-                is_ok: false,
-                ptr: Box::into_raw(Box::new(xs)) as *mut std::ffi::c_void,
-            }
-        }
+        Err(panic_err) => xross_core::abi::panicked(&*panic_err, #default_msg),
     }
 }
@@ -140,7 +140,12 @@ pub fn gen_arg_conversion(
             (quote! { #ptr_id: *const #inner_rust_ty, #len_id: usize }, conversion, quote!(#arg_id))
         }
         XrossType::Object { ownership, .. } => (
-            quote! { #arg_id: *mut std::ffi::c_void },
+            if *ownership == Ownership::Owned {
+                let base = extract_base_type(arg_ty);
+                quote! { #arg_id: <#base as xross_core::abi::XrossOwned>::Ffi }
+            } else {
+                quote! { #arg_id: *mut std::ffi::c_void }
+            },
             match ownership {
                 Ownership::Ref => {
                     let base = extract_base_type(arg_ty);
@@ -154,7 +159,13 @@ pub fn gen_arg_conversion(
                     let inner = extract_inner_type(arg_ty);
                     quote! { let #arg_id = unsafe { Box::from_raw(#arg_id as *mut #inner) }; }
                 }
-                Ownership::Owned | Ownership::Value => {
+                Ownership::Owned => {
+                    let base = extract_base_type(arg_ty);
+                    quote! {
+                        let #arg_id = unsafe { <#base as xross_core::abi::XrossOwned>::from_ffi(#arg_id) };
+                    }
+                }
+                Ownership::Value => {
                     let base = extract_base_type(arg_ty);
                     // Use ptr::read instead of Box::from_raw to avoid freeing memory that might be owned by Kotlin (e.g. Pure Enums).
                     quote! { let #arg_id = unsafe { std::ptr::read(#arg_id as *const #base) }; }
@@ -162,81 +173,35 @@ pub fn gen_arg_conversion(
             },
             quote! { #arg_id },
         ),
-        XrossType::Option(inner) => {
-            let inner_rust_ty = extract_base_type(arg_ty);
-            (
-                quote! { #arg_id: *mut std::ffi::c_void },
-                match &**inner {
-                    XrossType::String => quote! {
-                        let #arg_id = unsafe {
-                            if #arg_id.is_null() { None }
-                            else { Some(std::ffi::CStr::from_ptr(#arg_id as *const _).to_string_lossy().into_owned()) }
-                        };
-                    },
-                    XrossType::Object { .. } => quote! {
-                        let #arg_id = if #arg_id.is_null() { None }
-                        else { unsafe { Some(std::ptr::read(#arg_id as *const #inner_rust_ty)) } };
-                    },
-                    XrossType::F32 => quote! {
-                        let #arg_id = if #arg_id.is_null() { None }
-                        else { Some(f32::from_bits(#arg_id as u32)) };
-                    },
-                    XrossType::F64 => quote! {
-                        let #arg_id = if #arg_id.is_null() { None }
-                        else { Some(f64::from_bits(#arg_id as u64)) };
-                    },
-                    _ => quote! {
-                        let #arg_id = if #arg_id.is_null() { None }
-                        else { Some(#arg_id as usize as #inner_rust_ty) };
-                    },
-                },
-                quote! { #arg_id },
-            )
-        }
-        XrossType::Result { ok, err: _ } => {
-            let ok_inner = extract_base_type(arg_ty);
-            let gen_read = |ty: &XrossType, ptr: TokenStream, rust_ty: TokenStream| match ty {
-                XrossType::String => {
-                    quote! { std::ffi::CStr::from_ptr(#ptr as *const _).to_string_lossy().into_owned() }
-                }
-                XrossType::Object { .. } => quote! { std::ptr::read(#ptr as *const #rust_ty) },
-                XrossType::F32 => quote! { f32::from_bits(#ptr as u32) },
-                XrossType::F64 => quote! { f64::from_bits(#ptr as u64) },
-                _ => quote! { #ptr as usize as #rust_ty },
-            };
-            let ok_read = gen_read(ok, quote! { #arg_id.ptr }, quote! { #ok_inner });
-            (
-                quote! { #arg_id: xross_core::XrossResult },
-                quote! {
-                    let #arg_id = if #arg_id.is_ok {
-                        Ok(unsafe { #ok_read })
-                    } else {
-                        Err(unsafe {
-                            if #arg_id.ptr.is_null() { "Unknown Error".to_string() }
-                            else { std::ffi::CStr::from_ptr(#arg_id.ptr as *const _).to_string_lossy().into_owned() }
-                        })
-                    };
-                },
-                quote! { #arg_id },
-            )
-        }
+        XrossType::Option(_) => (
+            quote! { #arg_id: *mut std::ffi::c_void },
+            quote! { let #arg_id = unsafe { xross_core::abi::option_from_arg(#arg_id) }; },
+            quote! { #arg_id },
+        ),
+        XrossType::Result { .. } => (
+            quote! { #arg_id: xross_core::XrossResult },
+            quote! { let #arg_id = unsafe { xross_core::abi::result_from_arg(#arg_id) }; },
+            quote! { #arg_id },
+        ),
+        XrossType::CancelToken => (
+            quote! { #arg_id: *const std::sync::atomic::AtomicBool },
+            quote! { let #arg_id = unsafe { xross_core::XrossCancelToken::from_raw(#arg_id) }; },
+            if matches!(arg_ty, Type::Reference(_)) {
+                quote! { &#arg_id }
+            } else {
+                quote! { #arg_id }
+            },
+        ),
         _ => (quote! { #arg_id: #arg_ty }, quote! {}, quote! { #arg_id }),
     }
 }
 
 /// Helper to generate the pointer representation of a single value for XrossResult.
+/// The encoding itself is `xross_core::abi::XrossPtr`.
 pub fn gen_single_value_to_ptr(ty: &XrossType, val_ident: TokenStream) -> TokenStream {
     match ty {
-        XrossType::String => {
-            quote! { Box::into_raw(Box::new(xross_core::XrossBuffer::from(#val_ident))) as *mut std::ffi::c_void }
-        }
-        XrossType::Object { .. } => {
-            quote! { Box::into_raw(Box::new(#val_ident)) as *mut std::ffi::c_void }
-        }
-        XrossType::F32 => quote! { #val_ident.to_bits() as usize as *mut std::ffi::c_void },
-        XrossType::F64 => quote! { #val_ident.to_bits() as usize as *mut std::ffi::c_void },
         XrossType::Void => quote! { std::ptr::null_mut() },
-        _ => quote! { #val_ident as usize as *mut std::ffi::c_void },
+        _ => quote! { xross_core::abi::XrossPtr::into_xross_ptr(#val_ident) },
     }
 }
 
@@ -255,19 +220,9 @@ pub fn gen_ret_wrapping(
         XrossType::Vec(inner) | XrossType::Slice(inner) => (
             quote! { xross_core::XrossBuffer },
             if let XrossType::Object { .. } = &**inner {
-                quote! {
-                    {
-                        let v: Vec<*mut _> = #inner_call.iter().map(|item| Box::into_raw(Box::new(item.clone()))).collect();
-                        xross_core::XrossBuffer::from(v)
-                    }
-                }
+                quote! { xross_core::abi::buffer_from_objects(&#inner_call) }
             } else {
-                quote! {
-                    {
-                        let v = #inner_call.to_vec();
-                        xross_core::XrossBuffer::from(v)
-                    }
-                }
+                quote! { xross_core::abi::buffer_from_slice(&#inner_call) }
             },
         ),
         XrossType::Object { ownership, .. } => match ownership {
@@ -275,65 +230,28 @@ pub fn gen_ret_wrapping(
                 quote! { *mut std::ffi::c_void },
                 quote! { #inner_call as *const _ as *mut std::ffi::c_void },
             ),
-            Ownership::Owned | Ownership::Value => (
+            Ownership::Owned => match sig_output {
+                ReturnType::Type(_, ty) => (
+                    quote! { <#ty as xross_core::abi::XrossOwned>::Ffi },
+                    quote! { xross_core::abi::XrossOwned::into_ffi(#inner_call) },
+                ),
+                ReturnType::Default => unreachable!("an object return has a type"),
+            },
+            Ownership::Value => (
                 quote! { *mut std::ffi::c_void },
-                quote! { Box::into_raw(Box::new(#inner_call)) as *mut std::ffi::c_void },
+                quote! { xross_core::abi::XrossPtr::into_xross_ptr(#inner_call) },
             ),
             Ownership::Boxed => (
                 quote! { *mut std::ffi::c_void },
                 quote! { Box::into_raw(#inner_call) as *mut std::ffi::c_void },
             ),
         },
-        XrossType::Option(inner) => match &**inner {
-            XrossType::String => (
-                quote! { *mut std::ffi::c_char },
-                quote! {
-                    match #inner_call {
-                        Some(s) => std::ffi::CString::new(s).unwrap_or_default().into_raw(),
-                        None => std::ptr::null_mut(),
-                    }
-                },
-            ),
-            XrossType::Object { .. } => (
-                quote! { *mut std::ffi::c_void },
-                quote! {
-                    match #inner_call {
-                        Some(val) => Box::into_raw(Box::new(val)) as *mut std::ffi::c_void,
-                        None => std::ptr::null_mut(),
-                    }
-                },
-            ),
-            XrossType::F32 | XrossType::F64 => (
-                quote! { *mut std::ffi::c_void },
-                quote! {
-                    match #inner_call {
-                        Some(val) => val.to_bits() as usize as *mut std::ffi::c_void,
-                        None => std::ptr::null_mut(),
-                    }
-                },
-            ),
-            _ => (
-                quote! { *mut std::ffi::c_void },
-                quote! {
-                    match #inner_call {
-                        Some(val) => val as usize as *mut std::ffi::c_void,
-                        None => std::ptr::null_mut(),
-                    }
-                },
-            ),
-        },
-        XrossType::Result { ok, err } => {
-            let ok_ptr_logic = gen_single_value_to_ptr(ok, quote! { val });
-            let err_ptr_logic = gen_single_value_to_ptr(err, quote! { e });
-            (
-                quote! { xross_core::XrossResult },
-                quote! {
-                    match #inner_call {
-                        Ok(val) => xross_core::XrossResult { is_ok: true, ptr: #ok_ptr_logic },
-                        Err(e) => xross_core::XrossResult { is_ok: false, ptr: #err_ptr_logic },
-                    }
-                },
-            )
+        XrossType::Option(_) => (
+            quote! { *mut std::ffi::c_void },
+            quote! { xross_core::abi::option_into_ptr(#inner_call) },
+        ),
+        XrossType::Result { .. } => {
+            (quote! { xross_core::XrossResult }, quote! { xross_core::abi::result(#inner_call) })
         }
         _ => {
             if let ReturnType::Type(_, ty) = sig_output {
@@ -17,6 +17,162 @@ pub fn gen_field_layout_spec(
     }
 }
 
+/// Generates the `XrossFieldLayout` of a single field.
+pub fn gen_field_descriptor(
+    type_ident: &syn::Ident,
+    field_access: TokenStream,
+    field_name: &str,
+    field_ty: &syn::Type,
+) -> TokenStream {
+    quote! {
+        xross_core::XrossFieldLayout {
+            name: #field_name.to_string(),
+            offset: std::mem::offset_of!(#type_ident, #field_access) as u64,
+            size: std::mem::size_of::<#field_ty>() as u64,
+            align: std::mem::align_of::<#field_ty>() as u64,
+            type_name: std::any::type_name::<#field_ty>().to_string(),
+        }
+    }
+}
+
+/// Generates an `XrossLayout` of a struct from its field descriptors.
+pub fn gen_struct_descriptor(type_ident: &syn::Ident, fields: &[TokenStream]) -> TokenStream {
+    quote! {
+        xross_core::XrossLayout {
+            size: std::mem::size_of::<#type_ident>() as u64,
+            align: std::mem::align_of::<#type_ident>() as u64,
+            fields: vec![#(#fields),*],
+            tag: None,
+            variants: Vec::new(),
+        }
+    }
+}
+
+/// Generates an `XrossLayout` of an enum from its tag and `XrossVariantLayout`s.
+pub fn gen_enum_descriptor(
+    type_ident: &syn::Ident,
+    tag: TokenStream,
+    variants: &[TokenStream],
+) -> TokenStream {
+    quote! {
+        xross_core::XrossLayout {
+            size: std::mem::size_of::<#type_ident>() as u64,
+            align: std::mem::align_of::<#type_ident>() as u64,
+            fields: Vec::new(),
+            tag: #tag,
+            variants: vec![#(#variants),*],
+        }
+    }
+}
+
+/// Generates an `XrossVariantLayout`. `discriminant` is an `Option<i64>` expression.
+pub fn gen_variant_descriptor(
+    name: &str,
+    discriminant: TokenStream,
+    fields: &[TokenStream],
+) -> TokenStream {
+    quote! {
+        xross_core::XrossVariantLayout {
+            name: #name.to_string(),
+            discriminant: #discriminant,
+            fields: vec![#(#fields),*],
+        }
+    }
+}
+
+/// Returns the integer type that `#[repr(..)]` gives the discriminant, if any.
+/// `repr(C)` alone uses a C `int`.
+fn repr_tag_type(attrs: &[syn::Attribute]) -> Option<TokenStream> {
+    const INTS: [&str; 12] =
+        ["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128", "usize", "isize"];
+    let mut tag = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                if INTS.contains(&ident.to_string().as_str()) {
+                    tag = Some(quote! { #ident });
+                } else if ident == "C" && tag.is_none() {
+                    tag = Some(quote! { std::ffi::c_int });
+                }
+            }
+            Ok(())
+        });
+    }
+    tag
+}
+
+/// Generates the `XrossLayout` of an enum declared with `#[derive(XrossClass)]`.
+/// The tag is reported at offset 0 when `#[repr]` fixes it. Discriminants are known for
+/// enums with only unit variants and for `#[repr]` enums.
+pub fn generate_enum_descriptor(e: &syn::ItemEnum) -> TokenStream {
+    let name = &e.ident;
+    let tag_ty = repr_tag_type(&e.attrs);
+    let unit_only = e.variants.iter().all(|v| matches!(v.fields, syn::Fields::Unit));
+    let mut next = quote! { 0i64 };
+    let mut variants = Vec::new();
+    for v in &e.variants {
+        let v_ident = &v.ident;
+        let discriminant = if let Some((_, expr)) = &v.discriminant {
+            quote! { ((#expr) as i64) }
+        } else {
+            next.clone()
+        };
+        next = quote! { (#discriminant + 1) };
+        let discriminant = if unit_only {
+            quote! { Some(#name::#v_ident as i64) }
+        } else if tag_ty.is_some() {
+            quote! { Some(#discriminant) }
+        } else {
+            quote! { None }
+        };
+        let fields: Vec<TokenStream> = v
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let display = field
+                    .ident
+                    .as_ref()
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| crate::utils::ordinal_name(i));
+                let access = match &field.ident {
+                    Some(ident) => quote! { #v_ident . #ident },
+                    None => {
+                        let index = syn::Index::from(i);
+                        quote! { #v_ident . #index }
+                    }
+                };
+                gen_field_descriptor(name, access, &display, &field.ty)
+            })
+            .collect();
+        variants.push(gen_variant_descriptor(&v_ident.to_string(), discriminant, &fields));
+    }
+    let tag = match tag_ty {
+        Some(ty) => quote! {
+            Some(xross_core::XrossTagLayout { offset: 0, size: std::mem::size_of::<#ty>() as u64 })
+        },
+        None => quote! { None },
+    };
+    gen_enum_descriptor(name, tag, &variants)
+}
+
+/// Generates the `XrossLayout` of a struct declared with `#[derive(XrossClass)]`.
+pub fn generate_struct_descriptor(s: &syn::ItemStruct) -> TokenStream {
+    let name = &s.ident;
+    let fields: Vec<TokenStream> = match &s.fields {
+        syn::Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let f_name = field.ident.as_ref().unwrap();
+                gen_field_descriptor(name, quote! { #f_name }, &f_name.to_string(), &field.ty)
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    gen_struct_descriptor(name, &fields)
+}
+
 /// Generates the layout metadata logic for a struct.
 pub fn generate_struct_layout(s: &syn::ItemStruct) -> TokenStream {
     let name = &s.ident;
@@ -2,12 +2,13 @@ use crate::codegen::ffi::{
     gen_arg_conversion, gen_panic_error_arm, gen_receiver_logic, gen_ret_wrapping,
 };
 use crate::utils::extract_safety_attr;
-use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{ToTokens, format_ident, quote};
 use syn::punctuated::Punctuated;
-use syn::{FnArg, Pat, ReturnType};
+use syn::{CapturedParam, FnArg, Pat, ReturnType, TypeParamBound};
 use xross_metadata::{
-    HandleMode, Ownership, ThreadSafety, XrossField, XrossMethod, XrossMethodType, XrossType,
+    ConcurrencyLimit, HandleMode, Ownership, ThreadSafety, XrossField, XrossMethod,
+    XrossMethodType, XrossType,
 };
 
 /// Data container for FFI method generation.
@@ -16,6 +17,11 @@ pub struct MethodFfiData {
     pub export_ident: syn::Ident,
     pub method_type: XrossMethodType,
     pub is_async: bool,
+    pub concurrency: Option<ConcurrencyLimit>,
+    pub timeout_ms: Option<u64>,
+    pub contiguous: bool,
+    /// Constructors return a handle even for by-value structs: the binding's object owns it.
+    pub is_constructor: bool,
     pub args_meta: Vec<XrossField>,
     pub c_args: Vec<TokenStream>,
     pub call_args: Vec<TokenStream>,
@@ -31,12 +37,53 @@ impl MethodFfiData {
             export_ident,
             method_type: XrossMethodType::Static,
             is_async: false,
+            concurrency: None,
+            timeout_ms: None,
+            contiguous: false,
+            is_constructor: false,
             args_meta: Vec::new(),
             c_args: Vec::new(),
             call_args: Vec::new(),
             conversion_logic: Vec::new(),
         }
     }
+
+    /// Appends the per-call `timeout_ms` argument of exports declared with `timeout_ms`.
+    /// Must be called after the regular arguments have been processed.
+    pub fn push_timeout_arg(&mut self) {
+        if self.timeout_ms.is_none() {
+            return;
+        }
+        self.args_meta.push(XrossField {
+            name: "timeout_ms".to_string(),
+            ty: XrossType::U64,
+            safety: ThreadSafety::Lock,
+            docs: vec!["Per-call timeout in milliseconds. 0 uses the declared timeout.".into()],
+        });
+        self.c_args.push(quote! { timeout_ms: u64 });
+    }
+
+    /// Applies the `contiguous` option found at `span`, reporting it on exports that do not
+    /// return a `Vec` or slice of owned objects synchronously.
+    pub fn set_contiguous(
+        &mut self,
+        span: Option<proc_macro2::Span>,
+        ret_ty: &XrossType,
+        handle_mode: HandleMode,
+    ) {
+        let Some(span) = span else { return };
+        let is_objects = matches!(ret_ty, XrossType::Vec(inner) | XrossType::Slice(inner)
+            if matches!(&**inner, XrossType::Object { ownership: Ownership::Owned, .. }));
+        if !is_objects || self.is_async || handle_mode == HandleMode::Blocking {
+            crate::utils::emit_error(syn::Error::new(
+                span,
+                "`contiguous` is only supported on synchronous exports returning a `Vec` or \
+                 slice of objects",
+            ));
+            return;
+        }
+        self.contiguous = true;
+    }
 }
 
 /// Builds a full type signature.
@@ -44,6 +91,23 @@ pub fn build_signature(package: &str, name: &str) -> String {
     if package.is_empty() { name.to_string() } else { format!("{}.{}", package, name) }
 }
 
+/// Builds the object type of a class defined by the crate being compiled.
+pub fn build_self_object(package: &str, name: &str, ownership: Ownership) -> XrossType {
+    let crate_name = crate::utils::get_crate_name();
+    XrossType::Object {
+        signature: build_signature(package, name),
+        ownership,
+        symbol_prefix: Some(crate::utils::build_symbol_base(&crate_name, package, name)),
+        crate_name: Some(crate_name),
+    }
+}
+
+/// Returns true if the export hands out an `XrossTask` (async functions and blocking offloads).
+/// Sequence returns are excluded: their handle is returned synchronously.
+pub fn is_task_export(is_async: bool, handle_mode: HandleMode, ret_ty: &XrossType) -> bool {
+    !ret_ty.is_sequence() && (is_async || handle_mode == HandleMode::Blocking)
+}
+
 /// Generates the actual FFI wrapper function.
 pub fn write_ffi_function(
     ffi_data: &MethodFfiData,
@@ -53,12 +117,34 @@ pub fn write_ffi_function(
     handle_mode: HandleMode,
     toks: &mut Vec<TokenStream>,
 ) {
+    if ret_ty.is_sequence() {
+        write_sequence_ffi_function(ffi_data, ret_ty, sig_output, inner_call, toks);
+        return;
+    }
+
     if ffi_data.is_async {
-        write_async_ffi_function(ffi_data, ret_ty, sig_output, inner_call, toks);
+        write_async_ffi_function(ffi_data, sig_output, inner_call, toks);
+        return;
+    }
+
+    if handle_mode == HandleMode::Blocking {
+        write_blocking_ffi_function(ffi_data, inner_call, toks);
         return;
     }
 
-    let (c_ret_type, wrapper_body) = gen_ret_wrapping(ret_ty, sig_output, inner_call);
+    let (c_ret_type, wrapper_body) = if ffi_data.contiguous {
+        (
+            quote! { xross_core::XrossBuffer },
+            quote! { xross_core::abi::buffer_from_contiguous(#inner_call) },
+        )
+    } else if ffi_data.is_constructor {
+        (
+            quote! { *mut std::ffi::c_void },
+            quote! { xross_core::abi::XrossPtr::into_xross_ptr(#inner_call) },
+        )
+    } else {
+        gen_ret_wrapping(ret_ty, sig_output, inner_call)
+    };
     let export_ident = &ffi_data.export_ident;
     let c_args = &ffi_data.c_args;
     let conv_logic = &ffi_data.conversion_logic;
@@ -72,30 +158,8 @@ pub fn write_ffi_function(
         let success_return = if is_already_result {
             quote! { val }
         } else {
-            // val is already the FFI-wrapped type (e.g., *mut c_char, i32, etc.)
-            // We need to cast it to *mut c_void for XrossResult.ptr
-            let ptr_val = match ret_ty {
-                XrossType::Void => quote! { std::ptr::null_mut() },
-                XrossType::F32 => quote! { val.to_bits() as usize as *mut std::ffi::c_void },
-                XrossType::F64 => quote! { val.to_bits() as usize as *mut std::ffi::c_void },
-                XrossType::I8
-                | XrossType::I16
-                | XrossType::I32
-                | XrossType::I64
-                | XrossType::U16
-                | XrossType::ISize
-                | XrossType::USize
-                | XrossType::Bool => {
-                    quote! { val as usize as *mut std::ffi::c_void }
-                }
-                XrossType::String | XrossType::Vec(_) | XrossType::Slice(_) => {
-                    quote! { Box::into_raw(Box::new(val)) as *mut std::ffi::c_void }
-                }
-                _ => quote! { val as *mut std::ffi::c_void },
-            };
-            quote! {
-                xross_core::XrossResult { is_ok: true, ptr: #ptr_val }
-            }
+            // val is already the FFI-wrapped type (e.g., XrossBuffer, *mut c_void, i32).
+            quote! { xross_core::abi::ok(val) }
         };
 
         let error_arm = gen_panic_error_arm("");
@@ -162,6 +226,7 @@ pub fn process_method_args(
                     "arg".into()
                 };
                 let arg_ident = format_ident!("{}", arg_name);
+                crate::utils::validate_attrs(&pat_type.attrs);
                 let xross_ty = crate::types::resolver::resolve_type_with_attr(
                     &pat_type.ty,
                     &pat_type.attrs,
@@ -203,11 +268,11 @@ pub fn add_clone_method(
         is_default: false,
         is_raw: false,
         is_async: false,
+        concurrency: None,
+        timeout_ms: None,
+        contiguous: false,
         args: vec![],
-        ret: XrossType::Object {
-            signature: build_signature(package, name),
-            ownership: Ownership::Owned,
-        },
+        ret: build_self_object(package, name, Ownership::Owned),
         safety: ThreadSafety::Lock,
         docs: vec!["Creates a clone of the native object.".to_string()],
     });
@@ -223,6 +288,9 @@ pub fn add_drop_method(methods: &mut Vec<XrossMethod>, symbol_base: &str, handle
         is_default: false,
         is_raw: false,
         is_async: false,
+        concurrency: None,
+        timeout_ms: None,
+        contiguous: false,
         args: vec![],
         ret: XrossType::Void,
         safety: ThreadSafety::Lock,
@@ -230,9 +298,27 @@ pub fn add_drop_method(methods: &mut Vec<XrossMethod>, symbol_base: &str, handle
     });
 }
 
+/// Generates the `fn(T) -> XrossResult` mapper completing an `XrossTask` or producing the
+/// items of a sequence.
+fn gen_result_mapper() -> TokenStream {
+    quote! { xross_core::abi::ok }
+}
+
+/// Generates the `Option<Duration>` of an export: the per-call `timeout_ms` argument, or the
+/// declared default when it is 0.
+fn gen_timeout(ffi_data: &MethodFfiData) -> TokenStream {
+    match ffi_data.timeout_ms {
+        Some(default_ms) => quote! {
+            Some(std::time::Duration::from_millis(
+                if timeout_ms == 0 { #default_ms } else { timeout_ms }
+            ))
+        },
+        None => quote! { None },
+    }
+}
+
 pub fn write_async_ffi_function(
     ffi_data: &MethodFfiData,
-    ret_ty: &XrossType,
     _sig_output: &ReturnType,
     inner_call: TokenStream,
     toks: &mut Vec<TokenStream>,
@@ -241,22 +327,198 @@ pub fn write_async_ffi_function(
     let c_args = &ffi_data.c_args;
     let conv_logic = &ffi_data.conversion_logic;
 
-    let res_mapper = match ret_ty {
-        XrossType::Void => {
-            quote! { |_| xross_core::XrossResult { is_ok: true, ptr: std::ptr::null_mut() } }
+    let res_mapper = gen_result_mapper();
+
+    let spawn = if ffi_data.concurrency.is_none() && ffi_data.timeout_ms.is_none() {
+        quote! { let task = xross_core::xross_spawn_task(#inner_call, #res_mapper); }
+    } else {
+        let (limiter_decl, limiter) = match ffi_data.concurrency {
+            Some(limit) => {
+                let max = limit.max_concurrency as usize;
+                let queue = match limit.queue {
+                    Some(q) => quote! { Some(#q as usize) },
+                    None => quote! { None },
+                };
+                (
+                    quote! {
+                        static LIMITER: xross_core::XrossLimiter =
+                            xross_core::XrossLimiter::new(#max, #queue);
+                    },
+                    quote! { Some(&LIMITER) },
+                )
+            }
+            None => (quote! {}, quote! { None }),
+        };
+        let timeout = gen_timeout(ffi_data);
+        quote! {
+            #limiter_decl
+            let task = xross_core::xross_spawn_task_ext(#inner_call, #res_mapper, #limiter, #timeout);
         }
-        _ => {
-            let ptr_logic = super::conversion::gen_single_value_to_ptr(ret_ty, quote! { val });
-            quote! { |val| xross_core::XrossResult { is_ok: true, ptr: #ptr_logic } }
+    };
+
+    toks.push(quote! {
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn #export_ident(out: *mut xross_core::XrossTask, #(#c_args),*) {
+            #(#conv_logic)*
+            #spawn
+            unsafe { std::ptr::write(out, task) };
         }
+    });
+}
+
+/// Generates an FFI wrapper that runs a synchronous body on the blocking pool.
+/// Arguments are converted on the calling thread; the call itself runs on a worker, unless
+/// its timeout has expired by the time a worker picks it up.
+pub fn write_blocking_ffi_function(
+    ffi_data: &MethodFfiData,
+    inner_call: TokenStream,
+    toks: &mut Vec<TokenStream>,
+) {
+    let export_ident = &ffi_data.export_ident;
+    let c_args = &ffi_data.c_args;
+    let conv_logic = &ffi_data.conversion_logic;
+    let res_mapper = gen_result_mapper();
+    let spawn = match ffi_data.timeout_ms {
+        Some(_) => {
+            let timeout = gen_timeout(ffi_data);
+            quote! { xross_core::xross_spawn_blocking_task_ext(move || job.run(), #res_mapper, #timeout) }
+        }
+        None => quote! { xross_core::xross_spawn_blocking_task(move || job.run(), #res_mapper) },
     };
 
     toks.push(quote! {
         #[unsafe(no_mangle)]
         pub unsafe extern "C" fn #export_ident(out: *mut xross_core::XrossTask, #(#c_args),*) {
             #(#conv_logic)*
-            let task = xross_core::xross_spawn_task(#inner_call, #res_mapper);
+            // The bindings keep the receiver alive and locked until `drop_fn` returns, which
+            // waits for a started job to finish.
+            let job = unsafe { xross_core::XrossSendJob::new(move || #inner_call) };
+            let task = #spawn;
             unsafe { std::ptr::write(out, task) };
         }
     });
 }
+
+/// Reports an `impl Iterator` / `impl Stream` return that may borrow from the call.
+/// The handle outlives the FFI call, so the sequence must own its data: a non-`'static`
+/// bound is rejected, and borrowed inputs (`&self`, references, lifetime parameters) are only
+/// accepted with a `+ 'static` or lifetime-free `use<..>` bound.
+pub fn check_sequence_lifetime(sig: &syn::Signature, ret_ty: &XrossType) {
+    if !ret_ty.is_sequence() {
+        return;
+    }
+    let ReturnType::Type(_, ty) = &sig.output else { return };
+    let syn::Type::ImplTrait(it) = &**ty else { return };
+
+    let mut is_static = false;
+    for bound in &it.bounds {
+        let borrowed = match bound {
+            TypeParamBound::Lifetime(lt) if lt.ident == "static" => {
+                is_static = true;
+                None
+            }
+            TypeParamBound::Lifetime(lt) => Some(lt.to_token_stream()),
+            TypeParamBound::PreciseCapture(capture) => {
+                is_static = true;
+                capture
+                    .params
+                    .iter()
+                    .find(|p| matches!(p, CapturedParam::Lifetime(lt) if lt.ident != "static"))
+                    .map(|p| p.to_token_stream())
+            }
+            _ => None,
+        };
+        if let Some(lifetime) = borrowed {
+            crate::utils::emit_error(syn::Error::new_spanned(
+                lifetime,
+                "a returned iterator or stream outlives the call and cannot borrow; \
+                 return an owned sequence (e.g. `collect::<Vec<_>>().into_iter()`) and bound it \
+                 with `+ 'static`",
+            ));
+            return;
+        }
+    }
+
+    let borrows_input = sig.generics.lifetimes().next().is_some()
+        || sig.inputs.iter().any(|input| match input {
+            FnArg::Receiver(receiver) => receiver.reference.is_some(),
+            FnArg::Typed(pat_type) => has_borrow(pat_type.ty.to_token_stream()),
+        });
+    if borrows_input && !is_static {
+        crate::utils::emit_error(syn::Error::new_spanned(
+            ty,
+            "this iterator or stream may borrow from the arguments, which are released when \
+             the call returns; return an owned sequence and bound it with `+ 'static`",
+        ));
+    }
+}
+
+/// Returns true if the tokens contain a reference or a lifetime.
+fn has_borrow(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Punct(p) => p.as_char() == '&' || p.as_char() == '\'',
+        TokenTree::Group(g) => has_borrow(g.stream()),
+        _ => false,
+    })
+}
+
+/// Generates an FFI wrapper that hands out an owned `XrossIterator` / `XrossStream` handle.
+/// `async fn` returning a stream is awaited on the runtime before its items are forwarded.
+pub fn write_sequence_ffi_function(
+    ffi_data: &MethodFfiData,
+    ret_ty: &XrossType,
+    sig_output: &ReturnType,
+    inner_call: TokenStream,
+    toks: &mut Vec<TokenStream>,
+) {
+    let export_ident = &ffi_data.export_ident;
+    let c_args = &ffi_data.c_args;
+    let conv_logic = &ffi_data.conversion_logic;
+
+    if ffi_data.concurrency.is_some() || ffi_data.timeout_ms.is_some() {
+        crate::utils::emit_error(syn::Error::new_spanned(
+            sig_output,
+            "max_concurrency/timeout_ms are not supported on Iterator/Stream returns",
+        ));
+        return;
+    }
+
+    debug_assert!(ret_ty.is_sequence(), "write_sequence_ffi_function requires a sequence type");
+    let item_mapper = gen_result_mapper();
+
+    let (handle_ty, handle) = if let XrossType::Iterator(_) = ret_ty {
+        (
+            quote! { xross_core::XrossIterator },
+            quote! { xross_core::XrossIterator::new(#inner_call, #item_mapper) },
+        )
+    } else {
+        let is_receiver = matches!(
+            sig_output,
+            ReturnType::Type(_, ty) if matches!(&**ty, syn::Type::Path(tp)
+                if tp.path.segments.last().is_some_and(|s| s.ident == "Receiver"))
+        );
+        let stream = if is_receiver {
+            quote! { xross_core::xross_receiver_stream(s) }
+        } else {
+            quote! { s }
+        };
+        let future = if ffi_data.is_async {
+            quote! { { let fut = #inner_call; async move { let s = fut.await; #stream } } }
+        } else {
+            quote! { { let s = #inner_call; async move { #stream } } }
+        };
+        (
+            quote! { xross_core::XrossStream },
+            quote! { xross_core::xross_spawn_stream(#future, #item_mapper) },
+        )
+    };
+
+    toks.push(quote! {
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn #export_ident(out: *mut #handle_ty, #(#c_args),*) {
+            #(#conv_logic)*
+            let handle = #handle;
+            unsafe { std::ptr::write(out, handle) };
+        }
+    });
+}
@@ -56,16 +56,22 @@ pub fn generate_property_accessors(
         }
         XrossType::Option(inner) => {
             setter_args.push(quote! { _val: *mut std::ffi::c_void });
-            let ok_ptr_logic =
-                crate::codegen::ffi::gen_single_value_to_ptr(inner, quote! { v.clone() });
-            (
-                quote! { *mut std::ffi::c_void },
+            // Like a returned `Option`, except that a string is a boxed `XrossBuffer`.
+            let get_body = if matches!(&**inner, XrossType::String) {
+                let ok_ptr_logic =
+                    crate::codegen::ffi::gen_single_value_to_ptr(inner, quote! { v.clone() });
                 quote! {
                     match &_self.#field_ident {
                         Some(v) => #ok_ptr_logic,
                         None => std::ptr::null_mut(),
                     }
-                },
+                }
+            } else {
+                quote! { xross_core::abi::option_into_ptr(_self.#field_ident.clone()) }
+            };
+            (
+                quote! { *mut std::ffi::c_void },
+                get_body,
                 quote! {
                     if _val.is_null() {
                         _self.#field_ident = None;
@@ -75,20 +81,11 @@ pub fn generate_property_accessors(
                 },
             )
         }
-        XrossType::Result { ok, err } => {
+        XrossType::Result { .. } => {
             setter_args.push(quote! { _val: xross_core::XrossResult });
-            let ok_ptr_logic =
-                crate::codegen::ffi::gen_single_value_to_ptr(ok, quote! { val.clone() });
-            let err_ptr_logic =
-                crate::codegen::ffi::gen_single_value_to_ptr(err, quote! { e.clone() });
             (
                 quote! { xross_core::XrossResult },
-                quote! {
-                    match &_self.#field_ident {
-                        Ok(val) => xross_core::XrossResult { is_ok: true, ptr: #ok_ptr_logic },
-                        Err(e) => xross_core::XrossResult { is_ok: false, ptr: #err_ptr_logic },
-                    }
-                },
+                quote! { xross_core::abi::result(_self.#field_ident.clone()) },
                 quote! { /* Result setter is complex, simplified for now */ },
             )
         }
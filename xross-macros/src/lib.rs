@@ -11,14 +11,18 @@ use syn::{Item, ItemImpl, parse_macro_input};
 /// Generates metadata and FFI wrappers for a struct or enum.
 #[proc_macro_derive(XrossClass, attributes(xross_field, xross_package, xross))]
 pub fn xross_class_derive(input: TokenStream) -> TokenStream {
+    types::mapping::begin_expansion();
     let input = parse_macro_input!(input as Item);
-    macros::derive::impl_xross_class_derive(input).into()
+    // The class trait and companion macro are still emitted on errors,
+    // so `#[xross_methods]` on the type reports its own problems.
+    finish_expansion(macros::derive::impl_xross_class_derive(input))
 }
 
 /// Macro to define a class with its fields and methods in a DSL.
 #[proc_macro]
 pub fn xross_class(input: TokenStream) -> TokenStream {
-    macros::xross_class::impl_xross_class(input)
+    types::mapping::begin_expansion();
+    finish_expansion(macros::xross_class::impl_xross_class(input).into())
 }
 
 /// Attribute macro for methods within an `impl` block.
@@ -28,11 +32,30 @@ pub fn xross_methods(attr: TokenStream, item: TokenStream) -> TokenStream {
     macros::attribute::impl_xross_class_attribute(attr.into(), input_impl).into()
 }
 
+/// Second stage of `#[xross_methods]`, invoked by the companion macro of the type
+/// with the type's definition. Not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __xross_methods_impl(input: TokenStream) -> TokenStream {
+    types::mapping::begin_expansion();
+    finish_expansion(macros::attribute::class::impl_xross_methods_with_definition(input.into()))
+}
+
+/// Records a definition generated from a `cfg`-gated item, once rustc has kept the item.
+/// Not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __xross_definition(input: TokenStream) -> TokenStream {
+    types::mapping::begin_expansion();
+    finish_expansion(metadata::impl_deferred_definition(input.into()))
+}
+
 /// Attribute macro for standalone functions.
 #[proc_macro_attribute]
 pub fn xross_function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    types::mapping::begin_expansion();
     let input_fn = parse_macro_input!(item as syn::ItemFn);
-    macros::attribute::impl_xross_function_attribute(attr.into(), input_fn).into()
+    finish_expansion(macros::attribute::impl_xross_function_attribute(attr.into(), input_fn))
 }
 
 /// Attribute macro for raw methods.
@@ -44,12 +67,25 @@ pub fn xross_raw_method(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// Attribute macro for raw standalone functions.
 #[proc_macro_attribute]
 pub fn xross_raw_function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    types::mapping::begin_expansion();
     let input_fn = parse_macro_input!(item as syn::ItemFn);
-    macros::attribute::function::impl_xross_raw_function_attribute(attr.into(), input_fn).into()
+    finish_expansion(macros::attribute::function::impl_xross_raw_function_attribute(
+        attr.into(),
+        input_fn,
+    ))
 }
 
 /// Macro to define standalone functions in a DSL.
 #[proc_macro]
 pub fn xross_function_dsl(input: TokenStream) -> TokenStream {
-    macros::xross_function::impl_xross_function(input)
+    types::mapping::begin_expansion();
+    finish_expansion(macros::xross_function::impl_xross_function(input).into())
+}
+
+/// Appends the errors reported during the current expansion to `output`.
+fn finish_expansion(mut output: proc_macro2::TokenStream) -> TokenStream {
+    if let Some(err) = utils::take_errors() {
+        output.extend(err.to_compile_error());
+    }
+    output.into()
 }
@@ -1,23 +1,126 @@
 use crate::codegen::ffi::{
-    MethodFfiData, build_signature, process_method_args, resolve_return_type, write_ffi_function,
+    MethodFfiData, build_self_object, check_sequence_lifetime, is_task_export, process_method_args,
+    resolve_return_type, write_ffi_function,
 };
-use crate::metadata::{load_definition, save_definition};
+use crate::metadata::{save_definition, save_gated_definition};
 use crate::utils::*;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use syn::{FnArg, ImplItem, ItemImpl, ReturnType, Type};
+use syn::{Attribute, FnArg, ImplItem, ItemImpl, ReturnType, Type};
 use xross_metadata::{Ownership, ThreadSafety, XrossDefinition, XrossMethod};
 
-pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl) -> TokenStream {
+/// Expands `#[xross_methods]` into a call of the type's companion macro, which passes the
+/// definition generated by `#[derive(XrossClass)]` / `xross_class!` on to `__xross_methods_impl`.
+pub fn impl_xross_class_attribute(_attr: TokenStream, input_impl: ItemImpl) -> TokenStream {
+    let Type::Path(tp) = &*input_impl.self_ty else {
+        return syn::Error::new_spanned(
+            &input_impl.self_ty,
+            "xross_methods must be used on a direct type implementation",
+        )
+        .to_compile_error();
+    };
+    // The companion macro shares the type's name, so the path that names the type names it too.
+    let mut companion = tp.path.clone();
+    companion.segments.last_mut().unwrap().arguments = syn::PathArguments::None;
+
+    quote! {
+        #companion! { xross_core::__xross_methods_impl { #input_impl } }
+    }
+}
+
+/// Input of `__xross_methods_impl`: the JSON definition of the type, followed by the impl block.
+struct MethodsWithDefinition {
+    definition: syn::LitStr,
+    input_impl: ItemImpl,
+}
+
+impl syn::parse::Parse for MethodsWithDefinition {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let definition = input.parse()?;
+        input.parse::<syn::Token![;]>()?;
+        Ok(Self { definition, input_impl: input.parse()? })
+    }
+}
+
+/// Methods behind the same `cfg` attributes. Their definition is recorded separately,
+/// so the metadata only lists them when they are compiled.
+struct CfgGroup {
+    cfgs: Vec<Attribute>,
+    methods: Vec<XrossMethod>,
+    type_refs: Vec<(String, Type)>,
+}
+
+/// What a `cfg`-gated method adds to the expansion, moved behind its attributes once the
+/// method is processed.
+struct MethodGate {
+    cfgs: Vec<Attribute>,
+    first_function: usize,
+    outer_type_refs: Vec<(String, Type)>,
+}
+
+impl MethodGate {
+    fn begin(cfgs: Vec<Attribute>, extra_functions: &[TokenStream]) -> Self {
+        Self {
+            cfgs,
+            first_function: extra_functions.len(),
+            outer_type_refs: crate::types::mapping::take_type_refs(),
+        }
+    }
+
+    fn finish(
+        self,
+        extra_functions: &mut Vec<TokenStream>,
+        methods_meta: &mut Vec<XrossMethod>,
+        groups: &mut Vec<CfgGroup>,
+    ) {
+        let wrappers = extra_functions.split_off(self.first_function);
+        extra_functions.push(crate::codegen::ffi::gate_items(&self.cfgs, wrappers));
+
+        let type_refs = crate::types::mapping::take_type_refs();
+        crate::types::mapping::extend_type_refs(self.outer_type_refs);
+        let key = |cfgs: &[Attribute]| quote!(#(#cfgs)*).to_string();
+        let group = match groups.iter_mut().position(|g| key(&g.cfgs) == key(&self.cfgs)) {
+            Some(i) => &mut groups[i],
+            None => {
+                groups.push(CfgGroup { cfgs: self.cfgs, methods: vec![], type_refs: vec![] });
+                groups.last_mut().unwrap()
+            }
+        };
+        group.methods.extend(methods_meta.pop());
+        for (name, ty) in type_refs {
+            if !group.type_refs.iter().any(|(n, _)| *n == name) {
+                group.type_refs.push((name, ty));
+            }
+        }
+    }
+}
+
+pub fn impl_xross_methods_with_definition(input: TokenStream) -> TokenStream {
+    let MethodsWithDefinition { definition, mut input_impl } = match syn::parse2(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error(),
+    };
+    let mut definition: XrossDefinition = match serde_json::from_str(&definition.value()) {
+        Ok(definition) => definition,
+        Err(e) => {
+            return syn::Error::new_spanned(
+                &input_impl.self_ty,
+                format!("the XrossClass definition of this type is invalid ({}); rebuild it", e),
+            )
+            .to_compile_error();
+        }
+    };
+
     let type_name_ident = if let Type::Path(tp) = &*input_impl.self_ty {
-        &tp.path.segments.last().unwrap().ident
+        tp.path.segments.last().unwrap().ident.clone()
     } else {
-        panic!("xross_methods must be used on a direct type implementation");
+        return syn::Error::new_spanned(
+            &input_impl.self_ty,
+            "xross_methods must be used on a direct type implementation",
+        )
+        .to_compile_error();
     };
-
-    let mut definition = load_definition(type_name_ident).expect(
-        "XrossClass definition not found. Apply #[derive(XrossClass)] or xross_class! first.",
-    );
+    let type_name_ident = &type_name_ident;
 
     let (package_name, symbol_base) = match &definition {
         XrossDefinition::Struct(s) => (s.package_name.clone(), s.symbol_prefix.clone()),
@@ -28,6 +131,8 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
 
     let mut extra_functions = Vec::new();
     let mut methods_meta = Vec::new();
+    let mut cfg_groups = Vec::new();
+    let impl_cfgs = extract_cfg_attrs(&input_impl.attrs);
 
     for item in &mut input_impl.items {
         if let ImplItem::Fn(method) = item {
@@ -37,7 +142,12 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
             let mut is_raw = false;
             let mut raw_input: Option<super::raw::RawAttrInput> = None;
 
+            validate_attrs(&method.attrs);
             let mut handle_mode = extract_handle_mode(&method.attrs);
+            let task_options = extract_task_options(&method.attrs);
+            let contiguous = extract_contiguous(&method.attrs);
+            // Read before the `retain` below strips `#[xross_method(...)]`.
+            let safety = extract_safety_attr(&method.attrs, ThreadSafety::Lock);
 
             method.attrs.retain(|attr| {
                 if attr.path().is_ident("xross_new") {
@@ -52,15 +162,17 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
                     false
                 } else if attr.path().is_ident("xross_raw_method") {
                     is_raw = true;
-                    if let syn::Meta::List(list) = &attr.meta {
-                        if let Ok(input) =
-                            syn::parse2::<super::raw::RawAttrInput>(list.tokens.clone())
-                        {
+                    let parsed = attr.meta.require_list().and_then(|list| {
+                        syn::parse2::<super::raw::RawAttrInput>(list.tokens.clone())
+                    });
+                    match parsed {
+                        Ok(input) => {
                             if input.handle_mode != xross_metadata::HandleMode::Normal {
                                 handle_mode = input.handle_mode;
                             }
                             raw_input = Some(input);
                         }
+                        Err(e) => emit_error(e),
                     }
                     false
                 } else {
@@ -71,11 +183,17 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
             if !is_new && !is_method && !is_raw {
                 continue;
             }
+            let cfgs: Vec<_> =
+                impl_cfgs.iter().cloned().chain(extract_cfg_attrs(&method.attrs)).collect();
+            let gate = (!cfgs.is_empty()).then(|| MethodGate::begin(cfgs, &extra_functions));
 
             let rust_fn_name = &method.sig.ident;
             let is_async = method.sig.asyncness.is_some();
             let mut ffi_data = MethodFfiData::new(&symbol_base, rust_fn_name);
             ffi_data.is_async = is_async;
+            task_options.check_export(is_async, handle_mode, "methods");
+            ffi_data.concurrency = task_options.concurrency();
+            ffi_data.timeout_ms = task_options.timeout_ms;
 
             if is_raw && let Some(raw) = &raw_input {
                 // For raw methods, we use the signature from the attribute for metadata
@@ -105,10 +223,15 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
                         ffi_data.args_meta.push(xross_metadata::XrossField {
                             name: arg_name,
                             ty: xross_ty,
-                            safety: extract_safety_attr(&method.attrs, ThreadSafety::Lock),
+                            safety,
                             docs: vec![],
                         });
-                        let arg_id = format_ident!("{}", ffi_data.args_meta.last().unwrap().name);
+                        // Reuse the user's ident: this code is expanded through the companion
+                        // macro, so a fresh `call_site` ident would not see the body's bindings.
+                        let arg_id = match &*pat_type.pat {
+                            syn::Pat::Ident(id) => id.ident.clone(),
+                            _ => format_ident!("arg"),
+                        };
                         let arg_ty = &pat_type.ty;
                         ffi_data.c_args.push(quote!(#arg_id: #arg_ty));
                     }
@@ -127,11 +250,14 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
                     symbol: ffi_data.symbol_name.clone(),
                     method_type: ffi_data.method_type,
                     handle_mode,
-                    safety: extract_safety_attr(&method.attrs, ThreadSafety::Lock),
+                    safety,
                     is_constructor: false,
                     is_default: false,
                     is_raw: true,
                     is_async,
+                    concurrency: None,
+                    timeout_ms: None,
+                    contiguous: false,
                     args: ffi_data.args_meta.clone(),
                     ret: ret_ty.clone(),
                     docs: extract_docs(&method.attrs),
@@ -246,16 +372,17 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
                         }
                     });
                 }
+                if let Some(gate) = gate {
+                    gate.finish(&mut extra_functions, &mut methods_meta, &mut cfg_groups);
+                }
                 continue;
             }
 
             process_method_args(&method.sig.inputs, &package_name, type_name_ident, &mut ffi_data);
+            ffi_data.push_timeout_arg();
 
             let ret_ty = if is_new {
-                xross_metadata::XrossType::Object {
-                    signature: build_signature(&package_name, &type_name_ident.to_string()),
-                    ownership: Ownership::Owned,
-                }
+                build_self_object(&package_name, &type_name_ident.to_string(), Ownership::Owned)
             } else {
                 resolve_return_type(
                     &method.sig.output,
@@ -264,17 +391,22 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
                     type_name_ident,
                 )
             };
+            ffi_data.set_contiguous(contiguous, &ret_ty, handle_mode);
+            ffi_data.is_constructor = is_new;
 
             methods_meta.push(XrossMethod {
                 name: rust_fn_name.to_string(),
                 symbol: ffi_data.symbol_name.clone(),
                 method_type: ffi_data.method_type,
                 handle_mode,
-                safety: extract_safety_attr(&method.attrs, ThreadSafety::Lock),
+                safety,
                 is_constructor: is_new,
                 is_default,
                 is_raw: false,
-                is_async,
+                is_async: is_task_export(is_async, handle_mode, &ret_ty),
+                concurrency: ffi_data.concurrency,
+                timeout_ms: ffi_data.timeout_ms,
+                contiguous: ffi_data.contiguous,
                 args: ffi_data.args_meta.clone(),
                 ret: ret_ty.clone(),
                 docs: extract_docs(&method.attrs),
@@ -282,28 +414,49 @@ pub fn impl_xross_class_attribute(_attr: TokenStream, mut input_impl: ItemImpl)
 
             let call_args = &ffi_data.call_args;
             let inner_call = quote! { #type_name_ident::#rust_fn_name(#(#call_args),*) };
+            check_sequence_lifetime(&method.sig, &ret_ty);
             write_ffi_function(
                 &ffi_data,
                 &ret_ty,
-                &method.sig.output,
+                &crate::utils::replace_self(&method.sig.output, type_name_ident),
                 inner_call,
                 handle_mode,
                 &mut extra_functions,
             );
+            if let Some(gate) = gate {
+                gate.finish(&mut extra_functions, &mut methods_meta, &mut cfg_groups);
+            }
         }
     }
 
+    let class_definition = definition.clone();
     match &mut definition {
         XrossDefinition::Struct(s) => s.methods.extend(methods_meta),
         XrossDefinition::Enum(e) => e.methods.extend(methods_meta),
         XrossDefinition::Opaque(o) => o.methods.extend(methods_meta),
         XrossDefinition::Function(_f) => {
             if !methods_meta.is_empty() {
-                panic!("Cannot add methods to a standalone function definition.");
+                emit_error(syn::Error::new_spanned(
+                    &input_impl.self_ty,
+                    "Cannot add methods to a standalone function definition.",
+                ));
             }
         }
     }
 
-    save_definition(&definition);
+    extra_functions.push(save_definition(&definition, None));
+    for group in cfg_groups {
+        let mut gated = class_definition.clone();
+        if let Some(methods) = gated.methods_mut() {
+            methods.extend(group.methods);
+        }
+        crate::types::mapping::extend_type_refs(group.type_refs);
+        extra_functions.push(save_gated_definition(&gated, &group.cfgs));
+    }
+    // Only the impl is kept when the expansion reported errors; the wrappers would add noise.
+    if let Some(err) = crate::utils::take_errors() {
+        let err = err.to_compile_error();
+        return quote! { #err #input_impl };
+    }
     quote! { #(#extra_functions)* #input_impl }
 }
@@ -1,5 +1,6 @@
 use crate::codegen::ffi::{
-    MethodFfiData, process_method_args, resolve_return_type, write_ffi_function,
+    MethodFfiData, check_sequence_lifetime, process_method_args, resolve_return_type,
+    write_ffi_function,
 };
 use crate::utils::*;
 use proc_macro2::TokenStream;
@@ -25,29 +26,42 @@ fn impl_xross_function_attribute_ext(
     let mut handle_mode = None;
     let mut safety = None;
     let mut is_raw = force_raw;
+    let mut task_options = crate::utils::TaskOptions::default();
+    let mut contiguous = None;
     let mut raw_input: Option<super::raw::RawAttrInput> = None;
 
     if is_raw {
-        if let Ok(input) = syn::parse2::<super::raw::RawAttrInput>(attr.clone()) {
-            if input.handle_mode != xross_metadata::HandleMode::Normal {
-                handle_mode = Some(input.handle_mode);
+        match syn::parse2::<super::raw::RawAttrInput>(attr.clone()) {
+            Ok(input) => {
+                if input.handle_mode != xross_metadata::HandleMode::Normal {
+                    handle_mode = Some(input.handle_mode);
+                }
+                raw_input = Some(input);
+            }
+            Err(e) => {
+                let err = e.to_compile_error();
+                return quote! { #err #input_fn };
             }
-            raw_input = Some(input);
         }
     }
 
+    let mut raw_error = None;
     input_fn.attrs.retain(|attr| {
         if attr.path().is_ident("xross_raw_function") {
             if !is_raw {
                 is_raw = true;
-                if let syn::Meta::List(list) = &attr.meta {
-                    if let Ok(input) = syn::parse2::<super::raw::RawAttrInput>(list.tokens.clone())
-                    {
+                let parsed = attr
+                    .meta
+                    .require_list()
+                    .and_then(|list| syn::parse2::<super::raw::RawAttrInput>(list.tokens.clone()));
+                match parsed {
+                    Ok(input) => {
                         if input.handle_mode != xross_metadata::HandleMode::Normal {
                             handle_mode = Some(input.handle_mode);
                         }
                         raw_input = Some(input);
                     }
+                    Err(e) => raw_error = Some(e),
                 }
             }
             false
@@ -55,36 +69,43 @@ fn impl_xross_function_attribute_ext(
             true
         }
     });
+    if let Some(e) = raw_error {
+        let err = e.to_compile_error();
+        return quote! { #err #input_fn };
+    }
 
     if !attr.is_empty() {
         let res = syn::meta::parser(|meta| {
             if meta.path.is_ident("package") {
                 let value = meta.value()?;
-                if let Ok(lit) = value.parse::<syn::LitStr>() {
-                    package_name = lit.value();
-                } else if let Ok(id) = value.parse::<syn::Ident>() {
-                    package_name = id.to_string();
+                if value.peek(syn::LitStr) {
+                    package_name = value.parse::<syn::LitStr>()?.value();
+                } else {
+                    package_name = value.parse::<syn::Ident>()?.to_string();
                 }
             } else if meta.path.is_ident("critical") {
                 let allow_heap_access = crate::utils::parse_critical_nested(&meta)?;
                 handle_mode = Some(xross_metadata::HandleMode::Critical { allow_heap_access });
             } else if meta.path.is_ident("panicable") {
                 handle_mode = Some(xross_metadata::HandleMode::Panicable);
+            } else if meta.path.is_ident("blocking") {
+                handle_mode = Some(xross_metadata::HandleMode::Blocking);
             } else if meta.path.is_ident("safety") {
-                let value = meta.value()?.parse::<syn::Ident>()?;
-                safety = match value.to_string().as_str() {
-                    "Unsafe" => Some(ThreadSafety::Unsafe),
-                    "Atomic" => Some(ThreadSafety::Atomic),
-                    "Immutable" => Some(ThreadSafety::Immutable),
-                    "Lock" => Some(ThreadSafety::Lock),
-                    _ => None,
-                };
+                safety = Some(crate::utils::parse_safety_value(&meta)?);
+            } else if meta.path.is_ident("contiguous") {
+                contiguous = Some(syn::spanned::Spanned::span(&meta.path));
+            } else if !task_options.parse_nested(&meta)? {
+                return Err(meta.error(
+                    "unknown `xross_function` option; expected one of: package, critical, \
+                     panicable, blocking, safety, max_concurrency, queue, timeout_ms, contiguous",
+                ));
             }
             Ok(())
         })
         .parse2(attr);
         if let Err(e) = res {
-            panic!("Failed to parse xross_function attributes: {}", e);
+            let err = e.to_compile_error();
+            return quote! { #err #input_fn };
         }
     }
 
@@ -96,12 +117,17 @@ fn impl_xross_function_attribute_ext(
 
     let mut ffi_data = MethodFfiData::new(&symbol_prefix, rust_fn_name);
     ffi_data.is_async = is_async;
+    ffi_data.concurrency = task_options.concurrency();
+    ffi_data.timeout_ms = task_options.timeout_ms;
     let dummy_ident = syn::Ident::new("Global", proc_macro2::Span::call_site());
 
     let mut extra_functions = Vec::new();
     let handle_mode = handle_mode.unwrap_or_else(|| extract_handle_mode(&input_fn.attrs));
+    task_options.check_export(is_async, handle_mode, "functions");
     let safety = safety.unwrap_or_else(|| extract_safety_attr(&input_fn.attrs, ThreadSafety::Lock));
     let docs = extract_docs(&input_fn.attrs);
+    let cfgs = extract_cfg_attrs(&input_fn.attrs);
+    validate_attrs(&input_fn.attrs);
 
     if is_raw && let Some(raw) = &raw_input {
         for arg in &raw.sig_inputs {
@@ -121,7 +147,7 @@ fn impl_xross_function_attribute_ext(
                 ffi_data.args_meta.push(xross_metadata::XrossField {
                     name: arg_name,
                     ty: xross_ty,
-                    safety: safety.clone(),
+                    safety,
                     docs: vec![],
                 });
                 let arg_id = format_ident!("{}", ffi_data.args_meta.last().unwrap().name);
@@ -138,7 +164,7 @@ fn impl_xross_function_attribute_ext(
             true, // force_value
         );
 
-        crate::utils::register_xross_function_ext(
+        extra_functions.push(crate::utils::register_xross_function_ext(
             &package_name,
             &name_str,
             &ffi_data,
@@ -147,7 +173,8 @@ fn impl_xross_function_attribute_ext(
             &ret_ty,
             docs,
             true, // is_raw
-        );
+            &cfgs,
+        ));
 
         let export_ident = &ffi_data.export_ident;
         let c_args = &ffi_data.c_args;
@@ -236,15 +263,22 @@ fn impl_xross_function_attribute_ext(
             });
         }
 
-        return quote! { #(#extra_functions)* #input_fn };
+        if let Some(err) = crate::utils::take_errors() {
+            let err = err.to_compile_error();
+            return quote! { #err #input_fn };
+        }
+        let extra_functions = crate::codegen::ffi::gate_items(&cfgs, extra_functions);
+        return quote! { #extra_functions #input_fn };
     }
 
     process_method_args(&input_fn.sig.inputs, &package_name, &dummy_ident, &mut ffi_data);
+    ffi_data.push_timeout_arg();
 
     let ret_ty =
         resolve_return_type(&input_fn.sig.output, &input_fn.attrs, &package_name, &dummy_ident);
+    ffi_data.set_contiguous(contiguous, &ret_ty, handle_mode);
 
-    crate::utils::register_xross_function(
+    extra_functions.push(crate::utils::register_xross_function(
         &package_name,
         &name_str,
         &ffi_data,
@@ -252,10 +286,12 @@ fn impl_xross_function_attribute_ext(
         safety,
         &ret_ty,
         docs,
-    );
+        &cfgs,
+    ));
 
     let call_args = &ffi_data.call_args;
     let inner_call = quote! { #rust_fn_name(#(#call_args),*) };
+    check_sequence_lifetime(&input_fn.sig, &ret_ty);
     write_ffi_function(
         &ffi_data,
         &ret_ty,
@@ -265,5 +301,11 @@ fn impl_xross_function_attribute_ext(
         &mut extra_functions,
     );
 
-    quote! { #(#extra_functions)* #input_fn }
+    // Only the item is kept when the expansion reported errors; the wrappers would add noise.
+    if let Some(err) = crate::utils::take_errors() {
+        let err = err.to_compile_error();
+        return quote! { #err #input_fn };
+    }
+    let extra_functions = crate::codegen::ffi::gate_items(&cfgs, extra_functions);
+    quote! { #extra_functions #input_fn }
 }
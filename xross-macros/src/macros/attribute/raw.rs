@@ -53,10 +53,8 @@ impl Parse for RawAttrInput {
                 if input.peek(syn::token::Paren) {
                     let content;
                     syn::parenthesized!(content in input);
-                    if content.peek(heap_access) {
-                        content.parse::<heap_access>()?;
-                        allow_heap_access = true;
-                    }
+                    content.parse::<heap_access>()?;
+                    allow_heap_access = true;
                 }
                 handle_mode = xross_metadata::HandleMode::Critical { allow_heap_access };
                 if input.peek(Token![;]) {
@@ -14,7 +14,13 @@ pub fn impl_xross_class_derive(input: Item) -> TokenStream {
     let derive_toks = match input {
         Item::Struct(s) => struct_impl::impl_struct_derive(&s, &crate_name, &mut extra_functions),
         Item::Enum(e) => enum_impl::impl_enum_derive(&e, &crate_name, &mut extra_functions),
-        _ => panic!("#[derive(XrossClass)] only supports Struct and Enum"),
+        other => {
+            return syn::Error::new_spanned(
+                other,
+                "#[derive(XrossClass)] only supports Struct and Enum",
+            )
+            .to_compile_error();
+        }
     };
 
     quote::quote! {
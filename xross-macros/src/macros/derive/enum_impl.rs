@@ -1,6 +1,6 @@
 use crate::codegen::ffi::{
-    add_clone_method, add_drop_method, generate_common_ffi, generate_enum_aux_ffi,
-    generate_enum_layout,
+    add_clone_method, add_drop_method, generate_class_trait, generate_common_ffi,
+    generate_enum_aux_ffi, generate_enum_descriptor, generate_enum_layout,
 };
 use crate::metadata::save_definition;
 use crate::types::resolver::resolve_type_with_attr;
@@ -17,9 +17,11 @@ pub fn impl_enum_derive(
     let name = &e.ident;
     let name_str = name.to_string();
     let package = extract_package(&e.attrs);
+    validate_attrs(&e.attrs);
     let symbol_base = build_symbol_base(crate_name, &package, &name_str);
 
     let layout_logic = generate_enum_layout(e);
+    let layout_descriptor = generate_enum_descriptor(e);
     let is_clonable = extract_is_clonable(&e.attrs);
     let (clone_mode, drop_mode) = extract_special_modes(&e.attrs);
 
@@ -35,6 +37,7 @@ pub fn impl_enum_derive(
     for v in &e.variants {
         let v_ident = &v.ident;
         let v_str = v_ident.to_string();
+        validate_attrs(&v.attrs);
         let mut v_fields = Vec::new();
         let constructor_name = format_ident!("{}_new_{}", symbol_base, v_ident);
 
@@ -43,6 +46,7 @@ pub fn impl_enum_derive(
         let mut call_args = Vec::new();
 
         for (i, field) in v.fields.iter().enumerate() {
+            validate_attrs(&field.attrs);
             let field_name =
                 field.ident.as_ref().map(|id| id.to_string()).unwrap_or_else(|| ordinal_name(i));
             let ty = resolve_type_with_attr(&field.ty, &field.attrs, &package, Some(name));
@@ -89,7 +93,7 @@ pub fn impl_enum_derive(
         variants.push(XrossVariant { name: v_str, fields: v_fields, docs: extract_docs(&v.attrs) });
     }
 
-    save_definition(&XrossDefinition::Enum(XrossEnum {
+    let definition = XrossDefinition::Enum(XrossEnum {
         signature: if package.is_empty() {
             name_str.clone()
         } else {
@@ -102,18 +106,13 @@ pub fn impl_enum_derive(
         methods,
         docs: extract_docs(&e.attrs),
         is_copy: extract_is_copy(&e.attrs),
-    }));
-
-    let mut toks = Vec::new();
-    generate_common_ffi(
-        name,
-        &symbol_base,
-        layout_logic,
-        &mut toks,
-        is_clonable,
-        clone_mode,
-        drop_mode,
-    );
+        layout: None,
+    });
+    let embed = save_definition(&definition, Some(name));
+
+    let mut toks =
+        vec![embed, generate_class_trait(name, &definition, layout_logic, layout_descriptor)];
+    generate_common_ffi(name, &symbol_base, &mut toks, is_clonable, clone_mode, drop_mode);
 
     generate_enum_aux_ffi(name, &symbol_base, variant_name_arms, &mut toks);
     quote!(#(#toks)*)
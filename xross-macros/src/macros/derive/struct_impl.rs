@@ -1,12 +1,12 @@
 use crate::codegen::ffi::{
-    add_clone_method, add_drop_method, generate_common_ffi, generate_property_accessors,
-    generate_struct_layout,
+    add_clone_method, add_drop_method, generate_class_trait, generate_common_ffi,
+    generate_property_accessors, generate_struct_descriptor, generate_struct_layout,
 };
 use crate::metadata::save_definition;
 use crate::types::resolver::resolve_type_with_attr;
 use crate::utils::*;
 use proc_macro2::TokenStream;
-use xross_metadata::{ThreadSafety, XrossDefinition, XrossField, XrossStruct};
+use xross_metadata::{ThreadSafety, XrossDefinition, XrossField, XrossStruct, XrossType};
 
 pub fn impl_struct_derive(
     s: &syn::ItemStruct,
@@ -16,14 +16,18 @@ pub fn impl_struct_derive(
     let name = &s.ident;
     let name_str = name.to_string();
     let package = extract_package(&s.attrs);
+    validate_attrs(&s.attrs);
     let symbol_base = build_symbol_base(crate_name, &package, &name_str);
 
     let layout_logic = generate_struct_layout(s);
+    let layout_descriptor = generate_struct_descriptor(s);
     let is_clonable = extract_is_clonable(&s.attrs);
     let (clone_mode, drop_mode) = extract_special_modes(&s.attrs);
 
     let mut fields = Vec::new();
     let mut methods = Vec::new();
+    // By-value structs have no fields that are not exposed, and none that is not a scalar.
+    let mut all_scalar = matches!(&s.fields, syn::Fields::Named(f) if !f.named.is_empty());
 
     if is_clonable {
         add_clone_method(&mut methods, &symbol_base, &package, &name_str, clone_mode);
@@ -32,6 +36,7 @@ pub fn impl_struct_derive(
 
     if let syn::Fields::Named(f) = &s.fields {
         for field in &f.named {
+            validate_attrs(&field.attrs);
             let has_attr = field.attrs.iter().any(|a| {
                 a.path().is_ident("xross_field")
                     || a.path().segments.last().map(|s| s.ident == "xross_field").unwrap_or(false)
@@ -42,6 +47,7 @@ pub fn impl_struct_derive(
                 let field_name = field_ident.to_string();
                 let xross_ty =
                     resolve_type_with_attr(&field.ty, &field.attrs, &package, Some(name));
+                all_scalar &= is_by_value_field(&xross_ty);
                 fields.push(XrossField {
                     name: field_name.clone(),
                     ty: xross_ty.clone(),
@@ -57,10 +63,13 @@ pub fn impl_struct_derive(
                     &symbol_base,
                     extra_functions,
                 );
+            } else {
+                all_scalar = false;
             }
         }
     }
-    save_definition(&XrossDefinition::Struct(XrossStruct {
+    let is_copy = extract_is_copy(&s.attrs);
+    let definition = XrossDefinition::Struct(XrossStruct {
         signature: if package.is_empty() {
             name_str.clone()
         } else {
@@ -72,18 +81,34 @@ pub fn impl_struct_derive(
         fields,
         methods,
         docs: extract_docs(&s.attrs),
-        is_copy: extract_is_copy(&s.attrs),
-    }));
+        is_copy,
+        by_value: is_copy && all_scalar && extract_is_repr_c(&s.attrs),
+        layout: None,
+    });
+    let embed = save_definition(&definition, Some(name));
 
-    let mut toks = Vec::new();
-    generate_common_ffi(
-        name,
-        &symbol_base,
-        layout_logic,
-        &mut toks,
-        is_clonable,
-        clone_mode,
-        drop_mode,
-    );
+    let mut toks =
+        vec![embed, generate_class_trait(name, &definition, layout_logic, layout_descriptor)];
+    generate_common_ffi(name, &symbol_base, &mut toks, is_clonable, clone_mode, drop_mode);
     quote::quote!(#(#toks)*)
 }
+
+/// Field types a by-value struct may have: those with a fixed C counterpart.
+fn is_by_value_field(ty: &XrossType) -> bool {
+    matches!(
+        ty,
+        XrossType::Bool
+            | XrossType::I8
+            | XrossType::U8
+            | XrossType::I16
+            | XrossType::U16
+            | XrossType::I32
+            | XrossType::U32
+            | XrossType::I64
+            | XrossType::U64
+            | XrossType::ISize
+            | XrossType::USize
+            | XrossType::F32
+            | XrossType::F64
+    )
+}
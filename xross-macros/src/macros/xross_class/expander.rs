@@ -1,7 +1,9 @@
 use crate::codegen::ffi::{
-    MethodFfiData, add_clone_method, add_drop_method, build_signature, gen_field_layout_spec,
-    generate_common_ffi, generate_enum_aux_ffi, generate_property_accessors, process_method_args,
-    resolve_return_type, write_ffi_function,
+    MethodFfiData, add_clone_method, add_drop_method, build_signature, check_sequence_lifetime,
+    gen_enum_descriptor, gen_field_descriptor, gen_field_layout_spec, gen_struct_descriptor,
+    gen_variant_descriptor, generate_class_trait, generate_common_ffi, generate_enum_aux_ffi,
+    generate_property_accessors, is_task_export, process_method_args, resolve_return_type,
+    write_ffi_function,
 };
 use crate::macros::xross_class::parser::{VariantFieldInfo, XrossClassInput, XrossClassItem};
 use crate::metadata::save_definition;
@@ -52,7 +54,12 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
     }
 
     if name.is_empty() {
-        panic!("xross_class! requires a class or enum name");
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "xross_class! requires a class or enum name",
+        )
+        .to_compile_error()
+        .into();
     }
     let type_ident = format_ident!("{}", name);
     let crate_name = std::env::var("CARGO_PKG_NAME")
@@ -81,6 +88,7 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
         } else {
             false
         };
+        ffi_data.is_constructor = is_constructor;
 
         methods_meta.push(XrossMethod {
             name: rust_fn_name.to_string(),
@@ -91,7 +99,10 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
             is_constructor,
             is_default: false,
             is_raw: false,
-            is_async,
+            is_async: is_task_export(is_async, handle_mode, &ret_ty),
+            concurrency: None,
+            timeout_ms: None,
+            contiguous: false,
             args: ffi_data.args_meta.clone(),
             ret: ret_ty.clone(),
             docs: vec![],
@@ -105,6 +116,7 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
         };
         let call_args = &ffi_data.call_args;
         let inner_call = quote! { #type_prefix #rust_fn_name(#(#call_args),*) };
+        check_sequence_lifetime(&sig, &ret_ty);
         write_ffi_function(
             &ffi_data,
             &ret_ty,
@@ -116,12 +128,16 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
     }
 
     let layout_logic;
+    let layout_descriptor;
+    let definition;
     let mut variant_name_arms = Vec::new();
     let signature = build_signature(&package, &name);
 
     if is_enum {
         let mut variants_meta = Vec::new();
         let mut variant_specs = Vec::new();
+        let mut variant_descriptors = Vec::new();
+        let unit_only = variants_raw.iter().all(|v| matches!(v.fields, VariantFieldInfo::Unit));
         for v in &variants_raw {
             let v_ident = format_ident!("{}", v.name);
             let v_name_str = &v.name;
@@ -131,6 +147,7 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
             let mut internal_conversions = Vec::new();
             let mut call_args = Vec::new();
             let mut field_specs = Vec::new();
+            let mut field_descriptors = Vec::new();
 
             match &v.fields {
                 VariantFieldInfo::Unit => {
@@ -175,6 +192,12 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
                                 &f_name_str,
                                 f_ty,
                             ));
+                            field_descriptors.push(gen_field_descriptor(
+                                &type_ident,
+                                quote! { #v_ident . #f_name_ident },
+                                &f_name_str,
+                                f_ty,
+                            ));
                         } else {
                             call_args.push(c_call_arg);
                             let idx = syn::Index::from(i);
@@ -184,6 +207,12 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
                                 &f_name_str,
                                 f_ty,
                             ));
+                            field_descriptors.push(gen_field_descriptor(
+                                &type_ident,
+                                quote! { #v_ident . #idx },
+                                &f_name_str,
+                                f_ty,
+                            ));
                         }
                     }
 
@@ -212,13 +241,24 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
                     );
                 }
             }
+            // The enum's attributes are not visible here, so only unit-only discriminants are known.
+            let discriminant = if unit_only {
+                quote! { Some(#type_ident::#v_ident as i64) }
+            } else {
+                quote! { None }
+            };
+            variant_descriptors.push(gen_variant_descriptor(
+                v_name_str,
+                discriminant,
+                &field_descriptors,
+            ));
             variants_meta.push(XrossVariant {
                 name: v.name.clone(),
                 fields: v_fields_meta,
                 docs: vec![],
             });
         }
-        save_definition(&XrossDefinition::Enum(XrossEnum {
+        definition = XrossDefinition::Enum(XrossEnum {
             signature,
             symbol_prefix: symbol_base.clone(),
             package_name: package,
@@ -227,12 +267,15 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
             methods: methods_meta,
             docs: vec![],
             is_copy,
-        }));
+            layout: None,
+        });
         layout_logic = quote! { let mut parts = vec![format!("{}", std::mem::size_of::<#type_ident>() as u64)]; let variants: Vec<String> = vec![#(#variant_specs),*]; parts.push(variants.join(";")); parts.join(";") };
+        layout_descriptor = gen_enum_descriptor(&type_ident, quote! { None }, &variant_descriptors);
         generate_enum_aux_ffi(&type_ident, &symbol_base, variant_name_arms, &mut extra_functions);
     } else {
         let mut fields_meta = Vec::new();
         let mut field_specs = Vec::new();
+        let mut field_descriptors = Vec::new();
         for (f_name, f_ty) in fields_raw {
             let xross_ty = resolve_type_with_attr(&f_ty, &[], &package, Some(&type_ident));
             fields_meta.push(XrossField {
@@ -248,6 +291,12 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
                 &f_name,
                 &f_ty,
             ));
+            field_descriptors.push(gen_field_descriptor(
+                &type_ident,
+                quote! { #field_ident },
+                &f_name,
+                &f_ty,
+            ));
             generate_property_accessors(
                 &type_ident,
                 &field_ident,
@@ -257,7 +306,7 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
                 &mut extra_functions,
             );
         }
-        save_definition(&XrossDefinition::Struct(XrossStruct {
+        definition = XrossDefinition::Struct(XrossStruct {
             signature,
             symbol_prefix: symbol_base.clone(),
             package_name: package,
@@ -266,13 +315,23 @@ pub fn impl_xross_class(input: XrossClassInput) -> proc_macro::TokenStream {
             methods: methods_meta,
             docs: vec![],
             is_copy,
-        }));
+            // The declaration does not show the type's `repr`.
+            by_value: false,
+            layout: None,
+        });
         layout_logic = quote! { let mut parts = vec![format!("{}", std::mem::size_of::<#type_ident>() as u64)]; #(parts.push(#field_specs);)* parts.join(";") };
+        layout_descriptor = gen_struct_descriptor(&type_ident, &field_descriptors);
     }
+    extra_functions.push(save_definition(&definition, Some(&type_ident)));
+    extra_functions.push(generate_class_trait(
+        &type_ident,
+        &definition,
+        layout_logic,
+        layout_descriptor,
+    ));
     generate_common_ffi(
         &type_ident,
         &symbol_base,
-        layout_logic,
         &mut extra_functions,
         is_clonable,
         clone_mode,
@@ -15,6 +15,7 @@ syn::custom_keyword!(method);
 syn::custom_keyword!(drop);
 syn::custom_keyword!(critical);
 syn::custom_keyword!(panicable);
+syn::custom_keyword!(blocking);
 syn::custom_keyword!(heap_access);
 
 pub enum VariantFieldInfo {
@@ -217,6 +218,9 @@ impl Parse for XrossClassInput {
                     } else if m_content.peek(panicable) {
                         m_content.parse::<panicable>()?;
                         mode = xross_metadata::HandleMode::Panicable;
+                    } else if m_content.peek(blocking) {
+                        m_content.parse::<blocking>()?;
+                        mode = xross_metadata::HandleMode::Blocking;
                     }
                 }
                 if input.peek(Token![;]) {
@@ -1,5 +1,6 @@
 use crate::codegen::ffi::{
-    MethodFfiData, process_method_args, resolve_return_type, write_ffi_function,
+    MethodFfiData, check_sequence_lifetime, process_method_args, resolve_return_type,
+    write_ffi_function,
 };
 use quote::quote;
 use syn::parse::{Parse, ParseStream};
@@ -10,6 +11,7 @@ mod kw {
     syn::custom_keyword!(package);
     syn::custom_keyword!(critical);
     syn::custom_keyword!(panicable);
+    syn::custom_keyword!(blocking);
     syn::custom_keyword!(safety);
     syn::custom_keyword!(heap_access);
 }
@@ -53,6 +55,12 @@ impl Parse for XrossFunctionInput {
                 if input.peek(Token![;]) {
                     input.parse::<Token![;]>()?;
                 }
+            } else if input.peek(kw::blocking) {
+                input.parse::<kw::blocking>()?;
+                handle_mode = HandleMode::Blocking;
+                if input.peek(Token![;]) {
+                    input.parse::<Token![;]>()?;
+                }
             } else if input.peek(kw::safety) {
                 input.parse::<kw::safety>()?;
                 let id = input.parse::<syn::Ident>()?;
@@ -97,7 +105,7 @@ pub fn impl_xross_function(input: proc_macro::TokenStream) -> proc_macro::TokenS
 
     let ret_ty = resolve_return_type(&input.signature.output, &[], &package_name, &dummy_ident);
 
-    crate::utils::register_xross_function(
+    let mut extra_functions = vec![crate::utils::register_xross_function(
         &package_name,
         &name_str,
         &ffi_data,
@@ -105,11 +113,11 @@ pub fn impl_xross_function(input: proc_macro::TokenStream) -> proc_macro::TokenS
         input.safety,
         &ret_ty,
         vec![],
-    );
-
-    let mut extra_functions = Vec::new();
+        &[],
+    )];
     let call_args = &ffi_data.call_args;
     let inner_call = quote! { #rust_fn_name(#(#call_args),*) };
+    check_sequence_lifetime(&input.signature, &ret_ty);
     write_ffi_function(
         &ffi_data,
         &ret_ty,
@@ -1,5 +1,10 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use syn::spanned::Spanned;
 use xross_metadata::XrossDefinition;
 
 /// Returns the directory where xross metadata files are stored.
@@ -42,16 +47,152 @@ pub fn get_xross_dir() -> PathBuf {
     root.join("target").join("xross")
 }
 
+/// Returns the metadata directory of the compilation unit being compiled
+/// (`target/xross/<crate>/<unit>`, see [`unit_name`]).
+pub fn get_crate_dir() -> PathBuf {
+    get_xross_dir().join(crate::utils::get_crate_name()).join(unit_name())
+}
+
+/// Names the compilation unit of this compiler process: `lib` for the library,
+/// `test-<name>` for test harnesses, `bin-<name>` for binaries and `other` for
+/// processes that are not rustc (e.g. an IDE expanding macros).
+/// Cargo compiles several units of a crate at once, e.g. the library and its test harness
+/// under `cargo test`, so each unit keeps and prunes its own directory.
+fn unit_name() -> &'static str {
+    static UNIT: OnceLock<String> = OnceLock::new();
+    UNIT.get_or_init(|| unit_from_args(std::env::args()))
+}
+
+fn unit_from_args(args: impl IntoIterator<Item = String>) -> String {
+    let mut crate_name = None;
+    let mut crate_types = Vec::new();
+    let mut test = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--test" => test = true,
+            "--crate-name" => crate_name = args.next(),
+            "--crate-type" => crate_types.extend(args.next()),
+            _ => {
+                if let Some(name) = arg.strip_prefix("--crate-name=") {
+                    crate_name = Some(name.to_string());
+                } else if let Some(ty) = arg.strip_prefix("--crate-type=") {
+                    crate_types.push(ty.to_string());
+                }
+            }
+        }
+    }
+    let Some(crate_name) = crate_name else { return "other".to_string() };
+    let library = crate_types.iter().flat_map(|t| t.split(',')).any(|t| t != "bin");
+    if test {
+        format!("test-{}", crate_name)
+    } else if library {
+        xross_metadata::XROSS_LIBRARY_UNIT.to_string()
+    } else {
+        format!("bin-{}", crate_name)
+    }
+}
+
 /// Returns the file path for a given signature.
 pub fn get_path_by_signature(signature: &str) -> PathBuf {
-    get_xross_dir().join(format!("{}.json", signature))
+    get_crate_dir().join(format!("{}.json", signature))
+}
+
+const LOCK_FILE: &str = ".lock";
+const MANIFEST_FILE: &str = "session.manifest";
+
+/// Identifies the current compiler process. Every rustc invocation re-expands all macros
+/// of its compilation unit, so it is one build session of the unit's directory.
+fn session_id() -> &'static str {
+    static SESSION: OnceLock<String> = OnceLock::new();
+    SESSION.get_or_init(|| {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        format!("{}-{}", std::process::id(), nanos)
+    })
+}
+
+/// Runs `f` while holding the unit directory lock.
+/// The first call of a new build session prunes the definitions of the previous session;
+/// each definition written in this session is recorded in the manifest again.
+/// The manifest also records the metadata format the definitions are written in.
+fn with_session<R>(f: impl FnOnce(&Path) -> R) -> R {
+    let dir = get_crate_dir();
+    fs::create_dir_all(&dir).ok();
+    let lock =
+        fs::OpenOptions::new().create(true).truncate(false).write(true).open(dir.join(LOCK_FILE));
+    if let Ok(lock) = &lock {
+        lock.lock().ok();
+    }
+
+    let manifest = dir.join(MANIFEST_FILE);
+    let current = fs::read_to_string(&manifest).unwrap_or_default();
+    if current.lines().next() != Some(&format!("session {}", session_id())) {
+        prune_definitions(&dir);
+        // Definitions of releases that wrote all units into the crate directory itself.
+        if let Some(crate_dir) = dir.parent() {
+            prune_definitions(crate_dir);
+            fs::remove_file(crate_dir.join(MANIFEST_FILE)).ok();
+        }
+        let header = format!(
+            "session {}\nformat {}\nunit {}\n",
+            session_id(),
+            xross_metadata::XROSS_METADATA_FORMAT_VERSION,
+            unit_name()
+        );
+        write_atomic(&manifest, header.as_bytes());
+    }
+
+    f(&dir)
+}
+
+/// Removes the definitions and leftover temp files of a previous session.
+fn prune_definitions(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if path.extension().is_some_and(|e| e == "json") || name.ends_with(".tmp") {
+                fs::remove_file(&path).ok();
+            }
+        }
+    }
+}
+
+/// Writes `contents` to a temp file next to `path` and renames it into place,
+/// so readers never observe a partially written file.
+fn write_atomic(path: &Path, contents: &[u8]) {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let written = fs::File::create(&tmp).and_then(|mut f| {
+        f.write_all(contents)?;
+        f.sync_all()
+    });
+    if written.is_ok() && fs::rename(&tmp, path).is_ok() {
+        return;
+    }
+    fs::remove_file(&tmp).ok();
 }
 
-/// Saves the type definition to a JSON file in the metadata directory.
-/// Performs compatibility checks if a definition already exists.
-pub fn save_definition(def: &XrossDefinition) {
-    let xross_dir = get_xross_dir();
-    fs::create_dir_all(&xross_dir).ok();
+fn record_in_manifest(dir: &Path, file_name: &str) {
+    let manifest = dir.join(MANIFEST_FILE);
+    let mut contents = fs::read_to_string(&manifest).unwrap_or_default();
+    if !contents.lines().skip(1).any(|l| l == file_name) {
+        contents.push_str(file_name);
+        contents.push('\n');
+        write_atomic(&manifest, contents.as_bytes());
+    }
+}
+
+/// Saves the type definition to a JSON file in the crate's metadata directory.
+/// Performs compatibility checks if a definition already exists in this build session,
+/// reporting a conflicting duplicate at the macro invocation.
+/// Returns the tokens that embed the definition into the compiled library.
+/// `class` is the type that declares the definition, whose layout is embedded with it.
+pub fn save_definition(def: &XrossDefinition, class: Option<&syn::Ident>) -> TokenStream {
     let signature = def.signature();
     let path = get_path_by_signature(signature);
 
@@ -65,20 +206,133 @@ pub fn save_definition(def: &XrossDefinition) {
         XrossDefinition::Function(_) => {}
     }
 
-    if path.exists()
-        && let Ok(existing_content) = fs::read_to_string(&path)
-        && let Ok(existing_def) = serde_json::from_str::<XrossDefinition>(&existing_content)
-        && !is_structurally_compatible(&existing_def, &final_def)
-    {
-        panic!(
-            "\n[Xross Error] Duplicate definition detected for signature: '{}'\n\
-             The same signature is being defined multiple times with different structures.\n",
-            signature
-        );
+    with_session(|dir| {
+        let mut file_def = final_def.clone();
+        if path.exists()
+            && let Ok(existing_content) = fs::read_to_string(&path)
+            && let Ok(mut existing_def) = serde_json::from_str::<XrossDefinition>(&existing_content)
+        {
+            if !is_structurally_compatible(&existing_def, &final_def) {
+                crate::utils::emit_error(syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!(
+                        "Duplicate definition detected for signature: '{}'. The same signature \
+                         is being defined multiple times with different structures.",
+                        signature
+                    ),
+                ));
+                return;
+            }
+            // The file was written earlier in this session, e.g. by another impl block or a
+            // `cfg`-gated group of methods; keep its methods. The embedded fragment only holds
+            // this invocation's, since their type references are resolved per fragment.
+            if let (Some(methods), Some(existing)) =
+                (file_def.methods_mut(), existing_def.methods_mut())
+            {
+                for m in existing.drain(..) {
+                    if !methods.iter().any(|e| e.name == m.name && e.symbol == m.symbol) {
+                        methods.push(m);
+                    }
+                }
+            }
+        }
+
+        if let Ok(json) = serde_json::to_string(&file_def) {
+            write_atomic(&path, json.as_bytes());
+            record_in_manifest(dir, &format!("{}.json", signature));
+        }
+    });
+
+    embed_definition(&final_def, class)
+}
+
+/// Like [`save_definition`], but for a definition generated from an item gated by `cfgs`.
+/// Macros cannot evaluate `cfg`, so the definition is handed to `__xross_definition`
+/// behind the same attributes: rustc only expands it, writing the metadata file and
+/// embedding the definition, when the item is compiled.
+pub fn save_gated_definition(def: &XrossDefinition, cfgs: &[syn::Attribute]) -> TokenStream {
+    if cfgs.is_empty() {
+        return save_definition(def, None);
+    }
+    let json = serde_json::to_string(def).expect("Failed to serialize xross definition");
+    let type_refs = crate::types::mapping::take_type_refs()
+        .into_iter()
+        .map(|(name, ty)| quote! { #name => #ty });
+    quote! {
+        #(#cfgs)*
+        xross_core::__xross_definition! { #json; #(#type_refs),* }
     }
+}
 
-    if let Ok(json) = serde_json::to_string(&final_def) {
-        fs::write(&path, json).ok();
+/// Input of `__xross_definition`: a JSON definition and the type references it uses.
+struct DeferredDefinition {
+    json: syn::LitStr,
+    type_refs: Vec<(String, syn::Type)>,
+}
+
+impl syn::parse::Parse for DeferredDefinition {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let json = input.parse()?;
+        input.parse::<syn::Token![;]>()?;
+        let mut type_refs = Vec::new();
+        while !input.is_empty() {
+            let name: syn::LitStr = input.parse()?;
+            input.parse::<syn::Token![=>]>()?;
+            type_refs.push((name.value(), input.parse()?));
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(Self { json, type_refs })
+    }
+}
+
+/// Expands `__xross_definition`, generated by [`save_gated_definition`].
+pub fn impl_deferred_definition(input: TokenStream) -> TokenStream {
+    let DeferredDefinition { json, type_refs } = match syn::parse2(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error(),
+    };
+    let def: XrossDefinition = match serde_json::from_str(&json.value()) {
+        Ok(def) => def,
+        Err(e) => {
+            return syn::Error::new_spanned(&json, format!("invalid xross definition: {}", e))
+                .to_compile_error();
+        }
+    };
+    crate::types::mapping::extend_type_refs(type_refs);
+    save_definition(&def, None)
+}
+
+/// Generates a static that registers the definition in `xross_core::XROSS_METADATA`,
+/// so the bundle returned by `xross_metadata` always matches the compiled library.
+/// Object types referenced by bare name are paired with their `XrossClass` constants.
+/// This also makes it a compile error, spanning the type, to reference anything that is not
+/// a class (e.g. an alias of a primitive).
+/// The layout of `class`, known only once the type is compiled, is filled in at runtime.
+pub fn embed_definition(def: &XrossDefinition, class: Option<&syn::Ident>) -> TokenStream {
+    let json = serde_json::to_string(def).expect("Failed to serialize xross definition");
+    let type_refs = crate::types::mapping::take_type_refs().into_iter().map(|(name, ty)| {
+        let info = quote_spanned! { ty.span()=> xross_core::XrossClassInfo::of::<#ty> };
+        quote! { (#name, #info) }
+    });
+    let layout = match class {
+        Some(ty) => quote! { Some(<#ty as xross_core::XrossClass>::xross_layout_descriptor) },
+        None => quote! { None },
+    };
+    quote! {
+        const _: () = {
+            #[xross_core::linkme::distributed_slice(xross_core::XROSS_METADATA)]
+            #[linkme(crate = xross_core::linkme)]
+            static XROSS_METADATA_FRAGMENT: xross_core::XrossMetadataFragment =
+                xross_core::XrossMetadataFragment {
+                    json: #json,
+                    type_refs: &[#(#type_refs),*],
+                    layout: #layout,
+                    crate_name: env!("CARGO_PKG_NAME"),
+                    crate_version: env!("CARGO_PKG_VERSION"),
+                };
+        };
     }
 }
 
@@ -117,62 +371,3 @@ fn is_structurally_compatible(a: &XrossDefinition, b: &XrossDefinition) -> bool
         _ => false,
     }
 }
-
-/// Loads a definition from the metadata directory by its identifier name.
-pub fn load_definition(ident: &syn::Ident) -> Option<XrossDefinition> {
-    let xross_dir = get_xross_dir();
-    if !xross_dir.exists() {
-        return None;
-    }
-
-    if let Ok(entries) = fs::read_dir(xross_dir) {
-        for entry in entries.flatten() {
-            if let Ok(content) = fs::read_to_string(entry.path())
-                && let Ok(def) = serde_json::from_str::<XrossDefinition>(&content)
-                && *ident == def.name()
-            {
-                return Some(def);
-            }
-        }
-    }
-    None
-}
-
-/// Discovers the signature of a type by its name.
-/// Panics if multiple types with the same name are found in different packages.
-pub fn discover_signature(type_name: &str) -> Option<String> {
-    let xross_dir = get_xross_dir();
-    if !xross_dir.exists() {
-        return None;
-    }
-
-    let mut candidates = Vec::new();
-
-    if let Ok(entries) = fs::read_dir(xross_dir) {
-        for entry in entries.flatten() {
-            if let Ok(content) = fs::read_to_string(entry.path())
-                && let Ok(def) = serde_json::from_str::<XrossDefinition>(&content)
-                && def.name() == type_name
-            {
-                candidates.push(def.signature().to_string());
-            }
-        }
-    }
-
-    candidates.sort();
-    candidates.dedup();
-
-    if candidates.len() == 1 {
-        Some(candidates.remove(0))
-    } else if candidates.len() > 1 {
-        panic!(
-            "\n[Xross Error] Ambiguous type reference: '{}'\n\
-             Multiple types with the same name were found in different packages:\n\
-             {}\n",
-            type_name,
-            candidates.iter().map(|s| format!("  - {}", s)).collect::<Vec<_>>().join("\n")
-        );
-    } else {
-        None
-    }
-}
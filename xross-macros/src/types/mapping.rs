@@ -1,75 +1,214 @@
-use syn::{GenericArgument, PathArguments, Type, TypePath};
+use crate::utils::{emit_error, take_errors};
+use std::cell::RefCell;
+use syn::{GenericArgument, Path, PathArguments, Type, TypeImplTrait, TypeParamBound, TypePath};
 use xross_metadata::{Ownership, XrossType};
 
+thread_local! {
+    /// Object types mapped by name during the current macro expansion, with the Rust type
+    /// they came from. Embedded metadata resolves them through `XrossClass::SIGNATURE`.
+    static TYPE_REFS: RefCell<Vec<(String, Type)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records a compile error spanning `ty`. The returned placeholder keeps the expansion going
+/// so every unmappable type is reported at once.
+fn unmappable(ty: &Type, message: &str) -> XrossType {
+    emit_error(syn::Error::new_spanned(ty, format!("xross: {}", message)));
+    XrossType::Pointer
+}
+
+/// Clears the per-expansion state. Called at the start of every macro entry point.
+pub fn begin_expansion() {
+    take_type_refs();
+    take_errors();
+}
+
+fn record_type_ref(name: &str, ty: &Type) {
+    TYPE_REFS.with_borrow_mut(|refs| {
+        if !refs.iter().any(|(n, _)| n == name) {
+            refs.push((name.to_string(), ty.clone()));
+        }
+    });
+}
+
+/// Records type references taken earlier, e.g. those of a deferred definition.
+pub fn extend_type_refs(refs: Vec<(String, Type)>) {
+    for (name, ty) in refs {
+        record_type_ref(&name, &ty);
+    }
+}
+
+/// Takes the type references recorded since the last call.
+pub fn take_type_refs() -> Vec<(String, Type)> {
+    TYPE_REFS.with_borrow_mut(std::mem::take)
+}
+
 pub fn map_type(ty: &syn::Type) -> XrossType {
     match ty {
         Type::Reference(r) => map_type(&r.elem),
+        Type::Paren(p) => map_type(&p.elem),
+        Type::Group(g) => map_type(&g.elem),
 
         Type::Slice(s) => XrossType::Slice(Box::new(map_type(&s.elem))),
 
-        Type::Path(TypePath { path, .. }) => {
-            let last_segment = path.segments.last().unwrap();
-            let last_ident = last_segment.ident.to_string();
-
-            match last_ident.as_str() {
-                "i8" => XrossType::I8,
-                "u8" => XrossType::U8,
-                "i16" => XrossType::I16,
-                "u16" => XrossType::U16,
-                "i32" => XrossType::I32,
-                "u32" => XrossType::U32,
-                "i64" => XrossType::I64,
-                "u64" => XrossType::U64,
-                "isize" => XrossType::ISize,
-                "usize" => XrossType::USize,
-                "f32" => XrossType::F32,
-                "f64" => XrossType::F64,
-                "bool" => XrossType::Bool,
-                "String" => XrossType::String,
-
-                // ジェネリック型の処理
-                "Box" | "Option" | "Result" | "Vec" => {
-                    if let PathArguments::AngleBracketed(args) = &last_segment.arguments {
-                        let generic_types: Vec<XrossType> = args
-                            .args
-                            .iter()
-                            .filter_map(|arg| {
-                                if let GenericArgument::Type(inner_ty) = arg {
-                                    Some(map_type(inner_ty))
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect();
-
-                        match last_ident.as_str() {
-                            "Box" => {
-                                let mut inner = generic_types[0].clone();
-                                if let XrossType::Object { ownership, .. } = &mut inner {
-                                    *ownership = Ownership::Boxed;
-                                }
-                                inner
-                            }
-                            "Option" => XrossType::Option(Box::new(generic_types[0].clone())),
-                            "Vec" => XrossType::Vec(Box::new(generic_types[0].clone())),
-                            "Result" => XrossType::Result {
-                                ok: Box::new(generic_types[0].clone()),
-                                err: Box::new(generic_types[1].clone()),
-                            },
-                            _ => unreachable!(),
-                        }
-                    } else {
-                        XrossType::Pointer
+        Type::Ptr(_) => XrossType::Pointer,
+        Type::Tuple(t) if t.elems.is_empty() => XrossType::Void,
+
+        Type::Path(TypePath { qself: Some(_), .. }) => {
+            unmappable(ty, "qualified associated types are not supported")
+        }
+        Type::Path(TypePath { path, .. }) => map_path(ty, path),
+        Type::ImplTrait(it) => map_impl_trait(it),
+        _ => unmappable(ty, "only paths, references, slices and raw pointers can be bridged"),
+    }
+}
+
+/// Maps a (possibly qualified) path such as `u64`, `std::string::String` or `crate::models::Foo`.
+/// Built-in types are only recognized bare or under `std`/`core`/`alloc`, and the runtime types
+/// bare or under their own crate; any other uppercase path is a class, resolved later through
+/// `XrossClass`.
+fn map_path(ty: &Type, path: &Path) -> XrossType {
+    let last_segment = path.segments.last().unwrap();
+    let last_ident = last_segment.ident.to_string();
+    let first_ident = path.segments.first().unwrap().ident.to_string();
+    let is_builtin_path = (path.segments.len() == 1 && path.leading_colon.is_none())
+        || matches!(first_ident.as_str(), "std" | "core" | "alloc");
+
+    if is_builtin_path && let Some(prim) = map_primitive(&last_ident) {
+        return prim;
+    }
+
+    match last_ident.as_str() {
+        "String" if is_builtin_path => XrossType::String,
+        "XrossCancelToken" if is_bare_or(path, &["xross_core"]) => XrossType::CancelToken,
+        "XrossCancelToken" => {
+            unmappable(ty, "unsupported type; use `xross_core::XrossCancelToken`")
+        }
+
+        // tokio::sync::mpsc::Receiver<T> は Stream として公開
+        "Receiver" if is_bare_or(path, &["tokio", "sync", "mpsc"]) => {
+            match first_generic_type(&last_segment.arguments) {
+                Some(inner) => XrossType::Stream(Box::new(map_type(inner))),
+                None => unmappable(ty, "`Receiver` needs an item type"),
+            }
+        }
+        "Receiver" => unmappable(
+            ty,
+            "unsupported type; only `tokio::sync::mpsc::Receiver` can be bridged as a stream",
+        ),
+
+        // ジェネリック型の処理
+        "Box" | "Option" | "Vec" | "Result" if is_builtin_path => {
+            let generic_types: Vec<&Type> = match &last_segment.arguments {
+                PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(inner_ty) => Some(inner_ty),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            match (last_ident.as_str(), generic_types.as_slice()) {
+                ("Box", [inner]) => {
+                    let mut inner = map_type(inner);
+                    if let XrossType::Object { ownership, .. } = &mut inner {
+                        *ownership = Ownership::Boxed;
                     }
+                    inner
                 }
-
-                // 構造体や列挙型
-                s if s.chars().next().is_some_and(|c| c.is_uppercase()) => {
-                    XrossType::Object { signature: s.to_string(), ownership: Ownership::Owned }
+                ("Option", [inner]) => XrossType::Option(Box::new(map_type(inner))),
+                ("Vec", [inner]) => XrossType::Vec(Box::new(map_type(inner))),
+                ("Result", [ok, err]) => {
+                    XrossType::Result { ok: Box::new(map_type(ok)), err: Box::new(map_type(err)) }
                 }
-                _ => XrossType::Pointer,
+                ("Result", _) => unmappable(ty, "`Result` needs an explicit error type"),
+                _ => unmappable(ty, &format!("`{}` needs exactly one type argument", last_ident)),
             }
         }
-        _ => XrossType::Pointer,
+
+        // `Self` is resolved by the caller, which knows the implementing type.
+        "Self" => XrossType::object("Self", Ownership::Owned),
+
+        // 構造体や列挙型
+        s if s.chars().next().is_some_and(|c| c.is_uppercase()) => {
+            if !last_segment.arguments.is_empty() {
+                return unmappable(
+                    ty,
+                    "generic types other than Option, Vec, Box and Result are not supported",
+                );
+            }
+            record_type_ref(s, ty);
+            XrossType::object(s, Ownership::Owned)
+        }
+        s => unmappable(
+            ty,
+            &format!("cannot map `{}`; type aliases are not resolved, use the underlying type", s),
+        ),
+    }
+}
+
+/// Whether `path` is a single imported name or the full path of that name under `module`.
+fn is_bare_or(path: &Path, module: &[&str]) -> bool {
+    if path.segments.len() == 1 {
+        return path.leading_colon.is_none();
+    }
+    path.segments.len() == module.len() + 1
+        && path.segments.iter().zip(module).all(|(segment, name)| segment.ident == name)
+}
+
+fn map_primitive(name: &str) -> Option<XrossType> {
+    Some(match name {
+        "i8" => XrossType::I8,
+        "u8" => XrossType::U8,
+        "i16" => XrossType::I16,
+        "u16" => XrossType::U16,
+        "i32" => XrossType::I32,
+        "u32" => XrossType::U32,
+        "i64" => XrossType::I64,
+        "u64" => XrossType::U64,
+        "isize" => XrossType::ISize,
+        "usize" => XrossType::USize,
+        "f32" => XrossType::F32,
+        "f64" => XrossType::F64,
+        "bool" => XrossType::Bool,
+        _ => return None,
+    })
+}
+
+/// Maps `impl Iterator<Item = T>` / `impl Stream<Item = T>` to a sequence type.
+fn map_impl_trait(it: &TypeImplTrait) -> XrossType {
+    for bound in &it.bounds {
+        let TypeParamBound::Trait(tb) = bound else { continue };
+        let Some(segment) = tb.path.segments.last() else { continue };
+        let PathArguments::AngleBracketed(args) = &segment.arguments else { continue };
+        let item = args.args.iter().find_map(|arg| match arg {
+            GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+            _ => None,
+        });
+        let Some(item) = item else { continue };
+        match segment.ident.to_string().as_str() {
+            "Iterator" | "ExactSizeIterator" | "DoubleEndedIterator" => {
+                return XrossType::Iterator(Box::new(map_type(item)));
+            }
+            "Stream" => return XrossType::Stream(Box::new(map_type(item))),
+            _ => {}
+        }
+    }
+    unmappable(
+        &Type::ImplTrait(it.clone()),
+        "only `impl Iterator` and `impl Stream` with an `Item` can be bridged",
+    )
+}
+
+fn first_generic_type(args: &PathArguments) -> Option<&Type> {
+    if let PathArguments::AngleBracketed(args) = args {
+        args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+    } else {
+        None
     }
 }
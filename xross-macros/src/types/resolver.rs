@@ -1,5 +1,6 @@
-use crate::metadata::discover_signature;
+use crate::codegen::ffi::build_self_object;
 use crate::types::mapping::map_type;
+use crate::utils::{parse_xross_attrs, skip_nested};
 use syn::{Attribute, Type};
 use xross_metadata::{Ownership, XrossType};
 
@@ -19,8 +20,6 @@ pub fn resolve_type_with_attr_ext(
     current_ident: Option<&syn::Ident>,
     force_value: bool,
 ) -> XrossType {
-    let base_ty = map_type(ty);
-
     let (inner_ty, mut ownership) = match ty {
         Type::Reference(r) => {
             let ow = if r.mutability.is_some() { Ownership::MutRef } else { Ownership::Ref };
@@ -28,75 +27,74 @@ pub fn resolve_type_with_attr_ext(
         }
         _ => (ty, if force_value { Ownership::Value } else { Ownership::Owned }),
     };
-
-    if let XrossType::Object { ownership: base_ow, .. } = &base_ty
-        && *base_ow == Ownership::Boxed
-    {
+    if is_box(inner_ty) {
         ownership = Ownership::Boxed;
     }
 
+    // An explicit signature overrides the mapping, so the Rust type need not be a class.
     let mut xross_ty = None;
-    for attr in attrs {
-        if attr.path().is_ident("xross") {
-            let _ = attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("struct")
-                    || meta.path.is_ident("enum")
-                    || meta.path.is_ident("opaque")
-                {
-                    xross_ty = Some(XrossType::Object {
-                        signature: meta.value()?.parse::<syn::LitStr>()?.value(),
-                        ownership: ownership.clone(),
-                    });
-                } else if meta.path.is_ident("box") {
-                    xross_ty = Some(XrossType::Object {
-                        signature: meta.value()?.parse::<syn::LitStr>()?.value(),
-                        ownership: Ownership::Boxed,
-                    });
-                }
-                Ok(())
-            });
+    parse_xross_attrs(attrs, &["xross"], |meta| {
+        if meta.path.is_ident("struct")
+            || meta.path.is_ident("enum")
+            || meta.path.is_ident("opaque")
+        {
+            xross_ty = Some(XrossType::object(
+                meta.value()?.parse::<syn::LitStr>()?.value(),
+                ownership.clone(),
+            ));
+        } else if meta.path.is_ident("box") {
+            xross_ty = Some(XrossType::object(
+                meta.value()?.parse::<syn::LitStr>()?.value(),
+                Ownership::Boxed,
+            ));
+        } else {
+            skip_nested(meta)?;
         }
-    }
+        Ok(())
+    });
     if let Some(ty) = xross_ty {
         return ty;
     }
 
-    if let Type::Path(tp) = inner_ty
-        && tp.path.is_ident("Self")
-        && let Some(ident) = current_ident
-    {
-        let sig = if current_pkg.is_empty() {
-            ident.to_string()
-        } else {
-            format!("{}.{}", current_pkg, ident)
-        };
-        return XrossType::Object { signature: sig, ownership: ownership.clone() };
-    }
-
     let mut final_ty = map_type(inner_ty);
 
-    if let XrossType::Object { ownership: o, signature } = &mut final_ty {
-        if ownership != Ownership::Owned {
-            *o = ownership;
-        }
-
-        let is_self = current_ident.is_some_and(|ident| {
-            let self_name = ident.to_string();
-            signature == &self_name || signature == &format!("{}.{}", current_pkg, self_name)
-        });
+    if let XrossType::Object { ownership: o, .. } = &mut final_ty
+        && ownership != Ownership::Owned
+    {
+        *o = ownership;
+    }
 
-        if is_self {
-            *signature = if current_pkg.is_empty() {
-                current_ident.unwrap().to_string()
-            } else {
-                format!("{}.{}", current_pkg, current_ident.unwrap())
-            };
-        } else {
-            if let Some(discovered) = discover_signature(signature) {
-                *signature = discovered;
+    // `Self` and the implementing type's own name may appear anywhere, e.g. `Option<Self>`.
+    // Other types keep their bare name here. Bindings resolve it from the metadata set,
+    // and the embedded metadata resolves it through `XrossClass::SIGNATURE`.
+    if let Some(ident) = current_ident {
+        let self_name = ident.to_string();
+        let qualified = format!("{}.{}", current_pkg, self_name);
+        final_ty.visit_mut(&mut |ty| {
+            if let XrossType::Object { signature, ownership, .. } = ty
+                && (signature == "Self" || *signature == self_name || *signature == qualified)
+            {
+                *ty = build_self_object(current_pkg, &self_name, ownership.clone());
             }
-        }
+        });
     }
 
     final_ty
 }
+
+/// Returns true for `Box<T>` where `T` is a class.
+fn is_box(ty: &Type) -> bool {
+    let Type::Path(tp) = ty else { return false };
+    let Some(last) = tp.path.segments.last() else { return false };
+    if last.ident != "Box" {
+        return false;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &last.arguments else { return false };
+    args.args.iter().any(|arg| {
+        matches!(arg, syn::GenericArgument::Type(Type::Path(inner))
+        if inner.path.segments.last().is_some_and(|s| {
+            let name = s.ident.to_string();
+            name != "String" && name.starts_with(|c: char| c.is_uppercase())
+        }))
+    })
+}
@@ -1,16 +1,23 @@
 pub mod attributes;
+pub mod diagnostics;
 pub mod ordinal;
 
 pub use attributes::*;
+pub use diagnostics::*;
 pub use ordinal::*;
 
 use heck::ToSnakeCase;
 use xross_metadata::{HandleMode, ThreadSafety};
 
-pub fn get_symbol_prefix(package_name: &str) -> String {
-    let crate_name = std::env::var("CARGO_PKG_NAME")
+/// Returns the name of the crate being compiled, with `-` replaced by `_`.
+pub fn get_crate_name() -> String {
+    std::env::var("CARGO_PKG_NAME")
         .unwrap_or_else(|_| "unknown_crate".to_string())
-        .replace("-", "_");
+        .replace("-", "_")
+}
+
+pub fn get_symbol_prefix(package_name: &str) -> String {
+    let crate_name = get_crate_name();
 
     if package_name.is_empty() {
         crate_name
@@ -19,6 +26,7 @@ pub fn get_symbol_prefix(package_name: &str) -> String {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn register_xross_function(
     package_name: &str,
     name_str: &str,
@@ -27,7 +35,8 @@ pub fn register_xross_function(
     safety: ThreadSafety,
     ret_ty: &xross_metadata::XrossType,
     docs: Vec<String>,
-) {
+    cfgs: &[syn::Attribute],
+) -> proc_macro2::TokenStream {
     register_xross_function_ext(
         package_name,
         name_str,
@@ -37,9 +46,11 @@ pub fn register_xross_function(
         ret_ty,
         docs,
         false,
+        cfgs,
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn register_xross_function_ext(
     package_name: &str,
     name_str: &str,
@@ -49,8 +60,9 @@ pub fn register_xross_function_ext(
     ret_ty: &xross_metadata::XrossType,
     docs: Vec<String>,
     is_raw: bool,
-) {
-    use crate::metadata::save_definition;
+    cfgs: &[syn::Attribute],
+) -> proc_macro2::TokenStream {
+    use crate::metadata::save_gated_definition;
     use xross_metadata::{XrossDefinition, XrossMethod};
 
     let method_meta = XrossMethod {
@@ -62,7 +74,10 @@ pub fn register_xross_function_ext(
         is_constructor: false,
         is_default: false,
         is_raw,
-        is_async: ffi_data.is_async,
+        is_async: crate::codegen::ffi::is_task_export(ffi_data.is_async, handle_mode, ret_ty),
+        concurrency: ffi_data.concurrency,
+        timeout_ms: ffi_data.timeout_ms,
+        contiguous: ffi_data.contiguous,
         args: ffi_data.args_meta.clone(),
         ret: ret_ty.clone(),
         docs: docs.clone(),
@@ -77,7 +92,7 @@ pub fn register_xross_function_ext(
         docs,
     };
 
-    save_definition(&XrossDefinition::Function(definition));
+    save_gated_definition(&XrossDefinition::Function(definition), cfgs)
 }
 
 pub fn build_symbol_base(crate_name: &str, package: &str, type_name: &str) -> String {
@@ -102,3 +117,24 @@ pub fn is_primitive_type(ty: &syn::Type) -> bool {
     ];
     primitives.iter().any(|&p| type_str == p || (type_str.contains(p) && type_str.len() <= 5))
 }
+
+/// Replaces `Self` in `output` with `ident`, for return types spelled out in exports generated
+/// outside the `impl` block.
+pub fn replace_self(output: &syn::ReturnType, ident: &syn::Ident) -> syn::ReturnType {
+    fn replace(tokens: proc_macro2::TokenStream, ident: &syn::Ident) -> proc_macro2::TokenStream {
+        use proc_macro2::{Group, TokenTree};
+        tokens
+            .into_iter()
+            .map(|tt| match tt {
+                TokenTree::Ident(i) if i == "Self" => TokenTree::Ident(ident.clone()),
+                TokenTree::Group(g) => {
+                    let mut group = Group::new(g.delimiter(), replace(g.stream(), ident));
+                    group.set_span(g.span());
+                    TokenTree::Group(group)
+                }
+                tt => tt,
+            })
+            .collect()
+    }
+    syn::parse2(replace(quote::quote!(#output), ident)).unwrap_or_else(|_| output.clone())
+}
@@ -1,15 +1,112 @@
+use super::emit_error;
 use syn::{Attribute, Expr, ExprLit, Lit, Meta, Token};
-use xross_metadata::{HandleMode, ThreadSafety};
+use xross_metadata::{ConcurrencyLimit, HandleMode, ThreadSafety};
+
+/// Keys accepted by each xross helper attribute.
+/// `xross_raw_method`/`xross_raw_function` have their own syntax and are checked by `RawAttrInput`.
+const ATTR_KEYS: &[(&str, &[&str])] = &[
+    (
+        "xross_method",
+        &[
+            "critical",
+            "panicable",
+            "blocking",
+            "safety",
+            "unsafe",
+            "max_concurrency",
+            "queue",
+            "timeout_ms",
+            "contiguous",
+        ],
+    ),
+    ("xross_new", &["critical", "panicable", "blocking", "safety", "unsafe"]),
+    ("xross_default", &["critical", "panicable", "blocking", "safety", "unsafe"]),
+    ("xross_field", &["safety", "unsafe"]),
+    (
+        "xross",
+        &[
+            "clonable",
+            "clone",
+            "copy",
+            "drop",
+            "struct",
+            "enum",
+            "opaque",
+            "box",
+            "safety",
+            "unsafe",
+            "critical",
+            "panicable",
+            "blocking",
+        ],
+    ),
+];
+
+/// Reports unknown keys in the xross helper attributes of an item, method, field or argument.
+/// The extractors below only look at the keys they own, so a typo would otherwise be ignored.
+pub fn validate_attrs(attrs: &[Attribute]) {
+    for attr in attrs {
+        let Some((name, keys)) = ATTR_KEYS.iter().find(|(name, _)| attr.path().is_ident(name))
+        else {
+            continue;
+        };
+        if matches!(attr.meta, Meta::Path(_)) {
+            continue;
+        }
+        let res = attr.parse_nested_meta(|meta| {
+            if keys.iter().any(|key| meta.path.is_ident(key)) {
+                skip_nested(&meta)
+            } else {
+                Err(meta.error(format!(
+                    "unknown `{}` option; expected one of: {}",
+                    name,
+                    keys.join(", ")
+                )))
+            }
+        });
+        if let Err(e) = res {
+            emit_error(e);
+        }
+    }
+}
+
+/// Consumes the value of a key owned by another extractor (`= expr` or `(...)`).
+pub fn skip_nested(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.input.parse::<proc_macro2::Group>()?;
+    }
+    Ok(())
+}
+
+/// Parses every xross helper attribute in `attrs` with `f`, reporting parse errors.
+pub fn parse_xross_attrs(
+    attrs: &[Attribute],
+    names: &[&str],
+    mut f: impl FnMut(&syn::meta::ParseNestedMeta) -> syn::Result<()>,
+) {
+    for attr in attrs {
+        if names.iter().any(|name| attr.path().is_ident(name))
+            && !matches!(attr.meta, Meta::Path(_))
+            && let Err(e) = attr.parse_nested_meta(|meta| f(&meta))
+        {
+            emit_error(e);
+        }
+    }
+}
 
 pub fn parse_critical_nested(meta: &syn::meta::ParseNestedMeta) -> syn::Result<bool> {
     let mut allow_heap_access = false;
     if meta.input.peek(syn::token::Paren) {
-        let _ = meta.parse_nested_meta(|inner| {
+        meta.parse_nested_meta(|inner| {
             if inner.path.is_ident("heap_access") {
                 allow_heap_access = true;
+                Ok(())
+            } else {
+                Err(inner.error("unknown `critical` option; expected `heap_access`"))
             }
-            Ok(())
-        });
+        })?;
     }
     Ok(allow_heap_access)
 }
@@ -20,7 +117,11 @@ pub fn parse_handle_mode_nested(meta: &syn::meta::ParseNestedMeta) -> syn::Resul
         return match val.value().as_str() {
             "critical" => Ok(HandleMode::Critical { allow_heap_access: false }),
             "panicable" => Ok(HandleMode::Panicable),
-            _ => Ok(HandleMode::Normal),
+            "normal" => Ok(HandleMode::Normal),
+            _ => Err(syn::Error::new_spanned(
+                val,
+                "unknown handle mode; expected \"critical\", \"panicable\" or \"normal\"",
+            )),
         };
     }
     if meta.input.peek(syn::token::Paren) {
@@ -31,6 +132,8 @@ pub fn parse_handle_mode_nested(meta: &syn::meta::ParseNestedMeta) -> syn::Resul
                 mode = HandleMode::Critical { allow_heap_access };
             } else if inner.path.is_ident("panicable") {
                 mode = HandleMode::Panicable;
+            } else {
+                return Err(inner.error("unknown handle mode; expected `critical` or `panicable`"));
             }
             Ok(())
         })?;
@@ -42,51 +145,145 @@ pub fn parse_handle_mode_nested(meta: &syn::meta::ParseNestedMeta) -> syn::Resul
 pub fn extract_handle_mode(attrs: &[Attribute]) -> HandleMode {
     let mut mode = HandleMode::Normal;
 
-    for attr in attrs {
-        let path = attr.path();
-        if path.is_ident("xross_method")
-            || path.is_ident("xross_new")
-            || path.is_ident("xross_default")
-            || path.is_ident("xross_raw_method")
-            || path.is_ident("xross_raw_function")
-            || path.is_ident("xross")
-        {
-            let _ = attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("critical") {
-                    let allow_heap_access = parse_critical_nested(&meta)?;
-                    mode = HandleMode::Critical { allow_heap_access };
-                } else if meta.path.is_ident("panicable") {
-                    mode = HandleMode::Panicable;
-                }
-                Ok(())
-            });
+    parse_xross_attrs(attrs, &["xross_method", "xross_new", "xross_default", "xross"], |meta| {
+        if meta.path.is_ident("critical") {
+            let allow_heap_access = parse_critical_nested(meta)?;
+            mode = HandleMode::Critical { allow_heap_access };
+        } else if meta.path.is_ident("panicable") {
+            mode = HandleMode::Panicable;
+        } else if meta.path.is_ident("blocking") {
+            mode = HandleMode::Blocking;
+        } else {
+            skip_nested(meta)?;
         }
-    }
+        Ok(())
+    });
 
     mode
 }
 
+/// Options that control how an async export is spawned.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TaskOptions {
+    pub max_concurrency: Option<u32>,
+    pub queue: Option<u32>,
+    pub timeout_ms: Option<u64>,
+    /// Span of the first option, used to report options on a non-async export.
+    pub span: Option<proc_macro2::Span>,
+}
+
+impl TaskOptions {
+    /// Parses one `max_concurrency = N` / `queue = M` / `timeout_ms = T` entry.
+    /// Returns false for other keys.
+    pub fn parse_nested(&mut self, meta: &syn::meta::ParseNestedMeta) -> syn::Result<bool> {
+        let key_span = syn::spanned::Spanned::span(&meta.path);
+        if meta.path.is_ident("max_concurrency") {
+            let lit = meta.value()?.parse::<syn::LitInt>()?;
+            if lit.base10_parse::<u32>()? == 0 {
+                return Err(syn::Error::new_spanned(lit, "max_concurrency must be at least 1"));
+            }
+            self.max_concurrency = Some(lit.base10_parse()?);
+        } else if meta.path.is_ident("queue") {
+            self.queue = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+        } else if meta.path.is_ident("timeout_ms") {
+            let lit = meta.value()?.parse::<syn::LitInt>()?;
+            if lit.base10_parse::<u64>()? == 0 {
+                return Err(syn::Error::new_spanned(lit, "timeout_ms must be at least 1"));
+            }
+            self.timeout_ms = Some(lit.base10_parse()?);
+        } else {
+            return Ok(false);
+        }
+        self.span.get_or_insert(key_span);
+        Ok(true)
+    }
+
+    /// Builds the concurrency limit from the parsed `max_concurrency` / `queue` values.
+    pub fn concurrency(&self) -> Option<ConcurrencyLimit> {
+        match (self.max_concurrency, self.queue) {
+            (Some(max_concurrency), queue) => Some(ConcurrencyLimit { max_concurrency, queue }),
+            (None, Some(_)) => {
+                emit_error(syn::Error::new(
+                    self.span.unwrap_or_else(proc_macro2::Span::call_site),
+                    "`queue` requires `max_concurrency` to be set",
+                ));
+                None
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Reports the options the export they are attached to does not support: the concurrency
+    /// limit needs an async export, `timeout_ms` an async or `blocking` one.
+    pub fn check_export(&self, is_async: bool, handle_mode: HandleMode, what: &str) {
+        let span = self.span.unwrap_or_else(proc_macro2::Span::call_site);
+        if (self.max_concurrency.is_some() || self.queue.is_some()) && !is_async {
+            emit_error(syn::Error::new(
+                span,
+                format!("max_concurrency/queue are only supported on async {}", what),
+            ));
+        } else if self.timeout_ms.is_some() && !is_async && handle_mode != HandleMode::Blocking {
+            emit_error(syn::Error::new(
+                span,
+                format!("timeout_ms is only supported on async or blocking {}", what),
+            ));
+        }
+    }
+}
+
+pub fn extract_task_options(attrs: &[Attribute]) -> TaskOptions {
+    let mut options = TaskOptions::default();
+
+    parse_xross_attrs(attrs, &["xross_method"], |meta| {
+        if !options.parse_nested(meta)? {
+            skip_nested(meta)?;
+        }
+        Ok(())
+    });
+
+    options
+}
+
+/// Span of the `contiguous` key of `#[xross_method]`, if present.
+pub fn extract_contiguous(attrs: &[Attribute]) -> Option<proc_macro2::Span> {
+    let mut span = None;
+
+    parse_xross_attrs(attrs, &["xross_method"], |meta| {
+        if meta.path.is_ident("contiguous") {
+            span.get_or_insert(syn::spanned::Spanned::span(&meta.path));
+        } else {
+            skip_nested(meta)?;
+        }
+        Ok(())
+    });
+
+    span
+}
+
 pub fn extract_special_modes(attrs: &[Attribute]) -> (HandleMode, HandleMode) {
     let mut clone_mode = HandleMode::Normal;
     let mut drop_mode = HandleMode::Normal;
 
-    for attr in attrs {
-        if attr.path().is_ident("xross") {
-            let _ = attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("clone") {
-                    clone_mode = parse_handle_mode_nested(&meta)?;
-                } else if meta.path.is_ident("drop") {
-                    drop_mode = parse_handle_mode_nested(&meta)?;
-                }
-                Ok(())
-            });
+    parse_xross_attrs(attrs, &["xross"], |meta| {
+        // `clonable(mode)` is shorthand for `clonable, clone(mode)`.
+        if meta.path.is_ident("clone")
+            || (meta.path.is_ident("clonable") && meta.input.peek(syn::token::Paren))
+        {
+            clone_mode = parse_handle_mode_nested(meta)?;
+        } else if meta.path.is_ident("drop") {
+            drop_mode = parse_handle_mode_nested(meta)?;
+        } else {
+            skip_nested(meta)?;
         }
-    }
+        Ok(())
+    });
     (clone_mode, drop_mode)
 }
 
 pub fn extract_is_copy(attrs: &[Attribute]) -> bool {
-    attrs.iter().any(|attr| {
+    // 1. Check #[derive(Copy)]. A derive does not see the list it is part of, so this only
+    // finds `Copy` derived by another attribute.
+    let is_derived = attrs.iter().any(|attr| {
         if attr.path().is_ident("derive") {
             if let Meta::List(list) = &attr.meta {
                 let folder = list.tokens.to_string();
@@ -97,7 +294,38 @@ pub fn extract_is_copy(attrs: &[Attribute]) -> bool {
         } else {
             false
         }
-    })
+    });
+
+    // 2. Check #[xross(copy)]
+    let mut is_copy = false;
+    parse_xross_attrs(attrs, &["xross"], |meta| {
+        if meta.path.is_ident("copy") {
+            is_copy = true;
+        }
+        skip_nested(meta)
+    });
+    is_derived || is_copy
+}
+
+/// Returns true for `#[repr(C)]` without `packed` or `align`, the layout a C struct of the
+/// same fields has.
+pub fn extract_is_repr_c(attrs: &[Attribute]) -> bool {
+    let mut is_c = false;
+    let mut is_modified = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                is_c = true;
+            } else {
+                is_modified = true;
+            }
+            if meta.input.peek(syn::token::Paren) {
+                let _ = meta.input.parse::<proc_macro2::Group>();
+            }
+            Ok(())
+        });
+    }
+    is_c && !is_modified
 }
 
 pub fn extract_is_clonable(attrs: &[Attribute]) -> bool {
@@ -119,42 +347,47 @@ pub fn extract_is_clonable(attrs: &[Attribute]) -> bool {
         return true;
     }
 
-    // 2. Check #[xross(clonable)] or #[xross(clonable = true)]
-    for attr in attrs {
-        if attr.path().is_ident("xross") {
-            let mut is_clonable = false;
-            let _ = attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("clonable") {
-                    if meta.input.peek(Token![=]) {
-                        let value: syn::LitBool = meta.value()?.parse()?;
-                        is_clonable = value.value;
-                    } else {
-                        is_clonable = true;
-                    }
-                }
-                Ok(())
-            });
-            if is_clonable {
-                return true;
+    // 2. Check #[xross(clonable)], #[xross(clonable = true)] or #[xross(clonable(mode))]
+    let mut is_clonable = false;
+    parse_xross_attrs(attrs, &["xross"], |meta| {
+        if meta.path.is_ident("clonable") {
+            if meta.input.peek(Token![=]) {
+                let value: syn::LitBool = meta.value()?.parse()?;
+                is_clonable = value.value;
+            } else {
+                skip_nested(meta)?;
+                is_clonable = true;
             }
+        } else {
+            skip_nested(meta)?;
         }
-    }
+        Ok(())
+    });
 
-    false
+    is_clonable
 }
 
 pub fn extract_package(attrs: &[Attribute]) -> String {
     for attr in attrs {
-        if attr.path().is_ident("xross_package")
-            && let Ok(lit) = attr.parse_args::<Lit>()
-            && let Lit::Str(s) = lit
-        {
-            return s.value();
+        if attr.path().is_ident("xross_package") {
+            match attr.parse_args::<syn::LitStr>() {
+                Ok(s) => return s.value(),
+                Err(e) => emit_error(syn::Error::new(
+                    e.span(),
+                    "expected a package name, e.g. #[xross_package(\"com.example\")]",
+                )),
+            }
         }
     }
     "".to_string()
 }
 
+/// Returns the `#[cfg(...)]` attributes. Attribute macros see them before rustc evaluates
+/// them, so everything generated for the item must carry them too.
+pub fn extract_cfg_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs.iter().filter(|a| a.path().is_ident("cfg")).cloned().collect()
+}
+
 pub fn extract_docs(attrs: &[Attribute]) -> Vec<String> {
     attrs
         .iter()
@@ -170,39 +403,47 @@ pub fn extract_docs(attrs: &[Attribute]) -> Vec<String> {
         .collect()
 }
 
+/// Parses a `safety = Level` value.
+pub fn parse_safety_value(meta: &syn::meta::ParseNestedMeta) -> syn::Result<ThreadSafety> {
+    let value = meta.value()?.parse::<syn::Ident>()?;
+    Ok(match value.to_string().as_str() {
+        "Unsafe" => ThreadSafety::Unsafe,
+        "Direct" => ThreadSafety::Direct,
+        "Atomic" => ThreadSafety::Atomic,
+        "Immutable" => ThreadSafety::Immutable,
+        "Lock" => ThreadSafety::Lock,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                value,
+                "unknown safety level; expected Unsafe, Direct, Atomic, Immutable or Lock",
+            ));
+        }
+    })
+}
+
 pub fn extract_safety_attr(attrs: &[Attribute], default: ThreadSafety) -> ThreadSafety {
     let mut safety = default;
+    let mut safety_span = None;
     let mut is_unsafe_acknowledged = false;
 
-    for attr in attrs {
-        if attr.path().is_ident("xross_field")
-            || attr.path().is_ident("xross_method")
-            || attr.path().is_ident("xross")
-        {
-            let _ = attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("safety") {
-                    let value = meta.value()?.parse::<syn::Ident>()?;
-                    safety = match value.to_string().as_str() {
-                        "Unsafe" => ThreadSafety::Unsafe,
-                        "Direct" => ThreadSafety::Direct,
-                        "Atomic" => ThreadSafety::Atomic,
-                        "Immutable" => ThreadSafety::Immutable,
-                        "Lock" => ThreadSafety::Lock,
-                        _ => default,
-                    };
-                } else if meta.path.is_ident("unsafe") {
-                    is_unsafe_acknowledged = true;
-                }
-                Ok(())
-            });
+    parse_xross_attrs(attrs, &["xross_field", "xross_method", "xross"], |meta| {
+        if meta.path.is_ident("safety") {
+            safety_span = Some(syn::spanned::Spanned::span(&meta.path));
+            safety = parse_safety_value(meta)?;
+        } else if meta.path.is_ident("unsafe") {
+            is_unsafe_acknowledged = true;
+        } else {
+            skip_nested(meta)?;
         }
-    }
+        Ok(())
+    });
 
     if (safety == ThreadSafety::Unsafe || safety == ThreadSafety::Direct) && !is_unsafe_acknowledged
     {
-        panic!(
-            "Using safety level 'Unsafe' or 'Direct' requires an explicit 'unsafe' attribute. e.g. #[xross_method(safety = Direct, unsafe)]"
-        );
+        emit_error(syn::Error::new(
+            safety_span.unwrap_or_else(proc_macro2::Span::call_site),
+            "Using safety level 'Unsafe' or 'Direct' requires an explicit 'unsafe' attribute. e.g. #[xross_method(safety = Direct, unsafe)]",
+        ));
     }
 
     safety
@@ -0,0 +1,26 @@
+use std::cell::RefCell;
+
+thread_local! {
+    /// Errors recorded during the current macro expansion.
+    static ERRORS: RefCell<Vec<syn::Error>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records a compile error. The expansion keeps going so that every problem is reported
+/// at once; the macro entry point emits the collected errors as `compile_error!`.
+/// An attribute may be parsed by several extractors, so repeated errors are dropped.
+pub fn emit_error(err: syn::Error) {
+    ERRORS.with_borrow_mut(|errors| {
+        let key = |e: &syn::Error| (e.to_string(), format!("{:?}", e.span()));
+        if !errors.iter().any(|e| key(e) == key(&err)) {
+            errors.push(err);
+        }
+    });
+}
+
+/// Takes the errors recorded since the last call, combined into one.
+pub fn take_errors() -> Option<syn::Error> {
+    ERRORS.with_borrow_mut(std::mem::take).into_iter().reduce(|mut all, err| {
+        all.combine(err);
+        all
+    })
+}
@@ -0,0 +1,8 @@
+//! Diagnostics of the macros: each file in `tests/ui` must fail with the recorded error.
+//! Run with `TRYBUILD=overwrite` to update the `.stderr` files after changing a message.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -76,3 +76,30 @@ pub mod test {
         }
     }
 }
+
+pub mod models {
+    use super::*;
+    #[derive(XrossClass, Clone)]
+    pub struct CrossModuleService {
+        pub val: i32,
+    }
+}
+
+pub mod services {
+    use super::*;
+    use crate::models::CrossModuleService;
+
+    // The impl block lives outside the module that derives the type.
+    #[xross_methods]
+    impl CrossModuleService {
+        #[xross_new]
+        pub fn new(val: i32) -> Self {
+            CrossModuleService { val }
+        }
+
+        #[xross_method]
+        pub fn doubled(&self) -> i32 {
+            self.val * 2
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use xross_macros::XrossClass;
+
+#[derive(XrossClass, Clone)]
+pub struct Counter {
+    #[xross_field(safety = Direct)]
+    pub value: i32,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+mod other {
+    pub struct XrossCancelToken;
+}
+
+use xross_macros::xross_function;
+
+#[xross_function]
+pub fn work(token: other::XrossCancelToken) -> u32 {
+    let _ = token;
+    0
+}
+
+fn main() {}
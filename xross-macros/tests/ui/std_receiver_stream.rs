@@ -0,0 +1,8 @@
+use xross_macros::xross_function;
+
+#[xross_function]
+pub fn lines() -> std::sync::mpsc::Receiver<String> {
+    std::sync::mpsc::channel().1
+}
+
+fn main() {}
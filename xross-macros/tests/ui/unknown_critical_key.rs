@@ -0,0 +1,14 @@
+use xross_macros::{XrossClass, xross_methods};
+
+#[derive(XrossClass, Clone)]
+pub struct Service;
+
+#[xross_methods]
+impl Service {
+    #[xross_method(critical(heap_acess))]
+    pub fn run(&self) -> i32 {
+        1
+    }
+}
+
+fn main() {}
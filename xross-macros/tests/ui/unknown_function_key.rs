@@ -0,0 +1,8 @@
+use xross_macros::xross_function;
+
+#[xross_function(pakage = "tools")]
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn main() {}
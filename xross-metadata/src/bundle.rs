@@ -0,0 +1,154 @@
+use crate::{XrossDefinition, XrossMetadataError};
+use serde::{Deserialize, Serialize};
+
+/// Version of the metadata format described by this crate.
+/// Bumped whenever a change would make older readers misinterpret the metadata.
+pub const XROSS_METADATA_FORMAT_VERSION: u32 = 3;
+
+/// A crate whose definitions are part of a bundle.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossCrateInfo {
+    /// Package name as written in `Cargo.toml`.
+    pub name: String,
+    /// Package version as written in `Cargo.toml`.
+    pub version: String,
+}
+
+/// Byte order of a target.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum XrossEndian {
+    Little,
+    Big,
+}
+
+/// The platform a library was compiled for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossTarget {
+    /// Target triple, e.g. `x86_64-unknown-linux-gnu`.
+    #[serde(rename = "target")]
+    pub triple: String,
+    /// Width of pointers, `usize` and `isize` in bits.
+    pub pointer_width: u32,
+    pub endian: XrossEndian,
+}
+
+/// The metadata of one compiled library, as returned by `xross_metadata`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossMetadataBundle {
+    /// Format of this bundle. Readers must reject versions newer than they support.
+    pub format_version: u32,
+    /// Crates that contributed definitions, ordered by name.
+    pub crates: Vec<XrossCrateInfo>,
+    /// Target the library was compiled for. Serialized as the `target` triple,
+    /// `pointerWidth` and `endian` fields.
+    #[serde(flatten)]
+    pub target: XrossTarget,
+    /// Definitions merged by signature.
+    pub definitions: Vec<XrossDefinition>,
+}
+
+impl XrossMetadataBundle {
+    /// Creates a bundle of the current format version.
+    pub fn new(
+        crates: Vec<XrossCrateInfo>,
+        target: XrossTarget,
+        definitions: Vec<XrossDefinition>,
+    ) -> Self {
+        Self { format_version: XROSS_METADATA_FORMAT_VERSION, crates, target, definitions }
+    }
+
+    /// Parses a bundle, checking its format version before anything else so that a newer
+    /// format is reported as such rather than as a parse error.
+    pub fn from_json(json: &str) -> Result<Self, XrossMetadataError> {
+        let parse_error =
+            |source| XrossMetadataError::Parse { origin: "metadata bundle".to_string(), source };
+        let value: serde_json::Value = serde_json::from_str(json).map_err(parse_error)?;
+        let found = value.get("formatVersion").and_then(serde_json::Value::as_u64).unwrap_or(0);
+        check_format_version(found, "metadata bundle")?;
+        serde_json::from_value(value).map_err(parse_error)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialize xross metadata bundle")
+    }
+}
+
+/// Fails if metadata of format `found` cannot be read by this version of the crate.
+pub(crate) fn check_format_version(found: u64, origin: &str) -> Result<(), XrossMetadataError> {
+    if found == 0 || found > XROSS_METADATA_FORMAT_VERSION as u64 {
+        return Err(XrossMetadataError::UnsupportedVersion {
+            origin: origin.to_string(),
+            found,
+            supported: XROSS_METADATA_FORMAT_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the JSON Schema of [`XrossMetadataBundle`].
+#[cfg(feature = "schema")]
+pub fn xross_metadata_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(XrossMetadataBundle))
+        .expect("Failed to serialize xross metadata schema")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    fn bundle() -> XrossMetadataBundle {
+        let crates =
+            vec![XrossCrateInfo { name: "demo".to_string(), version: "1.0.0".to_string() }];
+        let target = XrossTarget {
+            triple: "x86_64-unknown-linux-gnu".to_string(),
+            pointer_width: 64,
+            endian: XrossEndian::Little,
+        };
+        XrossMetadataBundle::new(crates, target, vec![XrossDefinition::Struct(point())])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = bundle().to_json();
+        assert!(json.contains(&format!("\"formatVersion\":{}", XROSS_METADATA_FORMAT_VERSION)));
+        assert!(json.contains("\"target\":\"x86_64-unknown-linux-gnu\",\"pointerWidth\":64"));
+
+        let parsed = XrossMetadataBundle::from_json(&json).unwrap();
+        assert_eq!(parsed.crates, bundle().crates);
+        assert_eq!(parsed.target, bundle().target);
+        assert_eq!(parsed.definitions[0].signature(), "demo.Point");
+    }
+
+    #[test]
+    fn test_format_version() {
+        let json = bundle().to_json();
+        let current = format!("\"formatVersion\":{}", XROSS_METADATA_FORMAT_VERSION);
+        let newer = json.replacen(&current, "\"formatVersion\":99", 1);
+        assert!(matches!(
+            XrossMetadataBundle::from_json(&newer),
+            Err(XrossMetadataError::UnsupportedVersion { found: 99, .. })
+        ));
+        let unversioned = json.replacen(&format!("{},", current), "", 1);
+        assert!(matches!(
+            XrossMetadataBundle::from_json(&unversioned),
+            Err(XrossMetadataError::UnsupportedVersion { found: 0, .. })
+        ));
+        assert!(matches!(
+            XrossMetadataBundle::from_json("[]"),
+            Err(XrossMetadataError::UnsupportedVersion { found: 0, .. })
+        ));
+        assert!(matches!(
+            XrossMetadataBundle::from_json("{"),
+            Err(XrossMetadataError::Parse { .. })
+        ));
+    }
+}
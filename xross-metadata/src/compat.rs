@@ -0,0 +1,725 @@
+use crate::{
+    HandleMode, Ownership, XrossDefinition, XrossField, XrossMetadataSet, XrossMethod, XrossType,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Whether existing bindings keep working after a change.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum XrossCompatibility {
+    /// Bindings generated from the old metadata work with the new library.
+    Compatible,
+    /// Bindings generated from the old metadata must be regenerated.
+    Breaking,
+}
+
+/// What changed between two metadata snapshots.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum XrossChangeKind {
+    DefinitionAdded,
+    DefinitionRemoved,
+    /// A definition changed between struct, enum, opaque and function.
+    KindChanged,
+    /// A type stopped or started being passed by value.
+    CopyChanged,
+    /// A struct stopped or started being passed as its C layout instead of a handle.
+    ByValueChanged,
+    SymbolAdded,
+    SymbolRemoved,
+    /// A method's receiver (static, `&self`, `&mut self`, `self`) changed.
+    ReceiverChanged,
+    /// The number of arguments changed, including the trailing `timeout_ms` argument.
+    ArgumentCountChanged,
+    ArgumentTypeChanged,
+    /// An argument kept its type but is borrowed or owned differently.
+    ArgumentOwnershipChanged,
+    ArgumentRenamed,
+    ReturnTypeChanged,
+    ReturnOwnershipChanged,
+    /// A method became async or sync, changed how panics are returned, or started or
+    /// stopped returning its objects `contiguous`.
+    CallingConventionChanged,
+    HandleModeChanged,
+    ThreadSafetyChanged,
+    FieldAdded,
+    FieldRemoved,
+    FieldTypeChanged,
+    FieldOwnershipChanged,
+    VariantAdded,
+    VariantRemoved,
+    VariantsReordered,
+    /// The size or alignment of a type changed.
+    LayoutChanged,
+}
+
+/// A single difference between two metadata snapshots.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossChange {
+    pub kind: XrossChangeKind,
+    pub compatibility: XrossCompatibility,
+    /// Signature of the definition the change belongs to.
+    pub signature: String,
+    /// Method symbol, field, argument or variant that changed, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member: Option<String>,
+    /// Human-readable description, e.g. the old and new type.
+    pub detail: String,
+}
+
+/// Every change between two metadata snapshots, classified as compatible or breaking.
+///
+/// Serializes to JSON for release pipelines; [`is_breaking`] tells whether bindings
+/// generated from the old snapshot must be regenerated.
+///
+/// [`is_breaking`]: XrossCompatReport::is_breaking
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossCompatReport {
+    pub breaking: bool,
+    pub changes: Vec<XrossChange>,
+}
+
+impl XrossCompatReport {
+    /// Compares the metadata bindings were generated from (`old`) with a new release (`new`).
+    /// Both sets should be resolved, so that object types use full signatures.
+    pub fn between(old: &XrossMetadataSet, new: &XrossMetadataSet) -> Self {
+        let mut diff = Diff::default();
+        for old_def in old {
+            match new.get(old_def.signature()) {
+                Some(new_def) => diff.definition(old_def, new_def),
+                None => diff.push(
+                    XrossChangeKind::DefinitionRemoved,
+                    XrossCompatibility::Breaking,
+                    old_def.signature(),
+                    None,
+                    format!("{} was removed", kind_name(old_def)),
+                ),
+            }
+        }
+        for new_def in new {
+            if old.get(new_def.signature()).is_none() {
+                diff.push(
+                    XrossChangeKind::DefinitionAdded,
+                    XrossCompatibility::Compatible,
+                    new_def.signature(),
+                    None,
+                    format!("{} was added", kind_name(new_def)),
+                );
+            }
+        }
+        let breaking = diff.changes.iter().any(|c| c.compatibility == XrossCompatibility::Breaking);
+        Self { breaking, changes: diff.changes }
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.breaking
+    }
+
+    /// Returns only the changes that break existing bindings.
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &XrossChange> {
+        self.changes.iter().filter(|c| c.compatibility == XrossCompatibility::Breaking)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize xross compat report")
+    }
+}
+
+fn kind_name(def: &XrossDefinition) -> &'static str {
+    match def {
+        XrossDefinition::Struct(_) => "struct",
+        XrossDefinition::Enum(_) => "enum",
+        XrossDefinition::Opaque(_) => "opaque",
+        XrossDefinition::Function(_) => "function",
+    }
+}
+
+/// Returns the type with crate details cleared, and with every object ownership set to
+/// `Owned` unless `keep_ownership`, so that types can be compared by shape.
+fn normalize(ty: &XrossType, keep_ownership: bool) -> XrossType {
+    let mut ty = ty.clone();
+    ty.visit_mut(&mut |t| {
+        if let XrossType::Object { ownership, crate_name, symbol_prefix, .. } = t {
+            if !keep_ownership {
+                *ownership = Ownership::Owned;
+            }
+            *crate_name = None;
+            *symbol_prefix = None;
+        }
+    });
+    ty
+}
+
+enum TypeChange {
+    /// The type itself changed.
+    Shape,
+    /// Only how objects are borrowed or owned changed.
+    Ownership,
+}
+
+fn type_change(old: &XrossType, new: &XrossType) -> Option<TypeChange> {
+    if normalize(old, false) != normalize(new, false) {
+        Some(TypeChange::Shape)
+    } else if normalize(old, true) != normalize(new, true) {
+        Some(TypeChange::Ownership)
+    } else {
+        None
+    }
+}
+
+/// Whether a method's return travels through a different wrapper in `mode`.
+fn wraps_return(mode: HandleMode) -> bool {
+    matches!(mode, HandleMode::Panicable | HandleMode::Blocking)
+}
+
+#[derive(Default)]
+struct Diff {
+    changes: Vec<XrossChange>,
+}
+
+impl Diff {
+    fn push(
+        &mut self,
+        kind: XrossChangeKind,
+        compatibility: XrossCompatibility,
+        signature: &str,
+        member: Option<&str>,
+        detail: String,
+    ) {
+        self.changes.push(XrossChange {
+            kind,
+            compatibility,
+            signature: signature.to_string(),
+            member: member.map(str::to_string),
+            detail,
+        });
+    }
+
+    fn breaking(&mut self, kind: XrossChangeKind, sig: &str, member: Option<&str>, detail: String) {
+        self.push(kind, XrossCompatibility::Breaking, sig, member, detail);
+    }
+
+    fn definition(&mut self, old: &XrossDefinition, new: &XrossDefinition) {
+        let sig = old.signature();
+        match (old, new) {
+            (XrossDefinition::Struct(a), XrossDefinition::Struct(b)) => {
+                self.copy(sig, a.is_copy, b.is_copy);
+                if a.by_value != b.by_value {
+                    let detail =
+                        if b.by_value { "became by-value" } else { "is no longer by-value" };
+                    self.breaking(XrossChangeKind::ByValueChanged, sig, None, detail.to_string());
+                }
+                self.fields(sig, "", &a.fields, &b.fields, a.is_copy);
+            }
+            (XrossDefinition::Opaque(a), XrossDefinition::Opaque(b)) => {
+                self.copy(sig, a.is_copy, b.is_copy);
+                self.fields(sig, "", &a.fields, &b.fields, a.is_copy);
+            }
+            (XrossDefinition::Enum(a), XrossDefinition::Enum(b)) => {
+                self.copy(sig, a.is_copy, b.is_copy);
+                let old_names: Vec<&str> = a.variants.iter().map(|v| v.name.as_str()).collect();
+                let new_names: Vec<&str> = b.variants.iter().map(|v| v.name.as_str()).collect();
+                for v in &a.variants {
+                    match b.variants.iter().find(|n| n.name == v.name) {
+                        Some(n) => {
+                            let prefix = format!("{}.", v.name);
+                            self.fields(sig, &prefix, &v.fields, &n.fields, a.is_copy)
+                        }
+                        None => self.breaking(
+                            XrossChangeKind::VariantRemoved,
+                            sig,
+                            Some(&v.name),
+                            format!("variant {} was removed", v.name),
+                        ),
+                    }
+                }
+                for v in b.variants.iter().filter(|v| !old_names.contains(&v.name.as_str())) {
+                    // Old bindings cannot decode a value of a variant they do not know.
+                    self.breaking(
+                        XrossChangeKind::VariantAdded,
+                        sig,
+                        Some(&v.name),
+                        format!("variant {} was added", v.name),
+                    );
+                }
+                let kept_old: Vec<&str> =
+                    old_names.iter().copied().filter(|n| new_names.contains(n)).collect();
+                let kept_new: Vec<&str> =
+                    new_names.iter().copied().filter(|n| old_names.contains(n)).collect();
+                if kept_old != kept_new {
+                    self.breaking(
+                        XrossChangeKind::VariantsReordered,
+                        sig,
+                        None,
+                        format!(
+                            "variants [{}] became [{}]",
+                            kept_old.join(", "),
+                            kept_new.join(", ")
+                        ),
+                    );
+                }
+            }
+            (XrossDefinition::Function(_), XrossDefinition::Function(_)) => {}
+            _ => {
+                self.breaking(
+                    XrossChangeKind::KindChanged,
+                    sig,
+                    None,
+                    format!("{} became {}", kind_name(old), kind_name(new)),
+                );
+                return;
+            }
+        }
+        self.layout(old, new);
+        self.methods(sig, old.methods(), new.methods());
+    }
+
+    /// Compares sizes when both snapshots carry layouts (bundles from compiled libraries).
+    /// Bindings read sizes and offsets from `{prefix}_layout` when they load, so only Copy
+    /// types, which are passed by value, break.
+    fn layout(&mut self, old: &XrossDefinition, new: &XrossDefinition) {
+        let (Some(a), Some(b)) = (old.layout(), new.layout()) else { return };
+        if (a.size, a.align) == (b.size, b.align) {
+            return;
+        }
+        let is_copy = match old {
+            XrossDefinition::Struct(s) => s.is_copy,
+            XrossDefinition::Enum(e) => e.is_copy,
+            XrossDefinition::Opaque(o) => o.is_copy,
+            XrossDefinition::Function(_) => false,
+        };
+        let compatibility =
+            if is_copy { XrossCompatibility::Breaking } else { XrossCompatibility::Compatible };
+        self.push(
+            XrossChangeKind::LayoutChanged,
+            compatibility,
+            old.signature(),
+            None,
+            format!("size {} (align {}) became {} (align {})", a.size, a.align, b.size, b.align),
+        );
+    }
+
+    fn copy(&mut self, sig: &str, old: bool, new: bool) {
+        if old != new {
+            let detail = if new { "became Copy" } else { "is no longer Copy" };
+            self.breaking(XrossChangeKind::CopyChanged, sig, None, detail.to_string());
+        }
+    }
+
+    /// Compares fields by name. Offsets are read from `{prefix}_layout` at load time, so only
+    /// Copy types, which bindings copy inline, break when fields are added.
+    fn fields(
+        &mut self,
+        sig: &str,
+        prefix: &str,
+        old: &[XrossField],
+        new: &[XrossField],
+        is_copy: bool,
+    ) {
+        for field in old {
+            let member = format!("{}{}", prefix, field.name);
+            let Some(next) = new.iter().find(|f| f.name == field.name) else {
+                self.breaking(
+                    XrossChangeKind::FieldRemoved,
+                    sig,
+                    Some(&member),
+                    format!("field {} was removed", member),
+                );
+                continue;
+            };
+            match type_change(&field.ty, &next.ty) {
+                Some(TypeChange::Shape) => self.breaking(
+                    XrossChangeKind::FieldTypeChanged,
+                    sig,
+                    Some(&member),
+                    format!("{:?} became {:?}", field.ty, next.ty),
+                ),
+                Some(TypeChange::Ownership) => self.breaking(
+                    XrossChangeKind::FieldOwnershipChanged,
+                    sig,
+                    Some(&member),
+                    format!("{:?} became {:?}", field.ty, next.ty),
+                ),
+                None => {}
+            }
+            if field.safety != next.safety {
+                self.breaking(
+                    XrossChangeKind::ThreadSafetyChanged,
+                    sig,
+                    Some(&member),
+                    format!("{:?} became {:?}", field.safety, next.safety),
+                );
+            }
+        }
+        for field in new.iter().filter(|f| !old.iter().any(|o| o.name == f.name)) {
+            let member = format!("{}{}", prefix, field.name);
+            let compatibility =
+                if is_copy { XrossCompatibility::Breaking } else { XrossCompatibility::Compatible };
+            self.push(
+                XrossChangeKind::FieldAdded,
+                compatibility,
+                sig,
+                Some(&member),
+                format!("field {} was added", member),
+            );
+        }
+    }
+
+    /// Compares methods by exported symbol.
+    fn methods(&mut self, sig: &str, old: &[XrossMethod], new: &[XrossMethod]) {
+        let new_by_symbol: BTreeMap<&str, &XrossMethod> =
+            new.iter().map(|m| (m.symbol.as_str(), m)).collect();
+        for method in old {
+            match new_by_symbol.get(method.symbol.as_str()) {
+                Some(next) => self.method(sig, method, next),
+                None => self.breaking(
+                    XrossChangeKind::SymbolRemoved,
+                    sig,
+                    Some(&method.symbol),
+                    format!("{} is no longer exported", method.name),
+                ),
+            }
+        }
+        for method in new.iter().filter(|m| !old.iter().any(|o| o.symbol == m.symbol)) {
+            self.push(
+                XrossChangeKind::SymbolAdded,
+                XrossCompatibility::Compatible,
+                sig,
+                Some(&method.symbol),
+                format!("{} is now exported", method.name),
+            );
+        }
+    }
+
+    fn method(&mut self, sig: &str, old: &XrossMethod, new: &XrossMethod) {
+        let symbol = Some(old.symbol.as_str());
+        if old.method_type != new.method_type {
+            self.breaking(
+                XrossChangeKind::ReceiverChanged,
+                sig,
+                symbol,
+                format!("{:?} became {:?}", old.method_type, new.method_type),
+            );
+        }
+        if old.is_async != new.is_async
+            || old.contiguous != new.contiguous
+            || wraps_return(old.handle_mode) != wraps_return(new.handle_mode)
+        {
+            let convention = |m: &XrossMethod| {
+                format!(
+                    "{}{}{:?}",
+                    if m.is_async { "async " } else { "" },
+                    if m.contiguous { "contiguous " } else { "" },
+                    m.handle_mode
+                )
+            };
+            self.breaking(
+                XrossChangeKind::CallingConventionChanged,
+                sig,
+                symbol,
+                format!("{} became {}", convention(old), convention(new)),
+            );
+        } else if old.handle_mode != new.handle_mode {
+            // Normal and critical calls share the same signature; only the linker option differs.
+            self.push(
+                XrossChangeKind::HandleModeChanged,
+                XrossCompatibility::Compatible,
+                sig,
+                symbol,
+                format!("{:?} became {:?}", old.handle_mode, new.handle_mode),
+            );
+        }
+        if old.safety != new.safety {
+            self.breaking(
+                XrossChangeKind::ThreadSafetyChanged,
+                sig,
+                symbol,
+                format!("{:?} became {:?}", old.safety, new.safety),
+            );
+        }
+
+        if old.args.len() != new.args.len() || old.timeout_ms.is_some() != new.timeout_ms.is_some()
+        {
+            self.breaking(
+                XrossChangeKind::ArgumentCountChanged,
+                sig,
+                symbol,
+                format!(
+                    "{} argument(s){} became {}{}",
+                    old.args.len(),
+                    if old.timeout_ms.is_some() { " and a timeout" } else { "" },
+                    new.args.len(),
+                    if new.timeout_ms.is_some() { " and a timeout" } else { "" },
+                ),
+            );
+        }
+        for (a, b) in old.args.iter().zip(&new.args) {
+            let member = format!("{}({})", old.symbol, a.name);
+            match type_change(&a.ty, &b.ty) {
+                Some(TypeChange::Shape) => self.breaking(
+                    XrossChangeKind::ArgumentTypeChanged,
+                    sig,
+                    Some(&member),
+                    format!("{:?} became {:?}", a.ty, b.ty),
+                ),
+                Some(TypeChange::Ownership) => self.breaking(
+                    XrossChangeKind::ArgumentOwnershipChanged,
+                    sig,
+                    Some(&member),
+                    format!("{:?} became {:?}", a.ty, b.ty),
+                ),
+                None => {}
+            }
+            if a.name != b.name {
+                self.push(
+                    XrossChangeKind::ArgumentRenamed,
+                    XrossCompatibility::Compatible,
+                    sig,
+                    Some(&member),
+                    format!("{} became {}", a.name, b.name),
+                );
+            }
+        }
+        match type_change(&old.ret, &new.ret) {
+            Some(TypeChange::Shape) => self.breaking(
+                XrossChangeKind::ReturnTypeChanged,
+                sig,
+                symbol,
+                format!("{:?} became {:?}", old.ret, new.ret),
+            ),
+            Some(TypeChange::Ownership) => self.breaking(
+                XrossChangeKind::ReturnOwnershipChanged,
+                sig,
+                symbol,
+                format!("{:?} became {:?}", old.ret, new.ret),
+            ),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::{ThreadSafety, XrossEnum, XrossMethodType, XrossStruct, XrossVariant};
+
+    /// Compares `point()` and `color()` with the same definitions after `change_point` and
+    /// `change_color`.
+    fn report(
+        change_point: impl FnOnce(&mut XrossStruct),
+        change_color: impl FnOnce(&mut XrossEnum),
+    ) -> XrossCompatReport {
+        let old = set([XrossDefinition::Struct(point()), XrossDefinition::Enum(color())]);
+        let (mut p, mut c) = (point(), color());
+        change_point(&mut p);
+        change_color(&mut c);
+        let new = set([XrossDefinition::Struct(p), XrossDefinition::Enum(c)]);
+        XrossCompatReport::between(&old, &new)
+    }
+
+    fn kinds(report: &XrossCompatReport) -> Vec<(XrossChangeKind, XrossCompatibility)> {
+        report.changes.iter().map(|c| (c.kind, c.compatibility)).collect()
+    }
+
+    fn point_change(
+        change: impl FnOnce(&mut XrossStruct),
+    ) -> Vec<(XrossChangeKind, XrossCompatibility)> {
+        kinds(&report(change, |_| {}))
+    }
+
+    use XrossChangeKind as K;
+    const BREAKING: XrossCompatibility = XrossCompatibility::Breaking;
+    const COMPATIBLE: XrossCompatibility = XrossCompatibility::Compatible;
+
+    #[test]
+    fn test_identical_snapshots() {
+        let report = report(|_| {}, |_| {});
+        assert!(report.changes.is_empty());
+        assert!(!report.is_breaking());
+    }
+
+    #[test]
+    fn test_definitions() {
+        let old = set([XrossDefinition::Struct(point())]);
+        let new = set([XrossDefinition::Enum(color())]);
+        let report = XrossCompatReport::between(&old, &new);
+        assert_eq!(
+            kinds(&report),
+            [(K::DefinitionRemoved, BREAKING), (K::DefinitionAdded, COMPATIBLE)]
+        );
+
+        let mut as_enum = color();
+        as_enum.signature = "demo.Point".to_string();
+        let new = set([XrossDefinition::Enum(as_enum)]);
+        let report = XrossCompatReport::between(&old, &new);
+        assert_eq!(kinds(&report), [(K::KindChanged, BREAKING)]);
+    }
+
+    #[test]
+    fn test_symbols() {
+        assert_eq!(
+            point_change(|p| {
+                p.methods.remove(0);
+            }),
+            [(K::SymbolRemoved, BREAKING)]
+        );
+        let added = point_change(|p| p.methods.push(method("area", vec![], XrossType::F64)));
+        assert_eq!(added, [(K::SymbolAdded, COMPATIBLE)]);
+        // A renamed method keeps its symbol, so it is the same export.
+        assert!(point_change(|p| p.methods[0].name = "length".to_string()).is_empty());
+    }
+
+    #[test]
+    fn test_method_signature() {
+        let receiver = point_change(|p| p.methods[0].method_type = XrossMethodType::MutInstance);
+        assert_eq!(receiver, [(K::ReceiverChanged, BREAKING)]);
+        let count = point_change(|p| p.methods[0].args.push(field("unit", XrossType::Bool)));
+        assert_eq!(count, [(K::ArgumentCountChanged, BREAKING)]);
+        let timeout = point_change(|p| p.methods[0].timeout_ms = Some(100));
+        assert_eq!(timeout, [(K::ArgumentCountChanged, BREAKING)]);
+        let arg = point_change(|p| p.methods[1].args[0].ty = XrossType::I64);
+        assert_eq!(arg, [(K::ArgumentTypeChanged, BREAKING)]);
+        let renamed = point_change(|p| p.methods[1].args[0].name = "by".to_string());
+        assert_eq!(renamed, [(K::ArgumentRenamed, COMPATIBLE)]);
+        let ret = point_change(|p| p.methods[0].ret = XrossType::F32);
+        assert_eq!(ret, [(K::ReturnTypeChanged, BREAKING)]);
+        let safety = point_change(|p| p.methods[0].safety = ThreadSafety::Atomic);
+        assert_eq!(safety, [(K::ThreadSafetyChanged, BREAKING)]);
+    }
+
+    #[test]
+    fn test_ownership() {
+        let borrowed = point_change(|p| {
+            p.methods[2].args[0].ty = XrossType::object("demo.Point", Ownership::Owned)
+        });
+        assert_eq!(borrowed, [(K::ArgumentOwnershipChanged, BREAKING)]);
+        let ret =
+            point_change(|p| p.methods[3].ret = XrossType::object("demo.Point", Ownership::Boxed));
+        assert_eq!(ret, [(K::ReturnOwnershipChanged, BREAKING)]);
+
+        // Crate details are filled in by `resolve` and do not change the type.
+        let resolved = point_change(|p| {
+            if let XrossType::Object { crate_name, .. } = &mut p.methods[2].args[0].ty {
+                *crate_name = Some("demo".to_string());
+            }
+        });
+        assert!(resolved.is_empty());
+    }
+
+    #[test]
+    fn test_calling_convention() {
+        let is_async = point_change(|p| p.methods[0].is_async = true);
+        assert_eq!(is_async, [(K::CallingConventionChanged, BREAKING)]);
+        let panicable = point_change(|p| p.methods[0].handle_mode = HandleMode::Panicable);
+        assert_eq!(panicable, [(K::CallingConventionChanged, BREAKING)]);
+        let critical = point_change(|p| {
+            p.methods[0].handle_mode = HandleMode::Critical { allow_heap_access: false }
+        });
+        assert_eq!(critical, [(K::HandleModeChanged, COMPATIBLE)]);
+    }
+
+    #[test]
+    fn test_fields() {
+        let removed = point_change(|p| {
+            p.fields.pop();
+        });
+        assert_eq!(removed, [(K::FieldRemoved, BREAKING)]);
+        let added = point_change(|p| p.fields.push(field("z", XrossType::I32)));
+        assert_eq!(added, [(K::FieldAdded, COMPATIBLE)]);
+        let changed = point_change(|p| p.fields[0].ty = XrossType::F32);
+        assert_eq!(changed, [(K::FieldTypeChanged, BREAKING)]);
+        let safety = point_change(|p| p.fields[0].safety = ThreadSafety::Atomic);
+        assert_eq!(safety, [(K::ThreadSafetyChanged, BREAKING)]);
+
+        // Bindings copy Copy types inline, so a new field changes what they read.
+        let copy_added =
+            kinds(&report(|_| {}, |c| c.variants[0].fields.push(field("shade", XrossType::U8))));
+        assert_eq!(copy_added, [(K::FieldAdded, BREAKING)]);
+    }
+
+    #[test]
+    fn test_copy_and_by_value() {
+        let copy = point_change(|p| p.is_copy = true);
+        assert_eq!(copy, [(K::CopyChanged, BREAKING)]);
+        let by_value = point_change(|p| p.by_value = true);
+        assert_eq!(by_value, [(K::ByValueChanged, BREAKING)]);
+    }
+
+    #[test]
+    fn test_variants() {
+        let change = |f: fn(&mut XrossEnum)| kinds(&report(|_| {}, f));
+        assert_eq!(
+            change(|c| {
+                c.variants.pop();
+            }),
+            [(K::VariantRemoved, BREAKING)]
+        );
+        let added = change(|c| {
+            c.variants.push(XrossVariant {
+                name: "Alpha".to_string(),
+                fields: vec![],
+                docs: vec![],
+            })
+        });
+        assert_eq!(added, [(K::VariantAdded, BREAKING)]);
+        assert_eq!(change(|c| c.variants.swap(0, 2)), [(K::VariantsReordered, BREAKING)]);
+        // Removing a variant does not also report the others as reordered.
+        assert_eq!(
+            change(|c| {
+                c.variants.remove(0);
+            }),
+            [(K::VariantRemoved, BREAKING)]
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let with_layout = |size| {
+            let mut p = point();
+            p.layout = Some(layout(size));
+            XrossDefinition::Struct(p)
+        };
+        let old = set([with_layout(8)]);
+        let grown = set([with_layout(16)]);
+        let report = XrossCompatReport::between(&old, &grown);
+        assert_eq!(kinds(&report), [(K::LayoutChanged, COMPATIBLE)]);
+
+        let copy = |size| {
+            let XrossDefinition::Struct(mut p) = with_layout(size) else { unreachable!() };
+            p.is_copy = true;
+            XrossDefinition::Struct(p)
+        };
+        let report = XrossCompatReport::between(&set([copy(8)]), &set([copy(16)]));
+        assert_eq!(kinds(&report), [(K::LayoutChanged, BREAKING)]);
+
+        // Sizes are only compared when both snapshots carry them.
+        let report = XrossCompatReport::between(&set([XrossDefinition::Struct(point())]), &grown);
+        assert!(report.changes.is_empty());
+    }
+
+    #[test]
+    fn test_report_json() {
+        let report = report(
+            |p| {
+                p.methods.remove(0);
+            },
+            |_| {},
+        );
+        assert!(report.is_breaking());
+        assert_eq!(report.breaking_changes().count(), 1);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["breaking"], true);
+        assert_eq!(json["changes"][0]["kind"], "symbolRemoved");
+        assert_eq!(json["changes"][0]["compatibility"], "breaking");
+        assert_eq!(json["changes"][0]["signature"], "demo.Point");
+        assert_eq!(json["changes"][0]["member"], "demo_Point_len");
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{XrossDefinition, XrossField, XrossMetadataSet, XrossType};
+use std::fmt::Write;
+
+/// 64-bit FNV-1a, the hash behind every xross fingerprint.
+/// Simple enough to be reimplemented by binding generators in other languages.
+pub const fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+impl XrossType {
+    /// Canonical text of the type used in fingerprints, e.g. `Option<Object<Point:Ref>>`.
+    /// Objects use their bare name, so a type hashes the same before and after resolution.
+    pub fn abi_tag(&self) -> String {
+        let wrap = |name: &str, inner: &XrossType| format!("{}<{}>", name, inner.abi_tag());
+        match self {
+            XrossType::Void => "Void".to_string(),
+            XrossType::Bool => "Bool".to_string(),
+            XrossType::I8 => "I8".to_string(),
+            XrossType::U8 => "U8".to_string(),
+            XrossType::I16 => "I16".to_string(),
+            XrossType::U16 => "U16".to_string(),
+            XrossType::I32 => "I32".to_string(),
+            XrossType::U32 => "U32".to_string(),
+            XrossType::I64 => "I64".to_string(),
+            XrossType::U64 => "U64".to_string(),
+            XrossType::ISize => "ISize".to_string(),
+            XrossType::USize => "USize".to_string(),
+            XrossType::F32 => "F32".to_string(),
+            XrossType::F64 => "F64".to_string(),
+            XrossType::Pointer => "Pointer".to_string(),
+            XrossType::String => "String".to_string(),
+            XrossType::CancelToken => "CancelToken".to_string(),
+            XrossType::Slice(inner) => wrap("Slice", inner),
+            XrossType::Vec(inner) => wrap("Vec", inner),
+            XrossType::Option(inner) => wrap("Option", inner),
+            XrossType::Async(inner) => wrap("Async", inner),
+            XrossType::Stream(inner) => wrap("Stream", inner),
+            XrossType::Iterator(inner) => wrap("Iterator", inner),
+            XrossType::Result { ok, err } => format!("Result<{},{}>", ok.abi_tag(), err.abi_tag()),
+            XrossType::Object { signature, ownership, .. } => {
+                let name = signature.rsplit('.').next().unwrap_or(signature);
+                format!("Object<{}:{:?}>", name, ownership)
+            }
+        }
+    }
+}
+
+fn fields_text(fields: &[XrossField]) -> String {
+    fields.iter().map(|f| format!("{}:{}", f.name, f.ty.abi_tag())).collect::<Vec<_>>().join(";")
+}
+
+impl XrossDefinition {
+    /// Text covered by [`layout_fingerprint`]: `name:tag` per field joined by `;`, and for
+    /// enums `Variant{fields}` per variant in declaration order.
+    ///
+    /// [`layout_fingerprint`]: XrossDefinition::layout_fingerprint
+    pub fn layout_text(&self) -> String {
+        match self {
+            XrossDefinition::Struct(s) => fields_text(&s.fields),
+            XrossDefinition::Opaque(o) => fields_text(&o.fields),
+            XrossDefinition::Enum(e) => e
+                .variants
+                .iter()
+                .map(|v| format!("{}{{{}}}", v.name, fields_text(&v.fields)))
+                .collect::<Vec<_>>()
+                .join(";"),
+            XrossDefinition::Function(_) => String::new(),
+        }
+    }
+
+    /// Fingerprint of what bindings assume about `{prefix}_layout`: the exposed fields, their
+    /// types and the variant order. Offsets and sizes are read from `_layout` at load time,
+    /// so they are not part of it. Exported as `{prefix}_layout_fingerprint`.
+    pub fn layout_fingerprint(&self) -> u64 {
+        fnv1a64(self.layout_text().as_bytes())
+    }
+}
+
+impl XrossMetadataSet {
+    /// Hash of everything bindings rely on: every definition's layout and the symbol,
+    /// receiver, argument and return types and calling convention of every method.
+    /// Names and docs that do not reach the native interface are not part of it.
+    pub fn metadata_hash(&self) -> u64 {
+        let mut text = String::new();
+        for def in self {
+            let by_value = matches!(def, XrossDefinition::Struct(s) if s.by_value);
+            let _ =
+                writeln!(text, "{} {} by_value={}", def.signature(), def.layout_text(), by_value);
+            let mut methods: Vec<_> = def.methods().iter().collect();
+            methods.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+            for m in methods {
+                let args: Vec<String> = m.args.iter().map(|a| a.ty.abi_tag()).collect();
+                let _ = writeln!(
+                    text,
+                    "  {}({}) -> {} {:?} {:?} {:?} async={} timeout={} contiguous={}",
+                    m.symbol,
+                    args.join(","),
+                    m.ret.abi_tag(),
+                    m.method_type,
+                    m.handle_mode,
+                    m.safety,
+                    m.is_async,
+                    m.timeout_ms.is_some(),
+                    m.contiguous
+                );
+            }
+        }
+        fnv1a64(text.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::{Ownership, XrossStruct};
+
+    #[test]
+    fn test_fnv1a64() {
+        assert_eq!(fnv1a64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_abi_tag() {
+        let point = XrossType::object("demo.Point", Ownership::Ref);
+        assert_eq!(
+            XrossType::Option(Box::new(point.clone())).abi_tag(),
+            "Option<Object<Point:Ref>>"
+        );
+        assert_eq!(XrossType::object("Point", Ownership::Ref).abi_tag(), point.abi_tag());
+        let result = XrossType::Result {
+            ok: Box::new(XrossType::Vec(Box::new(XrossType::U8))),
+            err: Box::new(XrossType::String),
+        };
+        assert_eq!(result.abi_tag(), "Result<Vec<U8>,String>");
+    }
+
+    #[test]
+    fn test_layout_fingerprint() {
+        let def = XrossDefinition::Struct(point());
+        assert_eq!(def.layout_text(), "x:I32;y:I32");
+        assert_eq!(def.layout_fingerprint(), fnv1a64(b"x:I32;y:I32"));
+        assert_eq!(XrossDefinition::Enum(color()).layout_text(), "Red{};Green{};Blue{}");
+
+        // Field types and variant order are covered; a method is not.
+        let mut wider = def.clone();
+        let XrossDefinition::Struct(s) = &mut wider else { unreachable!() };
+        s.fields[0].ty = XrossType::I64;
+        assert_ne!(wider.layout_fingerprint(), def.layout_fingerprint());
+
+        let mut reordered = color();
+        reordered.variants.swap(0, 1);
+        let reordered = XrossDefinition::Enum(reordered);
+        assert_ne!(
+            reordered.layout_fingerprint(),
+            XrossDefinition::Enum(color()).layout_fingerprint()
+        );
+
+        let mut fewer_methods = point();
+        fewer_methods.methods.pop();
+        assert_eq!(
+            XrossDefinition::Struct(fewer_methods).layout_fingerprint(),
+            def.layout_fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_metadata_hash() {
+        let hash = |p: XrossStruct| {
+            set([XrossDefinition::Struct(p), XrossDefinition::Enum(color())]).metadata_hash()
+        };
+        let base = hash(point());
+        assert_eq!(hash(point()), base);
+
+        let mut wider = point();
+        wider.fields[0].ty = XrossType::I64;
+        assert_ne!(hash(wider), base);
+
+        let mut asynchronous = point();
+        asynchronous.methods[0].is_async = true;
+        assert_ne!(hash(asynchronous), base);
+
+        let mut documented = point();
+        documented.docs.push("A point.".to_string());
+        documented.methods[0].docs.push("Its length.".to_string());
+        assert_eq!(hash(documented), base);
+    }
+}
@@ -0,0 +1,96 @@
+//! Definitions shared by the unit tests of this crate.
+
+use crate::{
+    HandleMode, Ownership, ThreadSafety, XrossDefinition, XrossEnum, XrossField, XrossFieldLayout,
+    XrossLayout, XrossMetadataSet, XrossMethod, XrossMethodType, XrossStruct, XrossType,
+    XrossVariant,
+};
+
+pub(crate) fn field(name: &str, ty: XrossType) -> XrossField {
+    XrossField { name: name.to_string(), ty, docs: vec![], safety: ThreadSafety::Lock }
+}
+
+pub(crate) fn method(name: &str, args: Vec<XrossField>, ret: XrossType) -> XrossMethod {
+    XrossMethod {
+        name: name.to_string(),
+        symbol: format!("demo_Point_{}", name),
+        method_type: XrossMethodType::ConstInstance,
+        handle_mode: HandleMode::Normal,
+        is_constructor: false,
+        is_default: false,
+        is_raw: false,
+        is_async: false,
+        concurrency: None,
+        timeout_ms: None,
+        contiguous: false,
+        args,
+        ret,
+        docs: vec![],
+        safety: ThreadSafety::Lock,
+    }
+}
+
+pub(crate) fn point() -> XrossStruct {
+    XrossStruct {
+        signature: "demo.Point".to_string(),
+        symbol_prefix: "demo_Point".to_string(),
+        package_name: "demo".to_string(),
+        name: "Point".to_string(),
+        fields: vec![field("x", XrossType::I32), field("y", XrossType::I32)],
+        methods: vec![
+            method("len", vec![], XrossType::F64),
+            method("scale", vec![field("factor", XrossType::I32)], XrossType::Void),
+            method(
+                "distance",
+                vec![field("other", XrossType::object("demo.Point", Ownership::Ref))],
+                XrossType::F64,
+            ),
+            method("copy", vec![], XrossType::object("demo.Point", Ownership::Owned)),
+        ],
+        docs: vec![],
+        is_copy: false,
+        by_value: false,
+        layout: None,
+    }
+}
+
+pub(crate) fn color() -> XrossEnum {
+    let variant =
+        |name: &str| XrossVariant { name: name.to_string(), fields: vec![], docs: vec![] };
+    XrossEnum {
+        signature: "demo.Color".to_string(),
+        symbol_prefix: "demo_Color".to_string(),
+        package_name: "demo".to_string(),
+        name: "Color".to_string(),
+        variants: vec![variant("Red"), variant("Green"), variant("Blue")],
+        methods: vec![],
+        docs: vec![],
+        is_copy: true,
+        layout: None,
+    }
+}
+
+pub(crate) fn layout(size: u64) -> XrossLayout {
+    let field = |name: &str, offset| XrossFieldLayout {
+        name: name.to_string(),
+        offset,
+        size: 4,
+        align: 4,
+        type_name: "i32".to_string(),
+    };
+    XrossLayout {
+        size,
+        align: 4,
+        fields: vec![field("x", 0), field("y", 4)],
+        tag: None,
+        variants: vec![],
+    }
+}
+
+pub(crate) fn set(defs: impl IntoIterator<Item = XrossDefinition>) -> XrossMetadataSet {
+    let mut set = XrossMetadataSet::new();
+    for def in defs {
+        set.insert(def).unwrap();
+    }
+    set
+}
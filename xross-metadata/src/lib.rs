@@ -1,2 +1,16 @@
 mod metadata;
 pub use metadata::*;
+mod bundle;
+pub use bundle::*;
+mod compat;
+pub use compat::*;
+mod fingerprint;
+#[cfg(test)]
+mod fixtures;
+pub use fingerprint::*;
+mod set;
+pub use set::*;
+mod symbols;
+pub use symbols::*;
+mod targets;
+pub use targets::*;
@@ -4,6 +4,8 @@ mod method;
 pub use method::*;
 mod types;
 pub use types::*;
+mod layout;
+pub use layout::*;
 mod class;
 
 pub use class::*;
@@ -1,8 +1,9 @@
-use crate::{XrossField, XrossMethod};
+use crate::{XrossField, XrossFieldLayout, XrossLayout, XrossMethod, XrossType};
 use serde::{Deserialize, Serialize};
 
 /// Represents the definition of a type shared between Rust and JVM.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "kind", rename_all = "camelCase")]
 pub enum XrossDefinition {
     /// A structured data type with named fields.
@@ -34,10 +35,105 @@ impl XrossDefinition {
             XrossDefinition::Function(f) => &f.name,
         }
     }
+    /// Returns the package of this definition. Empty for the root package.
+    pub fn package_name(&self) -> &str {
+        match self {
+            XrossDefinition::Struct(s) => &s.package_name,
+            XrossDefinition::Enum(e) => &e.package_name,
+            XrossDefinition::Opaque(o) => &o.package_name,
+            XrossDefinition::Function(f) => &f.package_name,
+        }
+    }
+
+    /// Returns the methods of this definition. A standalone function is its only method.
+    pub fn methods(&self) -> &[XrossMethod] {
+        match self {
+            XrossDefinition::Struct(s) => &s.methods,
+            XrossDefinition::Enum(e) => &e.methods,
+            XrossDefinition::Opaque(o) => &o.methods,
+            XrossDefinition::Function(f) => std::slice::from_ref(&f.method),
+        }
+    }
+
+    /// Calls `f` on every type used by this definition (fields, variants, arguments and returns).
+    pub fn visit_types(&self, f: &mut impl FnMut(&XrossType)) {
+        let fields: Vec<&XrossField> = match self {
+            XrossDefinition::Struct(s) => s.fields.iter().collect(),
+            XrossDefinition::Enum(e) => e.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+            XrossDefinition::Opaque(o) => o.fields.iter().collect(),
+            XrossDefinition::Function(_) => Vec::new(),
+        };
+        for field in fields {
+            field.ty.visit(f);
+        }
+        for method in self.methods() {
+            for arg in &method.args {
+                arg.ty.visit(f);
+            }
+            method.ret.visit(f);
+        }
+    }
+
+    /// Calls `f` on every type used by this definition (fields, variants, arguments and returns).
+    pub fn visit_types_mut(&mut self, f: &mut impl FnMut(&mut XrossType)) {
+        let (fields, methods): (Vec<&mut XrossField>, Vec<&mut XrossMethod>) = match self {
+            XrossDefinition::Struct(s) => {
+                (s.fields.iter_mut().collect(), s.methods.iter_mut().collect())
+            }
+            XrossDefinition::Enum(e) => (
+                e.variants.iter_mut().flat_map(|v| v.fields.iter_mut()).collect(),
+                e.methods.iter_mut().collect(),
+            ),
+            XrossDefinition::Opaque(o) => {
+                (o.fields.iter_mut().collect(), o.methods.iter_mut().collect())
+            }
+            XrossDefinition::Function(func) => (Vec::new(), vec![&mut func.method]),
+        };
+        for field in fields {
+            field.ty.visit_mut(f);
+        }
+        for method in methods {
+            for arg in &mut method.args {
+                arg.ty.visit_mut(f);
+            }
+            method.ret.visit_mut(f);
+        }
+    }
+
+    /// Returns the memory layout of this definition, when known.
+    pub fn layout(&self) -> Option<&XrossLayout> {
+        match self {
+            XrossDefinition::Struct(s) => s.layout.as_ref(),
+            XrossDefinition::Enum(e) => e.layout.as_ref(),
+            XrossDefinition::Opaque(o) => o.layout.as_ref(),
+            XrossDefinition::Function(_) => None,
+        }
+    }
+
+    /// Returns the layout slot of this definition, or `None` for standalone functions.
+    pub fn layout_mut(&mut self) -> Option<&mut Option<XrossLayout>> {
+        match self {
+            XrossDefinition::Struct(s) => Some(&mut s.layout),
+            XrossDefinition::Enum(e) => Some(&mut e.layout),
+            XrossDefinition::Opaque(o) => Some(&mut o.layout),
+            XrossDefinition::Function(_) => None,
+        }
+    }
+
+    /// Returns the methods of this definition, or `None` for standalone functions.
+    pub fn methods_mut(&mut self) -> Option<&mut Vec<XrossMethod>> {
+        match self {
+            XrossDefinition::Struct(s) => Some(&mut s.methods),
+            XrossDefinition::Enum(e) => Some(&mut e.methods),
+            XrossDefinition::Opaque(o) => Some(&mut o.methods),
+            XrossDefinition::Function(_) => None,
+        }
+    }
 }
 
 /// Metadata for a Rust struct to be bridged to JVM.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct XrossStruct {
     pub signature: String,
@@ -48,10 +144,74 @@ pub struct XrossStruct {
     pub methods: Vec<XrossMethod>,
     pub docs: Vec<String>,
     pub is_copy: bool,
+    /// `#[repr(C)]` `Copy` struct whose fields are all scalar `#[xross_field]`s. Owned values
+    /// are passed and returned as the struct itself instead of a boxed handle; see
+    /// [`c_layout`](XrossStruct::c_layout).
+    #[serde(default)]
+    pub by_value: bool,
+    /// Memory layout, filled in for definitions embedded into a compiled library.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<XrossLayout>,
+}
+
+impl XrossStruct {
+    /// C layout of a [`by_value`](XrossStruct::by_value) struct on a target with
+    /// `pointer_width`-bit pointers, derived from its fields alone. Matches the embedded
+    /// [`layout`](XrossStruct::layout) of a library built for that target. `None` for other
+    /// structs.
+    pub fn c_layout(&self, pointer_width: u32) -> Option<XrossLayout> {
+        if !self.by_value {
+            return None;
+        }
+        let mut fields = Vec::with_capacity(self.fields.len());
+        let (mut offset, mut align) = (0u64, 1u64);
+        for field in &self.fields {
+            let (size, type_name) = scalar_c_type(&field.ty, pointer_width)?;
+            offset = offset.next_multiple_of(size);
+            align = align.max(size);
+            fields.push(XrossFieldLayout {
+                name: field.name.clone(),
+                offset,
+                size,
+                align: size,
+                type_name: type_name.to_string(),
+            });
+            offset += size;
+        }
+        Some(XrossLayout {
+            size: offset.next_multiple_of(align),
+            align,
+            fields,
+            tag: None,
+            variants: Vec::new(),
+        })
+    }
+}
+
+/// Size (which is also the alignment) and Rust name of a scalar field of a by-value struct.
+fn scalar_c_type(ty: &XrossType, pointer_width: u32) -> Option<(u64, &'static str)> {
+    let pointer = u64::from(pointer_width / 8);
+    Some(match ty {
+        XrossType::Bool => (1, "bool"),
+        XrossType::I8 => (1, "i8"),
+        XrossType::U8 => (1, "u8"),
+        XrossType::I16 => (2, "i16"),
+        XrossType::U16 => (2, "u16"),
+        XrossType::I32 => (4, "i32"),
+        XrossType::U32 => (4, "u32"),
+        XrossType::F32 => (4, "f32"),
+        XrossType::I64 => (8, "i64"),
+        XrossType::U64 => (8, "u64"),
+        XrossType::F64 => (8, "f64"),
+        XrossType::ISize => (pointer, "isize"),
+        XrossType::USize => (pointer, "usize"),
+        _ => return None,
+    })
 }
 
 /// Metadata for a Rust enum to be bridged to JVM.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct XrossEnum {
     pub signature: String,
@@ -62,10 +222,14 @@ pub struct XrossEnum {
     pub methods: Vec<XrossMethod>,
     pub docs: Vec<String>,
     pub is_copy: bool,
+    /// Memory layout, filled in for definitions embedded into a compiled library.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<XrossLayout>,
 }
 
 /// Metadata for a single variant of an enum.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct XrossVariant {
     pub name: String,
@@ -75,6 +239,7 @@ pub struct XrossVariant {
 
 /// Metadata for an opaque type managed via pointers.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct XrossOpaque {
     pub signature: String,
@@ -86,10 +251,14 @@ pub struct XrossOpaque {
     pub docs: Vec<String>,
     pub is_clonable: bool,
     pub is_copy: bool,
+    /// Memory layout, filled in for definitions embedded into a compiled library.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<XrossLayout>,
 }
 
 /// Metadata for a standalone function.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct XrossFunction {
     pub signature: String,
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 /// Metadata for a field in a struct or enum variant.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct XrossField {
     /// Name of the field.
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+/// Memory layout of a bridged type on the target it was compiled for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossLayout {
+    /// `size_of` the type in bytes.
+    pub size: u64,
+    /// `align_of` the type in bytes.
+    pub align: u64,
+    /// Fields of a struct in declaration order, including those not exposed to bindings.
+    /// Empty for enums.
+    pub fields: Vec<XrossFieldLayout>,
+    /// Location of an enum's discriminant. Only known when `#[repr]` fixes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<XrossTagLayout>,
+    /// Variants of an enum in declaration order. Empty for structs.
+    pub variants: Vec<XrossVariantLayout>,
+}
+
+/// Position of a single field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossFieldLayout {
+    /// Field name, or `_0`, `_1`, ... for tuple fields.
+    pub name: String,
+    /// Offset from the start of the value in bytes.
+    pub offset: u64,
+    pub size: u64,
+    pub align: u64,
+    /// Rust type of the field as reported by `std::any::type_name`.
+    pub type_name: String,
+}
+
+/// Location of an enum's discriminant.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossTagLayout {
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Fields and discriminant of an enum variant.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossVariantLayout {
+    pub name: String,
+    /// Value stored in the tag for this variant, when the enum fixes it (`#[repr]`
+    /// or only unit variants).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discriminant: Option<i64>,
+    /// Fields with offsets from the start of the enum value.
+    pub fields: Vec<XrossFieldLayout>,
+}
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 
 /// Metadata for a method to be bridged to JVM.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct XrossMethod {
     /// Name of the method.
@@ -26,6 +27,18 @@ pub struct XrossMethod {
     pub is_raw: bool,
     /// Whether this method is asynchronous.
     pub is_async: bool,
+    /// Concurrency limit of the async export. Calls beyond it are rejected.
+    #[serde(default)]
+    pub concurrency: Option<ConcurrencyLimit>,
+    /// Default timeout of the async or blocking export in milliseconds.
+    /// When set, the export takes a trailing `timeout_ms` argument (0 uses this default).
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Whether a returned `Vec` or slice of objects is one contiguous allocation of the
+    /// elements, released as a whole through the element type's `_drop_vec`, instead of an
+    /// owned pointer per element.
+    #[serde(default)]
+    pub contiguous: bool,
     /// Arguments of the method.
     pub args: Vec<XrossField>,
     /// Return type of the method.
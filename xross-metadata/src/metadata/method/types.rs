@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// Represents the type of a method based on its receiver.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum XrossMethodType {
     /// A static function that does not take a receiver (self).
     Static,
@@ -14,8 +15,20 @@ pub enum XrossMethodType {
     OwnedInstance,
 }
 
+/// Upper bound on in-flight calls of an async export.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ConcurrencyLimit {
+    /// Maximum number of calls running at the same time.
+    pub max_concurrency: u32,
+    /// Maximum number of calls waiting for a free slot. `None` means unbounded.
+    pub queue: Option<u32>,
+}
+
 /// Defines how the native method handle should be invoked.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "kind", rename_all = "camelCase")]
 pub enum HandleMode {
     /// Standard execution.
@@ -30,4 +43,7 @@ pub enum HandleMode {
     },
     /// Can panic and should be caught to propagate as an exception to JVM.
     Panicable,
+    /// Synchronous body offloaded to the blocking worker pool.
+    /// Exported with the same `XrossTask` contract as async functions; panics are captured.
+    Blocking,
 }
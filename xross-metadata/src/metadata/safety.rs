@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// Defines the thread safety level for accessing fields or calling methods.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ThreadSafety {
     /// No synchronization. Fastest access but requires external synchronization or single-threaded use.
     Unsafe,
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// Represents the ownership model of a type when bridged between Rust and JVM.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Ownership {
     /// Owned value, either inline in a struct or passed by value.
     Owned,
@@ -17,6 +18,7 @@ pub enum Ownership {
 
 /// Represents the data types supported by the Xross bridge.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum XrossType {
     /// No value.
     Void,
@@ -60,6 +62,13 @@ pub enum XrossType {
         signature: String,
         /// Ownership model for this object.
         ownership: Ownership,
+        /// Crate that defines the object type, when known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        crate_name: Option<String>,
+        /// Symbol prefix of the object's exported functions, when known.
+        /// Used to call into classes defined by another crate.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        symbol_prefix: Option<String>,
     },
     /// An optional value.
     Option(Box<XrossType>),
@@ -72,9 +81,28 @@ pub enum XrossType {
     },
     /// An asynchronous computation.
     Async(Box<XrossType>),
+    /// A pull-based asynchronous sequence (`impl Stream<Item = T>` or `mpsc::Receiver<T>`).
+    /// Returned as an owned `XrossStream` handle.
+    Stream(Box<XrossType>),
+    /// A pull-based synchronous sequence (`impl Iterator<Item = T>`).
+    /// Returned as an owned `XrossIterator` handle.
+    Iterator(Box<XrossType>),
+    /// Cooperative cancellation flag (`XrossCancelToken`) passed as a pointer to a shared atomic bool.
+    /// Bindings should set it when the calling thread or coroutine is cancelled.
+    CancelToken,
 }
 
 impl XrossType {
+    /// Creates an object type whose owning crate is not known yet.
+    pub fn object(signature: impl Into<String>, ownership: Ownership) -> Self {
+        XrossType::Object {
+            signature: signature.into(),
+            ownership,
+            crate_name: None,
+            symbol_prefix: None,
+        }
+    }
+
     /// Returns true if the type represents an owned value.
     pub fn is_owned(&self) -> bool {
         match self {
@@ -82,7 +110,70 @@ impl XrossType {
                 matches!(ownership, Ownership::Owned | Ownership::Boxed)
             }
             XrossType::Result { .. } | XrossType::Option(_) => true,
+            XrossType::Stream(_) | XrossType::Iterator(_) => true,
             _ => false,
         }
     }
+
+    /// Calls `f` on this type and on every type nested in it.
+    pub fn visit_mut(&mut self, f: &mut impl FnMut(&mut XrossType)) {
+        f(self);
+        match self {
+            XrossType::Slice(inner)
+            | XrossType::Vec(inner)
+            | XrossType::Option(inner)
+            | XrossType::Async(inner)
+            | XrossType::Stream(inner)
+            | XrossType::Iterator(inner) => inner.visit_mut(f),
+            XrossType::Result { ok, err } => {
+                ok.visit_mut(f);
+                err.visit_mut(f);
+            }
+            _ => {}
+        }
+    }
+
+    /// Calls `f` on this type and on every type nested in it.
+    pub fn visit(&self, f: &mut impl FnMut(&XrossType)) {
+        f(self);
+        match self {
+            XrossType::Slice(inner)
+            | XrossType::Vec(inner)
+            | XrossType::Option(inner)
+            | XrossType::Async(inner)
+            | XrossType::Stream(inner)
+            | XrossType::Iterator(inner) => inner.visit(f),
+            XrossType::Result { ok, err } => {
+                ok.visit(f);
+                err.visit(f);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns true if the type is returned through a pull-based sequence handle.
+    pub fn is_sequence(&self) -> bool {
+        matches!(self, XrossType::Stream(_) | XrossType::Iterator(_))
+    }
+
+    /// Runtime function releasing an `XrossBuffer` that holds this type: a `String`, or a
+    /// `Vec`/`Slice` of scalars or objects. The allocation is freed with the layout of the
+    /// element, so each element width has its own function. `None` for other types.
+    pub fn buffer_release_symbol(&self) -> Option<&'static str> {
+        let elem = match self {
+            XrossType::String => return Some("xross_free_buffer"),
+            XrossType::Vec(inner) | XrossType::Slice(inner) => inner,
+            _ => return None,
+        };
+        Some(match &**elem {
+            XrossType::Bool | XrossType::I8 | XrossType::U8 => "xross_free_buffer",
+            XrossType::I16 | XrossType::U16 => "xross_free_buffer_16",
+            XrossType::I32 | XrossType::U32 | XrossType::F32 => "xross_free_buffer_32",
+            XrossType::I64 | XrossType::U64 | XrossType::F64 => "xross_free_buffer_64",
+            XrossType::ISize | XrossType::USize | XrossType::Pointer | XrossType::Object { .. } => {
+                "xross_free_buffer_ptr"
+            }
+            _ => return None,
+        })
+    }
 }
@@ -0,0 +1,471 @@
+use crate::{
+    Ownership, XrossDefinition, XrossMetadataBundle, XrossStruct, XrossType, check_format_version,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A problem found while loading or validating a set of definitions.
+#[derive(Debug)]
+pub enum XrossMetadataError {
+    /// A file or directory could not be read.
+    Io { path: PathBuf, source: std::io::Error },
+    /// A file or bundle is not valid definition JSON.
+    Parse { origin: String, source: serde_json::Error },
+    /// Two definitions share a signature but are of different kinds.
+    Conflict { signature: String },
+    /// An object type refers to a signature that is not in the set.
+    UnknownType { signature: String, referenced_by: String },
+    /// A bare type name matches several definitions.
+    AmbiguousType { name: String, candidates: Vec<String>, referenced_by: String },
+    /// Two methods export the same native symbol.
+    DuplicateSymbol { symbol: String, first: String, second: String },
+    /// Metadata was written in a format this version cannot read.
+    /// `found` is 0 for unversioned bundles.
+    UnsupportedVersion { origin: String, found: u64, supported: u32 },
+    /// Two bundles merged for different targets were built for the same one.
+    DuplicateTarget { triple: String },
+}
+
+impl fmt::Display for XrossMetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "cannot read {}: {}", path.display(), source),
+            Self::Parse { origin, source } => {
+                write!(f, "invalid metadata in {}: {}", origin, source)
+            }
+            Self::Conflict { signature } => {
+                write!(f, "'{}' is defined several times with different kinds", signature)
+            }
+            Self::UnknownType { signature, referenced_by } => {
+                write!(f, "'{}' refers to unknown type '{}'", referenced_by, signature)
+            }
+            Self::AmbiguousType { name, candidates, referenced_by } => write!(
+                f,
+                "'{}' refers to '{}', which matches {}",
+                referenced_by,
+                name,
+                candidates.join(", ")
+            ),
+            Self::DuplicateSymbol { symbol, first, second } => {
+                write!(f, "symbol '{}' is exported by both '{}' and '{}'", symbol, first, second)
+            }
+            Self::DuplicateTarget { triple } => {
+                write!(f, "several bundles were built for target '{}'", triple)
+            }
+            Self::UnsupportedVersion { origin, found: 0, supported } => write!(
+                f,
+                "{} has no format version; regenerate it with a release supporting format {}",
+                origin, supported
+            ),
+            Self::UnsupportedVersion { origin, found, supported } => write!(
+                f,
+                "{} uses metadata format {}, but only format {} is supported; \
+                 update xross to read it",
+                origin, found, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for XrossMetadataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// The compilation unit whose metadata directory describes the built library.
+/// Each unit of a crate (library, test harness, binary) writes its definitions to
+/// `target/xross/<crate>/<unit>` and records the unit in its `session.manifest`.
+pub const XROSS_LIBRARY_UNIT: &str = "lib";
+
+/// A set of definitions keyed by signature, as produced by one or more crates.
+///
+/// Definitions with the same signature (e.g. a type's derive and its `#[xross_methods]`)
+/// are merged on insert. Object types may still use bare names until [`resolve`] runs.
+///
+/// [`resolve`]: XrossMetadataSet::resolve
+#[derive(Debug, Clone, Default)]
+pub struct XrossMetadataSet {
+    definitions: BTreeMap<String, XrossDefinition>,
+}
+
+impl XrossMetadataSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every `*.json` definition below `dir`, e.g. `target/xross`.
+    /// Hidden files (temp files and locks) are skipped, and so are directories whose
+    /// `session.manifest` belongs to a unit other than [`XROSS_LIBRARY_UNIT`], such as a
+    /// test harness. A directory whose manifest records a newer format than this crate
+    /// supports is rejected.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self, XrossMetadataError> {
+        let mut set = Self::new();
+        let mut pending = vec![dir.as_ref().to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let entries = std::fs::read_dir(&dir)
+                .map_err(|source| XrossMetadataError::Io { path: dir.clone(), source })?;
+            let mut paths: Vec<PathBuf> =
+                entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+            paths.sort();
+            if !check_manifest(&dir)? {
+                continue;
+            }
+            for path in paths {
+                let hidden =
+                    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.'));
+                if hidden {
+                    continue;
+                }
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.extension().is_some_and(|ext| ext == "json") {
+                    let json = std::fs::read_to_string(&path)
+                        .map_err(|source| XrossMetadataError::Io { path: path.clone(), source })?;
+                    let def = serde_json::from_str(&json).map_err(|source| {
+                        XrossMetadataError::Parse { origin: path.display().to_string(), source }
+                    })?;
+                    set.insert(def)?;
+                }
+            }
+        }
+        Ok(set)
+    }
+
+    /// Loads the definitions of a bundle returned by `xross_metadata`.
+    /// Use [`XrossMetadataBundle::from_json`] to also read its crates and target.
+    pub fn from_bundle(json: &str) -> Result<Self, XrossMetadataError> {
+        let mut set = Self::new();
+        for def in XrossMetadataBundle::from_json(json)?.definitions {
+            set.insert(def)?;
+        }
+        Ok(set)
+    }
+
+    /// Adds a definition, merging its methods into an existing one with the same signature.
+    /// Methods already present (same name and symbol) are kept once, and a missing layout
+    /// is taken from the new definition.
+    pub fn insert(&mut self, mut def: XrossDefinition) -> Result<(), XrossMetadataError> {
+        let Some(existing) = self.definitions.get_mut(def.signature()) else {
+            self.definitions.insert(def.signature().to_string(), def);
+            return Ok(());
+        };
+        if std::mem::discriminant(existing) != std::mem::discriminant(&def) {
+            return Err(XrossMetadataError::Conflict { signature: def.signature().to_string() });
+        }
+        if let (Some(slot @ None), Some(layout)) =
+            (existing.layout_mut(), def.layout_mut().and_then(Option::take))
+        {
+            *slot = Some(layout);
+        }
+        if let (Some(methods), Some(new_methods)) = (existing.methods_mut(), def.methods_mut()) {
+            for m in new_methods.drain(..) {
+                if !methods.iter().any(|e| e.name == m.name && e.symbol == m.symbol) {
+                    methods.push(m);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrites object types that use a bare name to the full signature of the only
+    /// definition with that name. Reports names that match nothing or several definitions.
+    pub fn resolve(&mut self) -> Result<(), Vec<XrossMetadataError>> {
+        let mut by_name: HashMap<String, Vec<String>> = HashMap::new();
+        for def in self.definitions.values() {
+            if !matches!(def, XrossDefinition::Function(_)) {
+                by_name
+                    .entry(def.name().to_string())
+                    .or_default()
+                    .push(def.signature().to_string());
+            }
+        }
+
+        let signatures: BTreeSet<String> = self.definitions.keys().cloned().collect();
+        let mut errors = Vec::new();
+        for def in self.definitions.values_mut() {
+            let referenced_by = def.signature().to_string();
+            def.visit_types_mut(&mut |ty| {
+                let XrossType::Object { signature, .. } = ty else { return };
+                if signatures.contains(signature.as_str()) {
+                    return;
+                }
+                match by_name.get(signature.as_str()).map(Vec::as_slice) {
+                    Some([resolved]) => *signature = resolved.clone(),
+                    Some(candidates) => errors.push(XrossMetadataError::AmbiguousType {
+                        name: signature.clone(),
+                        candidates: candidates.to_vec(),
+                        referenced_by: referenced_by.clone(),
+                    }),
+                    None => errors.push(XrossMetadataError::UnknownType {
+                        signature: signature.clone(),
+                        referenced_by: referenced_by.clone(),
+                    }),
+                }
+            });
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Checks that every object type refers to a definition in the set and that
+    /// every exported symbol is unique.
+    pub fn validate(&self) -> Result<(), Vec<XrossMetadataError>> {
+        let mut errors = Vec::new();
+        let mut symbols: HashMap<&str, &str> = HashMap::new();
+        for def in self.definitions.values() {
+            def.visit_types(&mut |ty| {
+                if let XrossType::Object { signature, .. } = ty
+                    && !self.definitions.contains_key(signature)
+                {
+                    errors.push(XrossMetadataError::UnknownType {
+                        signature: signature.clone(),
+                        referenced_by: def.signature().to_string(),
+                    });
+                }
+            });
+            for method in def.methods() {
+                if let Some(first) = symbols.insert(&method.symbol, def.signature()) {
+                    errors.push(XrossMetadataError::DuplicateSymbol {
+                        symbol: method.symbol.clone(),
+                        first: first.to_string(),
+                        second: def.signature().to_string(),
+                    });
+                }
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Returns the definition with the given signature.
+    pub fn get(&self, signature: &str) -> Option<&XrossDefinition> {
+        self.definitions.get(signature)
+    }
+
+    /// Returns the struct an owned object of type `ty` is passed as by value, if its definition
+    /// is [`by_value`](crate::XrossStruct::by_value).
+    pub fn by_value_struct(&self, ty: &XrossType) -> Option<&XrossStruct> {
+        let XrossType::Object { signature, ownership: Ownership::Owned, .. } = ty else {
+            return None;
+        };
+        match self.get(signature)? {
+            XrossDefinition::Struct(s) if s.by_value => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the definitions declared in `package`, ordered by signature.
+    pub fn by_package<'a>(
+        &'a self,
+        package: &'a str,
+    ) -> impl Iterator<Item = &'a XrossDefinition> + 'a {
+        self.definitions.values().filter(move |d| d.package_name() == package)
+    }
+
+    /// Returns all definitions, ordered by signature.
+    pub fn iter(&self) -> impl Iterator<Item = &XrossDefinition> {
+        self.definitions.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Returns the definitions ordered so that each comes after the types it refers to.
+    /// Definitions on a reference cycle (e.g. two types returning each other) are emitted
+    /// in signature order once nothing else can go first.
+    pub fn topological_order(&self) -> Vec<&XrossDefinition> {
+        let mut deps: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (signature, def) in &self.definitions {
+            let mut refs = BTreeSet::new();
+            def.visit_types(&mut |ty| {
+                if let XrossType::Object { signature: target, .. } = ty
+                    && target != signature
+                    && let Some((key, _)) = self.definitions.get_key_value(target.as_str())
+                {
+                    refs.insert(key.as_str());
+                }
+            });
+            deps.insert(signature.as_str(), refs);
+        }
+
+        let mut order = Vec::with_capacity(deps.len());
+        while !deps.is_empty() {
+            let ready: Vec<&str> =
+                deps.iter().filter(|(_, refs)| refs.is_empty()).map(|(sig, _)| *sig).collect();
+            let next = if ready.is_empty() { vec![*deps.keys().next().unwrap()] } else { ready };
+            for sig in next {
+                deps.remove(sig);
+                for refs in deps.values_mut() {
+                    refs.remove(sig);
+                }
+                order.push(&self.definitions[sig]);
+            }
+        }
+        order
+    }
+
+    /// Consumes the set, returning its definitions ordered by signature.
+    pub fn into_definitions(self) -> Vec<XrossDefinition> {
+        self.definitions.into_values().collect()
+    }
+}
+
+/// Checks the `format <n>` line that the macros write to each crate's `session.manifest`.
+/// Directories written before the format was versioned have no such line and are accepted.
+/// Checks the format recorded in `dir`'s manifest and returns whether its definitions
+/// describe the library. Directories without a manifest or a `unit` line are loaded.
+fn check_manifest(dir: &Path) -> Result<bool, XrossMetadataError> {
+    let path = dir.join("session.manifest");
+    let Ok(manifest) = std::fs::read_to_string(&path) else { return Ok(true) };
+    let format = manifest.lines().find_map(|l| l.strip_prefix("format "));
+    match format.map(|v| v.trim().parse::<u64>()) {
+        Some(Ok(found)) => check_format_version(found, &path.display().to_string())?,
+        Some(Err(_)) => {
+            return Err(XrossMetadataError::UnsupportedVersion {
+                origin: path.display().to_string(),
+                found: 0,
+                supported: crate::XROSS_METADATA_FORMAT_VERSION,
+            });
+        }
+        None => {}
+    }
+    let unit = manifest.lines().find_map(|l| l.strip_prefix("unit "));
+    Ok(unit.is_none_or(|u| u.trim() == XROSS_LIBRARY_UNIT))
+}
+
+impl<'a> IntoIterator for &'a XrossMetadataSet {
+    type Item = &'a XrossDefinition;
+    type IntoIter = std::collections::btree_map::Values<'a, String, XrossDefinition>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.definitions.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::XrossEnum;
+    use crate::fixtures::*;
+
+    /// A struct holding two `demo.Point`s, referred to as `target`.
+    fn line(target: &str) -> XrossStruct {
+        let end = || XrossType::object(target, Ownership::Owned);
+        XrossStruct {
+            signature: "demo.Line".to_string(),
+            symbol_prefix: "demo_Line".to_string(),
+            name: "Line".to_string(),
+            fields: vec![field("start", end()), field("end", end())],
+            methods: vec![],
+            ..point()
+        }
+    }
+
+    fn write_unit(dir: &Path, unit: &str, def: &XrossDefinition) {
+        std::fs::create_dir_all(dir).unwrap();
+        let manifest =
+            format!("session 1\nformat {}\nunit {}\n", crate::XROSS_METADATA_FORMAT_VERSION, unit);
+        std::fs::write(dir.join("session.manifest"), manifest).unwrap();
+        let json = serde_json::to_string(def).unwrap();
+        std::fs::write(dir.join(format!("{}.json", def.signature())), json).unwrap();
+    }
+
+    #[test]
+    fn test_insert_merges_methods() {
+        let mut first = point();
+        first.methods.truncate(2);
+        let mut second = point();
+        second.methods.drain(..1);
+        second.layout = Some(layout(8));
+
+        let set = set([XrossDefinition::Struct(first), XrossDefinition::Struct(second)]);
+        let merged = set.get("demo.Point").unwrap();
+        let names: Vec<&str> = merged.methods().iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["len", "scale", "distance", "copy"]);
+        assert_eq!(merged.layout().map(|l| l.size), Some(8));
+
+        let mut conflicting = set.clone();
+        let as_enum = XrossEnum { signature: "demo.Point".to_string(), ..color() };
+        assert!(matches!(
+            conflicting.insert(XrossDefinition::Enum(as_enum)),
+            Err(XrossMetadataError::Conflict { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_and_validate() {
+        let mut loose =
+            set([XrossDefinition::Struct(point()), XrossDefinition::Struct(line("Point"))]);
+        assert!(loose.validate().is_err());
+        loose.resolve().unwrap();
+        loose.validate().unwrap();
+
+        let mut unknown = set([XrossDefinition::Struct(line("Missing"))]);
+        let errors = unknown.resolve().unwrap_err();
+        assert!(
+            matches!(&errors[0], XrossMetadataError::UnknownType { signature, .. } if signature == "Missing")
+        );
+
+        let mut twice = point();
+        twice.signature = "other.Point".to_string();
+        let mut ambiguous = set([
+            XrossDefinition::Struct(point()),
+            XrossDefinition::Struct(twice),
+            XrossDefinition::Struct(line("Point")),
+        ]);
+        let errors = ambiguous.resolve().unwrap_err();
+        assert!(matches!(&errors[0], XrossMetadataError::AmbiguousType { .. }));
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let set =
+            set([XrossDefinition::Struct(line("demo.Point")), XrossDefinition::Struct(point())]);
+        let order: Vec<&str> = set.topological_order().iter().map(|d| d.signature()).collect();
+        assert_eq!(order, ["demo.Point", "demo.Line"]);
+        assert_eq!(set.by_package("demo").count(), 2);
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let root = std::env::temp_dir().join(format!("xross-set-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        for def in [XrossDefinition::Struct(point()), XrossDefinition::Struct(line("Point"))] {
+            let json = serde_json::to_string(&def).unwrap();
+            std::fs::write(root.join("nested").join(format!("{}.json", def.signature())), json)
+                .unwrap();
+        }
+        std::fs::write(root.join(".partial.json.tmp"), "{").unwrap();
+        let mut loaded = XrossMetadataSet::load_dir(&root).unwrap();
+
+        std::fs::write(root.join("session.manifest"), "session 1\nformat 99\n").unwrap();
+        let newer = XrossMetadataSet::load_dir(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        loaded.resolve().unwrap();
+        loaded.validate().unwrap();
+        assert!(matches!(newer, Err(XrossMetadataError::UnsupportedVersion { found: 99, .. })));
+    }
+
+    #[test]
+    fn test_load_dir_units() {
+        let root = std::env::temp_dir().join(format!("xross-units-{}", std::process::id()));
+        write_unit(&root.join("demo/lib"), "lib", &XrossDefinition::Struct(point()));
+        write_unit(&root.join("demo/test-demo"), "test-demo", &XrossDefinition::Enum(color()));
+
+        let set = XrossMetadataSet::load_dir(&root).unwrap();
+        std::fs::remove_dir_all(&root).ok();
+        assert!(set.get("demo.Point").is_some());
+        assert!(set.get("demo.Color").is_none());
+    }
+}
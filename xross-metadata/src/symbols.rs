@@ -0,0 +1,143 @@
+use crate::{XrossDefinition, XrossField, XrossMetadataSet, XrossType};
+
+/// Functions releasing the buffers Rust hands out, one per element width
+/// (see `XrossType::buffer_release_symbol`).
+pub const XROSS_BUFFER_RELEASE_SYMBOLS: &[&str] = &[
+    "xross_free_buffer",
+    "xross_free_buffer_16",
+    "xross_free_buffer_32",
+    "xross_free_buffer_64",
+    "xross_free_buffer_ptr",
+];
+
+/// Symbols exported by every library that links `xross-core`, independent of its definitions.
+pub const XROSS_RUNTIME_SYMBOLS: &[&str] = &[
+    "xross_metadata",
+    "xross_abi_version",
+    "xross_metadata_hash",
+    "xross_free_buffer",
+    "xross_free_buffer_16",
+    "xross_free_buffer_32",
+    "xross_free_buffer_64",
+    "xross_free_buffer_ptr",
+    "xross_free_scalar",
+];
+
+/// What a generated native symbol is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XrossSymbolKind {
+    Method,
+    PropertyGetter,
+    PropertySetter,
+    VariantConstructor,
+    /// `_drop_vec`, `_layout`, `_get_tag` and the other functions every class gets.
+    Support,
+}
+
+/// A native symbol generated for a definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XrossSymbol {
+    pub name: String,
+    pub kind: XrossSymbolKind,
+    /// The method, field or variant it belongs to, if any.
+    pub member: Option<String>,
+}
+
+impl XrossSymbol {
+    fn new(name: String, kind: XrossSymbolKind, member: Option<&str>) -> Self {
+        Self { name, kind, member: member.map(str::to_string) }
+    }
+}
+
+/// Suffix of the accessors of a field, matching the `_get`/`_set` variant the macros export.
+fn property_suffix(ty: &XrossType) -> &'static str {
+    match ty {
+        XrossType::String => "_str",
+        XrossType::Option(_) => "_opt",
+        XrossType::Result { .. } => "_res",
+        _ => "",
+    }
+}
+
+fn push_properties(symbols: &mut Vec<XrossSymbol>, prefix: &str, fields: &[XrossField]) {
+    for f in fields {
+        let base = format!("{}_property_{}{}", prefix, f.name, property_suffix(&f.ty));
+        let member = Some(f.name.as_str());
+        symbols.push(XrossSymbol::new(
+            format!("{}_get", base),
+            XrossSymbolKind::PropertyGetter,
+            member,
+        ));
+        symbols.push(XrossSymbol::new(
+            format!("{}_set", base),
+            XrossSymbolKind::PropertySetter,
+            member,
+        ));
+    }
+}
+
+impl XrossDefinition {
+    /// Returns the prefix of the symbols generated for a class, or the symbol of a function.
+    pub fn symbol_prefix(&self) -> &str {
+        match self {
+            XrossDefinition::Struct(s) => &s.symbol_prefix,
+            XrossDefinition::Enum(e) => &e.symbol_prefix,
+            XrossDefinition::Opaque(o) => &o.symbol_prefix,
+            XrossDefinition::Function(f) => &f.symbol,
+        }
+    }
+
+    /// Lists every native symbol the macros export for this definition: its methods
+    /// (including `_clone` and `_drop`), field accessors, variant constructors, and the
+    /// `_drop_vec`, layout and enum helpers of classes.
+    pub fn exported_symbols(&self) -> Vec<XrossSymbol> {
+        let mut symbols: Vec<XrossSymbol> = self
+            .methods()
+            .iter()
+            .map(|m| XrossSymbol::new(m.symbol.clone(), XrossSymbolKind::Method, Some(&m.name)))
+            .collect();
+        if matches!(self, XrossDefinition::Function(_)) {
+            return symbols;
+        }
+
+        let prefix = self.symbol_prefix();
+        for suffix in ["drop_vec", "layout", "layout_descriptor", "layout_fingerprint"] {
+            symbols.push(XrossSymbol::new(
+                format!("{}_{}", prefix, suffix),
+                XrossSymbolKind::Support,
+                None,
+            ));
+        }
+        match self {
+            XrossDefinition::Struct(s) => push_properties(&mut symbols, prefix, &s.fields),
+            XrossDefinition::Opaque(o) => push_properties(&mut symbols, prefix, &o.fields),
+            XrossDefinition::Enum(e) => {
+                for suffix in ["get_tag", "get_variant_name"] {
+                    symbols.push(XrossSymbol::new(
+                        format!("{}_{}", prefix, suffix),
+                        XrossSymbolKind::Support,
+                        None,
+                    ));
+                }
+                for v in &e.variants {
+                    symbols.push(XrossSymbol::new(
+                        format!("{}_new_{}", prefix, v.name),
+                        XrossSymbolKind::VariantConstructor,
+                        Some(&v.name),
+                    ));
+                }
+            }
+            XrossDefinition::Function(_) => {}
+        }
+        symbols
+    }
+}
+
+impl XrossMetadataSet {
+    /// Every symbol exported for the definitions of this set, paired with their signature.
+    pub fn exported_symbols(&self) -> Vec<(&str, XrossSymbol)> {
+        self.iter()
+            .flat_map(|def| def.exported_symbols().into_iter().map(|s| (def.signature(), s)))
+            .collect()
+    }
+}
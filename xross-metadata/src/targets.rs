@@ -0,0 +1,174 @@
+use crate::{XrossLayout, XrossMetadataBundle, XrossMetadataError, XrossMetadataSet, XrossTarget};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How a definition differs between the targets of a [`XrossMultiTargetMetadata`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum XrossTargetDifferenceKind {
+    /// Sizes, alignments, offsets or the enum tag differ, e.g. because of `usize` fields.
+    LayoutDiffers,
+    /// The definition is missing on some targets, e.g. because of `#[cfg(target_os)]`.
+    MissingOnTarget,
+}
+
+/// A definition that bindings cannot treat the same way on every target.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct XrossTargetDifference {
+    pub kind: XrossTargetDifferenceKind,
+    pub signature: String,
+    /// Target triples involved: those with a differing layout, or those missing the definition.
+    pub targets: Vec<String>,
+}
+
+/// Metadata of one library compiled for several targets.
+///
+/// Definitions are merged like in a [`XrossMetadataSet`]; layouts are kept per target
+/// triple, since they legitimately differ between e.g. 32- and 64-bit targets.
+#[derive(Debug, Clone, Default)]
+pub struct XrossMultiTargetMetadata {
+    targets: Vec<XrossTarget>,
+    definitions: XrossMetadataSet,
+    /// Layouts by signature, then by target triple.
+    layouts: BTreeMap<String, BTreeMap<String, XrossLayout>>,
+    /// Triples that declare each signature.
+    declared: BTreeMap<String, Vec<String>>,
+}
+
+impl XrossMultiTargetMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merges bundles of the same library built for different targets.
+    pub fn merge(
+        bundles: impl IntoIterator<Item = XrossMetadataBundle>,
+    ) -> Result<Self, XrossMetadataError> {
+        let mut merged = Self::new();
+        for bundle in bundles {
+            merged.add(bundle)?;
+        }
+        Ok(merged)
+    }
+
+    /// Adds the bundle of one more target.
+    pub fn add(&mut self, bundle: XrossMetadataBundle) -> Result<(), XrossMetadataError> {
+        let triple = bundle.target.triple.clone();
+        if self.targets.iter().any(|t| t.triple == triple) {
+            return Err(XrossMetadataError::DuplicateTarget { triple });
+        }
+        for mut def in bundle.definitions {
+            let signature = def.signature().to_string();
+            if let Some(layout) = def.layout_mut().and_then(Option::take) {
+                self.layouts.entry(signature.clone()).or_default().insert(triple.clone(), layout);
+            }
+            self.declared.entry(signature).or_default().push(triple.clone());
+            self.definitions.insert(def)?;
+        }
+        self.targets.push(bundle.target);
+        Ok(())
+    }
+
+    /// Targets in the order they were added.
+    pub fn targets(&self) -> &[XrossTarget] {
+        &self.targets
+    }
+
+    /// Definitions of all targets, without layouts.
+    pub fn definitions(&self) -> &XrossMetadataSet {
+        &self.definitions
+    }
+
+    /// Returns the layout of `signature` on the target `triple`.
+    pub fn layout(&self, signature: &str, triple: &str) -> Option<&XrossLayout> {
+        self.layouts.get(signature)?.get(triple)
+    }
+
+    /// Lists definitions whose layout differs between targets or that some targets lack.
+    pub fn differences(&self) -> Vec<XrossTargetDifference> {
+        let mut differences = Vec::new();
+        for def in &self.definitions {
+            let signature = def.signature();
+            let declared = self.declared.get(signature).map(Vec::as_slice).unwrap_or_default();
+            let missing: Vec<String> = self
+                .targets
+                .iter()
+                .filter(|t| !declared.contains(&t.triple))
+                .map(|t| t.triple.clone())
+                .collect();
+            if !missing.is_empty() {
+                differences.push(XrossTargetDifference {
+                    kind: XrossTargetDifferenceKind::MissingOnTarget,
+                    signature: signature.to_string(),
+                    targets: missing,
+                });
+            }
+
+            let Some(layouts) = self.layouts.get(signature) else { continue };
+            let mut values = layouts.values();
+            let first = values.next();
+            if values.any(|l| Some(l) != first) {
+                differences.push(XrossTargetDifference {
+                    kind: XrossTargetDifferenceKind::LayoutDiffers,
+                    signature: signature.to_string(),
+                    targets: layouts.keys().cloned().collect(),
+                });
+            }
+        }
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::{XrossDefinition, XrossEndian};
+
+    fn bundle(triple: &str, pointer_width: u32, point_size: u64) -> XrossMetadataBundle {
+        let target =
+            XrossTarget { triple: triple.to_string(), pointer_width, endian: XrossEndian::Little };
+        let point = crate::XrossStruct { layout: Some(layout(point_size)), ..point() };
+        let mut color = color();
+        color.layout =
+            Some(XrossLayout { size: 4, align: 4, fields: vec![], tag: None, variants: vec![] });
+        XrossMetadataBundle::new(
+            vec![],
+            target,
+            vec![XrossDefinition::Struct(point), XrossDefinition::Enum(color)],
+        )
+    }
+
+    #[test]
+    fn test_merge() {
+        let host = bundle("x86_64-unknown-linux-gnu", 64, 8);
+        let mut other = bundle("i686-unknown-linux-gnu", 32, 16);
+        other.definitions.retain(|d| d.signature() != "demo.Color");
+
+        let merged = XrossMultiTargetMetadata::merge([host.clone(), other.clone()]).unwrap();
+        assert_eq!(merged.targets().len(), 2);
+        assert_eq!(merged.definitions().len(), 2);
+        assert_eq!(merged.layout("demo.Point", "i686-unknown-linux-gnu").unwrap().size, 16);
+        assert_eq!(merged.layout("demo.Point", "x86_64-unknown-linux-gnu").unwrap().size, 8);
+
+        let differences = merged.differences();
+        let kind_of = |sig: &str| differences.iter().find(|d| d.signature == sig).map(|d| d.kind);
+        assert_eq!(kind_of("demo.Point"), Some(XrossTargetDifferenceKind::LayoutDiffers));
+        assert_eq!(kind_of("demo.Color"), Some(XrossTargetDifferenceKind::MissingOnTarget));
+
+        let same = XrossMultiTargetMetadata::merge([
+            host.clone(),
+            bundle("aarch64-unknown-linux-gnu", 64, 8),
+        ])
+        .unwrap();
+        assert!(same.differences().is_empty());
+
+        assert!(matches!(
+            XrossMultiTargetMetadata::merge([host, other.clone(), other]),
+            Err(XrossMetadataError::DuplicateTarget { triple }) if triple == "i686-unknown-linux-gnu"
+        ));
+    }
+}
@@ -0,0 +1,128 @@
+use xross_metadata::{
+    HandleMode, Ownership, ThreadSafety, XrossDefinition, XrossEndian, XrossEnum, XrossField,
+    XrossFunction, XrossMetadataBundle, XrossMethod, XrossMethodType, XrossStruct, XrossTarget,
+    XrossType, XrossVariant, xross_metadata_schema,
+};
+
+const SCHEMA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schema.json");
+
+fn field(name: &str, ty: XrossType) -> XrossField {
+    XrossField { name: name.to_string(), ty, docs: vec![], safety: ThreadSafety::Lock }
+}
+
+fn method(name: &str, args: Vec<XrossField>, ret: XrossType) -> XrossMethod {
+    XrossMethod {
+        name: name.to_string(),
+        symbol: format!("demo_{}", name),
+        method_type: XrossMethodType::Static,
+        handle_mode: HandleMode::Normal,
+        is_constructor: false,
+        is_default: false,
+        is_raw: false,
+        is_async: false,
+        concurrency: None,
+        timeout_ms: None,
+        contiguous: false,
+        args,
+        ret,
+        docs: vec![],
+        safety: ThreadSafety::Lock,
+    }
+}
+
+/// A bundle using every kind of definition and the nested types.
+fn bundle() -> XrossMetadataBundle {
+    let point = XrossStruct {
+        signature: "demo.Point".to_string(),
+        symbol_prefix: "demo_Point".to_string(),
+        package_name: "demo".to_string(),
+        name: "Point".to_string(),
+        fields: vec![
+            field("x", XrossType::I32),
+            field("label", XrossType::Option(Box::new(XrossType::String))),
+            field("weights", XrossType::Vec(Box::new(XrossType::F64))),
+        ],
+        methods: vec![method(
+            "parse",
+            vec![field("text", XrossType::String)],
+            XrossType::Result {
+                ok: Box::new(XrossType::object("demo.Point", Ownership::Owned)),
+                err: Box::new(XrossType::String),
+            },
+        )],
+        docs: vec!["A point.".to_string()],
+        is_copy: false,
+        by_value: false,
+        layout: None,
+    };
+    let shape = XrossEnum {
+        signature: "demo.Shape".to_string(),
+        symbol_prefix: "demo_Shape".to_string(),
+        package_name: "demo".to_string(),
+        name: "Shape".to_string(),
+        variants: vec![XrossVariant {
+            name: "Circle".to_string(),
+            fields: vec![field("radius", XrossType::F32)],
+            docs: vec![],
+        }],
+        methods: vec![],
+        docs: vec![],
+        is_copy: false,
+        layout: None,
+    };
+    let mut ticks = method(
+        "ticks",
+        vec![field("cancel", XrossType::CancelToken)],
+        XrossType::Stream(Box::new(XrossType::U64)),
+    );
+    ticks.is_async = true;
+    ticks.handle_mode = HandleMode::Blocking;
+    let ticks = XrossFunction {
+        signature: "demo.ticks".to_string(),
+        symbol: ticks.symbol.clone(),
+        package_name: "demo".to_string(),
+        name: "ticks".to_string(),
+        method: ticks,
+        docs: vec![],
+    };
+    let target = XrossTarget {
+        triple: "x86_64-unknown-linux-gnu".to_string(),
+        pointer_width: 64,
+        endian: XrossEndian::Little,
+    };
+    XrossMetadataBundle::new(
+        vec![],
+        target,
+        vec![
+            XrossDefinition::Struct(point),
+            XrossDefinition::Enum(shape),
+            XrossDefinition::Function(ticks),
+        ],
+    )
+}
+
+#[test]
+fn test_schema_is_current() {
+    let generated = serde_json::to_string_pretty(&xross_metadata_schema()).unwrap() + "\n";
+    let committed = std::fs::read_to_string(SCHEMA_PATH).unwrap();
+    assert!(
+        committed == generated,
+        "schema.json is outdated; regenerate it with \
+         `cargo run -p xross-cli -- schema > xross-metadata/schema.json`"
+    );
+}
+
+#[test]
+fn test_bundle_matches_schema() {
+    let schema: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(SCHEMA_PATH).unwrap()).unwrap();
+    let validator = jsonschema::validator_for(&schema).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&bundle().to_json()).unwrap();
+    if let Err(error) = validator.validate(&value) {
+        panic!("bundle does not match the schema: {}", error);
+    }
+
+    let mut invalid = value.clone();
+    invalid["pointerWidth"] = serde_json::json!("64");
+    assert!(validator.validate(&invalid).is_err());
+}
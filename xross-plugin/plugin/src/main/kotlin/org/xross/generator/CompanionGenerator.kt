@@ -1,6 +1,7 @@
 package org.xross.generator
 
 import com.squareup.kotlinpoet.*
+import org.xross.generator.util.AbiFingerprint
 import org.xross.generator.util.FFMConstants
 import org.xross.generator.util.GeneratorUtils
 import org.xross.helper.StringHelper.toCamelCase
@@ -22,8 +23,19 @@ object CompanionGenerator {
         val init = CodeBlock.builder()
             .addStatement("val lookup = %T.loaderLookup()", SymbolLookup::class.asTypeName())
             .addStatement("%T.initializeHeap(lookup, linker)", ClassName(if (basePackage.isEmpty()) "xross.runtime" else "$basePackage.xross.runtime", "XrossRuntime"))
+            .addStatement("%T.verifyAbi(lookup, linker)", ClassName(if (basePackage.isEmpty()) "xross.runtime" else "$basePackage.xross.runtime", "XrossRuntime"))
 
-        HandleResolver.resolveAllHandles(init, meta)
+        if (meta !is XrossDefinition.Function) {
+            init.addStatement(
+                "%T.verifyLayout(lookup, linker, %S, %S.toULong(16).toLong(), %S)",
+                ClassName(if (basePackage.isEmpty()) "xross.runtime" else "$basePackage.xross.runtime", "XrossRuntime"),
+                "${meta.symbolPrefix}_layout_fingerprint",
+                AbiFingerprint.layoutFingerprint(meta).toULong().toString(16),
+                meta.signature,
+            )
+        }
+
+        HandleResolver.resolveAllHandles(init, meta, basePackage)
 
         if (meta !is XrossDefinition.Function) {
             init.add("\n// --- Native Layout Resolution ---\n")
@@ -48,7 +60,8 @@ object CompanionGenerator {
             when (meta) {
                 is XrossDefinition.Struct -> {
                     LayoutGenerator.buildStructLayoutInit(init, meta)
-                    LayoutGenerator.buildStructAbiLayoutInit(init, meta)
+                    // A by-value struct's ABI_LAYOUT is fixed at generation time
+                    if (!meta.byValue) LayoutGenerator.buildStructAbiLayoutInit(init, meta)
                 }
                 is XrossDefinition.Enum -> {
                     LayoutGenerator.buildEnumLayoutInit(init, meta)
@@ -92,13 +105,13 @@ object CompanionGenerator {
         )
 
         if (meta !is XrossDefinition.Function) {
-            handles.addAll(listOf("dropHandle", "layoutHandle", "xrossFreeBufferHandle"))
+            handles.addAll(listOf("dropHandle", "dropVecHandle", "layoutHandle"))
             if (meta.methods.any { it.name == "clone" }) {
                 handles.add("cloneHandle")
             }
-        } else {
-            handles.add("xrossFreeBufferHandle")
         }
+        handles.addAll(HandleResolver.BUFFER_RELEASE_HANDLES.values)
+        handles.add(HandleResolver.FREE_SCALAR_HANDLE)
 
         when (meta) {
             is XrossDefinition.Struct -> {
@@ -149,7 +162,13 @@ object CompanionGenerator {
 
         if (meta !is XrossDefinition.Function) {
             builder.addProperty(PropertySpec.builder("LAYOUT", LAYOUT_TYPE, KModifier.INTERNAL).mutable().initializer("%T.structLayout()", MEMORY_LAYOUT).build())
-            builder.addProperty(PropertySpec.builder("ABI_LAYOUT", LAYOUT_TYPE, KModifier.INTERNAL).mutable().initializer("%T.structLayout()", MEMORY_LAYOUT).build())
+            // Declared before the initializer block so that handles passing this struct by value can use it
+            val abiLayout = if (meta is XrossDefinition.Struct && meta.byValue) {
+                LayoutGenerator.byValueAbiLayout(meta)
+            } else {
+                CodeBlock.of("%T.structLayout()", MEMORY_LAYOUT)
+            }
+            builder.addProperty(PropertySpec.builder("ABI_LAYOUT", LAYOUT_TYPE, KModifier.INTERNAL).mutable().initializer(abiLayout).build())
             builder.addProperty(PropertySpec.builder("STRUCT_SIZE", Long::class.asTypeName(), KModifier.INTERNAL).mutable().initializer("0L").build())
         }
     }
@@ -65,7 +65,7 @@ object EnumVariantGenerator {
                         // entries マップ用のインスタンスは永続フラグを立てる
                         // addFactoryBody 内ですでに flag 変数が作成されているため、ここでは何も定義しない
                         fields.forEach { field ->
-                            if (field.ty is XrossType.Object && field.ty.isOwned) {
+                            if (field.ty is XrossType.Object && field.ty.isOwned && !field.ty.byValue) {
                                 val argName = "argOf" + field.name.toCamelCase()
                                 addStatement("$argName.relinquish()")
                             }
@@ -14,13 +14,34 @@ import org.xross.helper.StringHelper.toCamelCase
 import org.xross.structures.*
 
 object HandleResolver {
-    fun resolveAllHandles(init: CodeBlock.Builder, meta: XrossDefinition) {
+    /** Buffer release functions of the runtime and the companion properties holding them. */
+    val BUFFER_RELEASE_HANDLES = linkedMapOf(
+        "xross_free_buffer" to "xrossFreeBufferHandle",
+        "xross_free_buffer_16" to "xrossFreeBuffer16Handle",
+        "xross_free_buffer_32" to "xrossFreeBuffer32Handle",
+        "xross_free_buffer_64" to "xrossFreeBuffer64Handle",
+        "xross_free_buffer_ptr" to "xrossFreeBufferPtrHandle",
+    )
+
+    /** Companion property holding `xross_free_scalar`, which releases the boxed scalar of a `Some`. */
+    const val FREE_SCALAR_HANDLE = "xrossFreeScalarHandle"
+
+    fun resolveAllHandles(init: CodeBlock.Builder, meta: XrossDefinition, basePackage: String) {
         // Basic handles
+        BUFFER_RELEASE_HANDLES.forEach { (symbol, handle) ->
+            init.addStatement(
+                "this.$handle = linker.downcallHandle(lookup.find(%S).get(), %T.ofVoid(%L))",
+                symbol,
+                FUNCTION_DESCRIPTOR,
+                FFMConstants.XROSS_STRING_LAYOUT_CODE,
+            )
+        }
         init.addStatement(
-            "this.xrossFreeBufferHandle = linker.downcallHandle(lookup.find(%S).get(), %T.ofVoid(%L))",
-            "xross_free_buffer",
+            "this.$FREE_SCALAR_HANDLE = linker.downcallHandle(lookup.find(%S).get(), %T.ofVoid(%M, %M))",
+            "xross_free_scalar",
             FUNCTION_DESCRIPTOR,
-            FFMConstants.XROSS_STRING_LAYOUT_CODE,
+            ADDRESS,
+            JAVA_LONG,
         )
 
         if (meta !is XrossDefinition.Function) {
@@ -44,19 +65,26 @@ object HandleResolver {
                 }
                 init.addStatement("this.${suffix}Handle = linker.downcallHandle(lookup.find(%S).get(), %L%L)", symbol, desc, options)
             }
+            // Releases a `contiguous` return; takes the XrossBuffer by value.
+            init.addStatement(
+                "this.dropVecHandle = linker.downcallHandle(lookup.find(%S).get(), %T.ofVoid(%L))",
+                "${meta.symbolPrefix}_drop_vec",
+                FUNCTION_DESCRIPTOR,
+                FFMConstants.XROSS_STRING_LAYOUT_CODE,
+            )
         }
 
         when (meta) {
-            is XrossDefinition.Struct -> resolveStructHandles(init, meta)
-            is XrossDefinition.Enum -> resolveEnumHandles(init, meta)
+            is XrossDefinition.Struct -> resolveStructHandles(init, meta, basePackage)
+            is XrossDefinition.Enum -> resolveEnumHandles(init, meta, basePackage)
             is XrossDefinition.Opaque -> resolveOpaqueHandles(init, meta)
             is XrossDefinition.Function -> {}
         }
 
-        resolveMethodHandles(init, meta)
+        resolveMethodHandles(init, meta, basePackage)
     }
 
-    private fun getArgLayouts(handleMode: HandleMode, fields: List<XrossField>): List<CodeBlock> {
+    private fun getArgLayouts(handleMode: HandleMode, fields: List<XrossField>, basePackage: String): List<CodeBlock> {
         val layouts = mutableListOf<CodeBlock>()
         fields.forEach { field ->
             val layoutCode = if (handleMode is HandleMode.Critical) field.ty.layoutCodeCritical else field.ty.layoutCode
@@ -74,7 +102,9 @@ object HandleResolver {
                 }
 
                 is XrossType.Object -> {
-                    if (field.ty.ownership == XrossType.Ownership.Value) {
+                    if (field.ty.byValue) {
+                        layouts.add(byValueLayout(field.ty, basePackage))
+                    } else if (field.ty.ownership == XrossType.Ownership.Value) {
                         // Pass by value: use the class's ABI_LAYOUT
                         val className = field.ty.signature.substringAfterLast('.')
                         layouts.add(CodeBlock.of("%N.ABI_LAYOUT", className))
@@ -92,9 +122,12 @@ object HandleResolver {
         return layouts
     }
 
-    private fun resolveStructHandles(init: CodeBlock.Builder, meta: XrossDefinition.Struct) {
+    /** The C layout a by-value struct is passed or returned as. */
+    private fun byValueLayout(ty: XrossType.Object, basePackage: String): CodeBlock = CodeBlock.of("%T.ABI_LAYOUT", GeneratorUtils.getClassName(ty.signature, basePackage))
+
+    private fun resolveStructHandles(init: CodeBlock.Builder, meta: XrossDefinition.Struct, basePackage: String) {
         meta.methods.filter { it.isConstructor }.forEach { method ->
-            val argLayouts = getArgLayouts(method.handleMode, method.args)
+            val argLayouts = getArgLayouts(method.handleMode, method.args, basePackage)
             val isPanicable = method.handleMode is HandleMode.Panicable
 
             val desc = if (isPanicable) {
@@ -121,7 +154,7 @@ object HandleResolver {
         resolvePropertyHandles(init, meta.symbolPrefix, meta.fields)
     }
 
-    private fun resolveEnumHandles(init: CodeBlock.Builder, meta: XrossDefinition.Enum) {
+    private fun resolveEnumHandles(init: CodeBlock.Builder, meta: XrossDefinition.Enum, basePackage: String) {
         init.addStatement(
             "this.getTagHandle = linker.downcallHandle(lookup.find(%S).get(), %T.of(%M, %M))",
             "${meta.symbolPrefix}_get_tag",
@@ -138,7 +171,7 @@ object HandleResolver {
         )
 
         meta.variants.forEach { v ->
-            val argLayouts = getArgLayouts(HandleMode.Normal, v.fields)
+            val argLayouts = getArgLayouts(HandleMode.Normal, v.fields, basePackage)
             val desc = if (argLayouts.isEmpty()) {
                 CodeBlock.of("%T.of(%M)", FUNCTION_DESCRIPTOR, ADDRESS)
             } else {
@@ -264,13 +297,13 @@ object HandleResolver {
         }
     }
 
-    private fun resolveMethodHandles(init: CodeBlock.Builder, meta: XrossDefinition) {
+    private fun resolveMethodHandles(init: CodeBlock.Builder, meta: XrossDefinition, basePackage: String) {
         meta.methods.filter { !it.isConstructor && it.name != "drop" && it.name != "layout" }.forEach { method ->
             val args = mutableListOf<CodeBlock>()
             if (method.methodType != XrossMethodType.Static) args.add(CodeBlock.of("%M", ADDRESS))
-            args.addAll(getArgLayouts(method.handleMode, method.args))
+            args.addAll(getArgLayouts(method.handleMode, method.args, basePackage))
 
-            val isComplexRet = method.ret is XrossType.RustString || method.isAsync || method.ret is XrossType.Vec || method.ret is XrossType.Slice
+            val isComplexRet = method.ret is XrossType.RustString || method.isAsync || method.ret is XrossType.Vec || method.ret is XrossType.Slice || method.ret.isSequence
 
             val isPanicable = method.handleMode is HandleMode.Panicable
             val desc = if (method.ret is XrossType.Void && !method.isAsync && !isPanicable) {
@@ -281,7 +314,9 @@ object HandleResolver {
                 CodeBlock.of("%T.ofVoid(%L)", FUNCTION_DESCRIPTOR, allArgs.joinToCode(", "))
             } else {
                 val argsPart = if (args.isEmpty()) CodeBlock.of("") else CodeBlock.of(", %L", args.joinToCode(", "))
-                val retLayout = if (method.ret is XrossType.Object && method.ret.ownership == XrossType.Ownership.Value) {
+                val retLayout = if (method.ret is XrossType.Object && method.ret.byValue) {
+                    byValueLayout(method.ret, basePackage)
+                } else if (method.ret is XrossType.Object && method.ret.ownership == XrossType.Ownership.Value) {
                     val className = method.ret.signature.substringAfterLast('.')
                     CodeBlock.of("%N.ABI_LAYOUT", className)
                 } else {
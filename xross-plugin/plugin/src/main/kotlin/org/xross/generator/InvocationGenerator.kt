@@ -8,7 +8,6 @@ import org.xross.generator.util.*
 import org.xross.helper.StringHelper.escapeKotlinKeyword
 import org.xross.helper.StringHelper.toCamelCase
 import org.xross.structures.*
-import java.lang.foreign.ValueLayout
 
 object InvocationGenerator {
     private val ADDRESS = FFMConstants.ADDRESS
@@ -127,7 +126,7 @@ object InvocationGenerator {
                 if (isMutableArg) {
                     body.addStatement("%L.clearCache()", name)
                 }
-                if (arg.ty.isOwned) {
+                if (arg.ty.isOwned && !arg.ty.byValue) {
                     body.addStatement("%L.relinquish()", name)
                 }
             }
@@ -169,9 +168,10 @@ object InvocationGenerator {
             body.addStatement("val dropFnPtr = task.get(%M, 16L)", ADDRESS)
 
             body.addStatement(
-                "val pollFn = linker.downcallHandle(pollFnPtr, %T.of(%L, %M))",
+                "val pollFn = linker.downcallHandle(pollFnPtr, %T.of(%M, %M, %M))",
                 FFMConstants.FUNCTION_DESCRIPTOR,
-                FFMConstants.XROSS_RESULT_LAYOUT_CODE,
+                FFMConstants.JAVA_INT,
+                ADDRESS,
                 ADDRESS,
             )
             body.addStatement(
@@ -180,7 +180,16 @@ object InvocationGenerator {
                 ADDRESS,
             )
 
-            body.beginControlFlow("%T.awaitFuture(taskPtr, pollFn, dropFn)", ClassName(runtimePkg, "XrossAsync"))
+            val cancelFlags = GeneratorUtils.cancelTokenNames(method).takeIf { method.handleMode is HandleMode.Blocking }.orEmpty()
+            if (cancelFlags.isEmpty()) {
+                body.beginControlFlow("%T.awaitFuture(taskPtr, pollFn, dropFn)", ClassName(runtimePkg, "XrossAsync"))
+            } else {
+                body.beginControlFlow(
+                    "%T.awaitFuture(taskPtr, pollFn, dropFn, listOf(%L))",
+                    ClassName(runtimePkg, "XrossAsync"),
+                    cancelFlags.joinToString(", "),
+                )
+            }
             body.addResultVariantResolution(
                 method.ret,
                 "it",
@@ -216,6 +225,10 @@ object InvocationGenerator {
             // Success case: resolve the actual return type
             if (method.ret is XrossType.Void) {
                 body.addStatement("Unit")
+            } else if (method.contiguous) {
+                body.add("val okVal = ")
+                body.addContiguousResolution(method.ret, "ptr.reinterpret(24L)", selfType, basePackage)
+                body.addStatement("okVal")
             } else {
                 body.add("val okVal = ")
                 body.addResultVariantResolution(
@@ -250,12 +263,38 @@ object InvocationGenerator {
                 val callExpr =
                     if (call.toString() == "outPanic" || call.toString() == "outBuf") call else CodeBlock.of("(%L as %T)", call, MEMORY_SEGMENT)
                 body.addStatement("val resRaw = %L", callExpr)
-                body.beginControlFlow("if (resRaw == %T.NULL)", MEMORY_SEGMENT)
-                    .addStatement("throw %T(%S)", NullPointerException::class.asTypeName(), "Unexpected NULL return")
-                body.nextControlFlow("else")
                 val (size, drop, from) = getExprs(returnType)
-                body.addResourceConstruction(retTy, "resRaw", size, from, drop)
-                body.endControlFlow().endControlFlow()
+                if (retTy.byValue) {
+                    // The struct lives in JVM memory: nothing to drop on the native side
+                    body.addStatement("%L(resRaw, parent = null, isPersistent = true)", from)
+                } else {
+                    body.beginControlFlow("if (resRaw == %T.NULL)", MEMORY_SEGMENT)
+                        .addStatement("throw %T(%S)", NullPointerException::class.asTypeName(), "Unexpected NULL return")
+                    body.nextControlFlow("else")
+                    body.addResourceConstruction(retTy, "resRaw", size, from, drop)
+                    body.endControlFlow()
+                }
+                body.endControlFlow()
+            }
+
+            is XrossType.Iterator, is XrossType.Stream -> {
+                // The handle is copied out of outBuf; each item is a XrossResult holding the value
+                val inner = if (retTy is XrossType.Iterator) retTy.inner else (retTy as XrossType.Stream).inner
+                val wrapper = if (retTy is XrossType.Iterator) {
+                    CodeBlock.of("%T", ClassName(runtimePkg, "XrossSequence"))
+                } else {
+                    CodeBlock.of("%T.streamFlow", ClassName(runtimePkg, "XrossAsync"))
+                }
+                body.beginControlFlow("%L(%L)", wrapper, call)
+                body.addResultVariantResolution(
+                    inner,
+                    "it",
+                    GeneratorUtils.resolveReturnType(inner, basePackage),
+                    selfType,
+                    basePackage,
+                    "dropHandle",
+                )
+                body.endControlFlow()
             }
 
             is XrossType.Optional -> {
@@ -282,7 +321,11 @@ object InvocationGenerator {
                 } else {
                     CodeBlock.of("(%L as %T)", call, MEMORY_SEGMENT)
                 }
-                body.addResultVariantResolution(retTy, callExpr, returnType, selfType, basePackage)
+                if (method.contiguous) {
+                    body.addContiguousResolution(retTy, callExpr, selfType, basePackage)
+                } else {
+                    body.addResultVariantResolution(retTy, callExpr, returnType, selfType, basePackage)
+                }
             }
 
             else -> {
@@ -291,7 +334,7 @@ object InvocationGenerator {
                     is XrossType.U16 -> Short::class.asTypeName()
                     is XrossType.U32 -> Int::class.asTypeName()
                     is XrossType.U64 -> Long::class.asTypeName()
-                    is XrossType.USize -> if (ValueLayout.ADDRESS.byteSize() == 8L) Long::class.asTypeName() else Int::class.asTypeName()
+                    is XrossType.USize -> if (XrossGenerator.property.pointerWidth == 64) Long::class.asTypeName() else Int::class.asTypeName()
                     else -> returnType
                 }
                 if (jvmType != returnType) {
@@ -2,6 +2,7 @@ package org.xross.generator
 
 import com.squareup.kotlinpoet.CodeBlock
 import com.squareup.kotlinpoet.asTypeName
+import com.squareup.kotlinpoet.joinToCode
 import org.xross.generator.util.FFMConstants.MEMORY_LAYOUT
 import org.xross.helper.StringHelper.toCamelCase
 import org.xross.structures.XrossDefinition
@@ -125,6 +126,27 @@ object LayoutGenerator {
         init.endControlFlow()
     }
 
+    /**
+     * The C layout of a by-value struct, known at generation time: its fields are scalars in
+     * declaration order, each aligned to its own size.
+     */
+    fun byValueAbiLayout(meta: XrossDefinition.Struct): CodeBlock {
+        val members = mutableListOf<CodeBlock>()
+        var offset = 0L
+        var align = 1L
+        meta.fields.forEach { field ->
+            val size = field.ty.kotlinSize
+            val pad = (size - offset % size) % size
+            if (pad > 0) members.add(CodeBlock.of("%T.paddingLayout(%L)", MEMORY_LAYOUT, pad))
+            members.add(CodeBlock.of("%M.withName(%S)", field.ty.layoutMember, field.name))
+            offset += pad + size
+            align = maxOf(align, size)
+        }
+        val tail = (align - offset % align) % align
+        if (tail > 0) members.add(CodeBlock.of("%T.paddingLayout(%L)", MEMORY_LAYOUT, tail))
+        return CodeBlock.of("%T.structLayout(%L)", MEMORY_LAYOUT, members.joinToCode(", "))
+    }
+
     fun buildEnumLayoutInit(init: CodeBlock.Builder, meta: XrossDefinition.Enum) {
         init.add("\n// --- Enum Offset and VarHandle Resolution ---\n")
         init.addStatement("val variantRegex = %T(%S)", Regex::class.asTypeName(), "(\\w+)(?:\\{(.*)})?")
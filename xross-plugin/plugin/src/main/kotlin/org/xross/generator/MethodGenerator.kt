@@ -67,7 +67,7 @@ object MethodGenerator {
                 )
             }
 
-            method.args.forEach { arg ->
+            GeneratorUtils.kotlinArgs(method.args).forEach { arg ->
                 funBuilder.addParameter(
                     arg.name.toCamelCase().escapeKotlinKeyword(),
                     GeneratorUtils.resolveReturnType(arg.ty, basePackage),
@@ -76,7 +76,7 @@ object MethodGenerator {
 
             val isPanicable = method.handleMode is HandleMode.Panicable
             val isComplexRet =
-                method.ret is XrossType.RustString || method.isAsync || method.ret is XrossType.Vec || method.ret is XrossType.Slice
+                method.ret is XrossType.RustString || method.isAsync || method.ret is XrossType.Vec || method.ret is XrossType.Slice || method.ret.isSequence
 
             if (method.isRaw) {
                 // Generate the raw private method first
@@ -85,7 +85,7 @@ object MethodGenerator {
                 val actualReturnType = if (isPanicable || isValueRet) MEMORY_SEGMENT else returnType
                 val rawFunBuilder = FunSpec.builder(rawKotlinName).returns(actualReturnType).addModifiers(KModifier.PRIVATE)
                 if (method.isAsync) rawFunBuilder.addModifiers(KModifier.SUSPEND)
-                method.args.forEach { arg ->
+                GeneratorUtils.kotlinArgs(method.args).forEach { arg ->
                     val kType = if (arg.ty is XrossType.Object && arg.ty.ownership == XrossType.Ownership.Value) MEMORY_SEGMENT else GeneratorUtils.resolveReturnType(arg.ty, basePackage)
                     rawFunBuilder.addParameter(
                         arg.name.toCamelCase().escapeKotlinKeyword(),
@@ -102,6 +102,7 @@ object MethodGenerator {
                 }
 
                 rawBody.beginControlFlow("return try")
+                val rawScopes = GeneratorUtils.beginCancellationScopes(method, rawBody, basePackage)
                 val callArgs = mutableListOf<CodeBlock>()
                 if (method.methodType != XrossMethodType.Static) callArgs.add(CodeBlock.of("currentSegment"))
                 val argPrep = CodeBlock.builder()
@@ -173,6 +174,8 @@ object MethodGenerator {
                 } else {
                     val invokeExpr = if (method.ret is XrossType.Void) {
                         CodeBlock.of("%L.invoke(%L)", handleName, callArgs.joinToCode(", "))
+                    } else if (method.ret is XrossType.Object && method.ret.byValue) {
+                        byValueInvoke(handleName, callArgs)
                     } else {
                         CodeBlock.of("%L.invokeExact(%L)", handleName, callArgs.joinToCode(", "))
                     }
@@ -181,6 +184,7 @@ object MethodGenerator {
 
                 rawBody.add(argPrep.build())
                 if (needsArena || (forceConfined && !(isPanicable || isValueRet))) rawBody.endControlFlow()
+                repeat(rawScopes) { rawBody.endControlFlow() }
                 rawBody.nextControlFlow("catch (e: Throwable)")
                 rawBody.addStatement("if (e is %T) throw e", ClassName("$basePackage.xross.runtime", "XrossException"))
                 rawBody.addStatement("throw %T(e)", RuntimeException::class.asTypeName())
@@ -192,14 +196,14 @@ object MethodGenerator {
                 // Now generate the wrapper method with TODO
                 val wrapperFunBuilder = FunSpec.builder(kotlinName).returns(actualReturnType)
                 if (method.isAsync) wrapperFunBuilder.addModifiers(KModifier.SUSPEND)
-                method.args.forEach { arg ->
+                GeneratorUtils.kotlinArgs(method.args).forEach { arg ->
                     val kType = if (arg.ty is XrossType.Object && arg.ty.ownership == XrossType.Ownership.Value) MEMORY_SEGMENT else GeneratorUtils.resolveReturnType(arg.ty, basePackage)
                     wrapperFunBuilder.addParameter(
                         arg.name.toCamelCase().escapeKotlinKeyword(),
                         kType,
                     )
                 }
-                val argNames = method.args.joinToString(", ") { it.name.toCamelCase().escapeKotlinKeyword() }
+                val argNames = GeneratorUtils.kotlinArgs(method.args).joinToString(", ") { it.name.toCamelCase().escapeKotlinKeyword() }
                 val callStmt = if (method.ret is XrossType.Void && !isPanicable) "" else "return "
                 wrapperFunBuilder.addCode("// TODO: Define how to process arguments and handle panics (if any) before returning\n")
                 wrapperFunBuilder.addCode("${callStmt}$rawKotlinName($argNames)\n")
@@ -219,6 +223,7 @@ object MethodGenerator {
             if (method.ret !is XrossType.Void) body.add("return ")
 
             body.beginControlFlow("try")
+            val scopes = GeneratorUtils.beginCancellationScopes(method, body, basePackage)
             val callArgs = mutableListOf<CodeBlock>()
             if (method.methodType != XrossMethodType.Static) callArgs.add(CodeBlock.of("currentSegment"))
 
@@ -273,6 +278,8 @@ object MethodGenerator {
             } else {
                 if (method.ret is XrossType.Void) {
                     CodeBlock.of("%L.invoke(%L)", handleName, callArgs.joinToCode(", "))
+                } else if (method.ret is XrossType.Object && method.ret.byValue) {
+                    byValueInvoke(handleName, callArgs)
                 } else {
                     CodeBlock.of("%L.invokeExact(%L)", handleName, callArgs.joinToCode(", "))
                 }
@@ -282,6 +289,7 @@ object MethodGenerator {
             body.add(InvocationGenerator.applyMethodCall(method, call, returnType, selfType, basePackage, meta = meta))
 
             if (needsArena || forceConfined) body.endControlFlow()
+            repeat(scopes) { body.endControlFlow() }
             body.nextControlFlow("catch (e: Throwable)")
             val xrossException = ClassName("$basePackage.xross.runtime", "XrossException")
             body.addStatement("if (e is %T) throw e", xrossException)
@@ -305,7 +313,7 @@ object MethodGenerator {
                     val withFunBuilder = FunSpec.builder(withFunName).addTypeVariable(TypeVariableName("R"))
                     if (method.isAsync) withFunBuilder.addModifiers(KModifier.SUSPEND)
 
-                    method.args.forEach { arg ->
+                    GeneratorUtils.kotlinArgs(method.args).forEach { arg ->
                         withFunBuilder.addParameter(
                             arg.name.toCamelCase().escapeKotlinKeyword(),
                             GeneratorUtils.resolveReturnType(arg.ty, basePackage),
@@ -330,6 +338,7 @@ object MethodGenerator {
                     }
 
                     withBody.beginControlFlow("return try")
+                    val withScopes = GeneratorUtils.beginCancellationScopes(method, withBody, basePackage)
                     val withCallArgs = mutableListOf<CodeBlock>()
                     if (method.methodType != XrossMethodType.Static) withCallArgs.add(CodeBlock.of("curSeg"))
 
@@ -376,7 +385,7 @@ object MethodGenerator {
                     withArgPrep.beginControlFlow("try")
                     withArgPrep.addStatement("block(view)")
                     withArgPrep.nextControlFlow("finally")
-                    withArgPrep.addStatement("xrossFreeBufferHandle.invoke(resRaw)")
+                    withArgPrep.addStatement("%L.invoke(resRaw)", method.ret.bufferReleaseHandle)
                     withArgPrep.endControlFlow()
                     withArgPrep.endControlFlow()
                     withArgPrep.addStatement("res")
@@ -384,6 +393,7 @@ object MethodGenerator {
                     if (!method.isAsync) withArgPrep.endControlFlow()
 
                     withBody.add(withArgPrep.build())
+                    repeat(withScopes) { withBody.endControlFlow() }
 
                     withBody.nextControlFlow("catch (e: Throwable)")
                     withBody.addStatement(
@@ -403,4 +413,13 @@ object MethodGenerator {
             }
         }
     }
+    /**
+     * Calls a handle returning a by-value struct. The struct is written into memory from an
+     * automatic arena, which the returned object keeps alive.
+     */
+    private fun byValueInvoke(handleName: String, callArgs: List<CodeBlock>): CodeBlock {
+        val args = mutableListOf(CodeBlock.of("(java.lang.foreign.Arena.ofAuto() as %T)", SegmentAllocator::class.asTypeName()))
+        args.addAll(callArgs)
+        return CodeBlock.of("%L.invokeExact(%L)", handleName, args.joinToCode(", "))
+    }
 }
@@ -1,6 +1,7 @@
 package org.xross.generator
 
 import com.squareup.kotlinpoet.*
+import com.squareup.kotlinpoet.ParameterizedTypeName.Companion.parameterizedBy
 import org.xross.generator.util.*
 import java.io.File
 import java.lang.foreign.Arena
@@ -19,11 +20,20 @@ object RuntimeGenerator {
 
         // --- XrossException ---
         val xrossException = TypeSpec.classBuilder("XrossException")
+            .addModifiers(KModifier.OPEN)
             .superclass(Throwable::class)
             .primaryConstructor(FunSpec.constructorBuilder().addParameter("error", Any::class).build())
             .addProperty(PropertySpec.builder("error", Any::class).initializer("error").build())
             .build()
 
+        // --- XrossRejectedException ---
+        val xrossRejectedException = TypeSpec.classBuilder("XrossRejectedException")
+            .addKdoc("Thrown when an export bounded by `max_concurrency` is busy and the call was not started.")
+            .superclass(ClassName(pkg, "XrossException"))
+            .addSuperclassConstructorParameter("error")
+            .primaryConstructor(FunSpec.constructorBuilder().addParameter("error", String::class).build())
+            .build()
+
         // --- XrossObject Interface ---
         val xrossObject = TypeSpec.interfaceBuilder("XrossObject")
             .addSuperinterface(AutoCloseable::class)
@@ -123,6 +133,373 @@ object RuntimeGenerator {
             )
             .build()
 
+        // --- XrossContiguousList ---
+        val elementType = TypeVariableName("T", ClassName(pkg, "XrossObject"))
+        val xrossContiguousList = TypeSpec.classBuilder("XrossContiguousList")
+            .addKdoc("The elements of a `contiguous` return: views into one native allocation, valid until the list is closed or collected.")
+            .addTypeVariable(elementType)
+            .superclass(ClassName("kotlin.collections", "AbstractList").parameterizedBy(elementType))
+            .addSuperinterface(ClassName(pkg, "XrossObject"))
+            .primaryConstructor(
+                FunSpec.constructorBuilder()
+                    .addParameter("buffer", MEMORY_SEGMENT)
+                    .addParameter("stride", Long::class)
+                    .addParameter("dropVecHandle", MethodHandle::class)
+                    .addParameter("view", LambdaTypeName.get(null, MEMORY_SEGMENT, ClassName(pkg, "XrossObject"), returnType = elementType))
+                    .build(),
+            )
+            .addProperty(PropertySpec.builder("stride", Long::class, KModifier.PRIVATE).initializer("stride").build())
+            .addProperty(
+                PropertySpec.builder("view", LambdaTypeName.get(null, MEMORY_SEGMENT, ClassName(pkg, "XrossObject"), returnType = elementType), KModifier.PRIVATE)
+                    .initializer("view").build(),
+            )
+            .addProperty(
+                PropertySpec.builder("header", MEMORY_SEGMENT, KModifier.PRIVATE)
+                    .initializer("%T.ofAuto().allocate(24L).copyFrom(buffer.reinterpret(24L))", Arena::class)
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("size", Int::class, KModifier.OVERRIDE)
+                    .initializer("header.get(java.lang.foreign.ValueLayout.JAVA_LONG, 8L).toInt()")
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("segment", MEMORY_SEGMENT, KModifier.OVERRIDE)
+                    .initializer("header.get(java.lang.foreign.ValueLayout.ADDRESS, 16L).reinterpret(size * stride)")
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("_isValid", ClassName("java.util.concurrent.atomic", "AtomicBoolean"), KModifier.PRIVATE)
+                    .initializer("java.util.concurrent.atomic.AtomicBoolean(true)").build(),
+            )
+            .addProperty(
+                PropertySpec.builder("isValid", Boolean::class, KModifier.OVERRIDE)
+                    .getter(FunSpec.getterBuilder().addStatement("return _isValid.get()").build())
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("cleanable", CLEANABLE, KModifier.PRIVATE)
+                    .initializer(
+                        CodeBlock.builder()
+                            .add("run {\n")
+                            .indent()
+                            .add(
+                                "val h = header\n" +
+                                    "val v = _isValid\n" +
+                                    "%T.registerCleaner(this) {\n" +
+                                    "    if (v.compareAndSet(true, false)) {\n" +
+                                    "        try { dropVecHandle.invoke(h) } catch (e: Throwable) { e.printStackTrace() }\n" +
+                                    "    }\n" +
+                                    "}\n",
+                                ClassName(pkg, "XrossRuntime"),
+                            )
+                            .unindent()
+                            .add("}")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("get")
+                    .addModifiers(KModifier.OVERRIDE)
+                    .addParameter("index", Int::class)
+                    .returns(elementType)
+                    .addCode(
+                        "if (index < 0 || index >= size) throw IndexOutOfBoundsException(\"Index \$index out of bounds for size \$size\")\n" +
+                            "if (!isValid) throw NullPointerException(\"Access to invalid native collection\")\n" +
+                            "return view(segment.asSlice(index * stride, stride), this)\n",
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("clearCache")
+                    .addModifiers(KModifier.OVERRIDE)
+                    .addStatement("// Elements are views; nothing is cached")
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("close")
+                    .addModifiers(KModifier.OVERRIDE)
+                    .addStatement("cleanable.clean()")
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("relinquish")
+                    .addModifiers(KModifier.OVERRIDE)
+                    .addStatement("_isValid.set(false)")
+                    .build(),
+            )
+            .build()
+
+        // --- XrossPullHandle ---
+        val xrossPullHandle = TypeSpec.classBuilder("XrossPullHandle")
+            .addKdoc(
+                "An `XrossIterator` or `XrossStream` returned by an export. Closed once exhausted, when [close] is called, or when collected.",
+            )
+            .addModifiers(KModifier.INTERNAL)
+            .addSuperinterface(AutoCloseable::class)
+            .primaryConstructor(
+                FunSpec.constructorBuilder()
+                    .addParameter("handle", MEMORY_SEGMENT)
+                    .addParameter("isStream", Boolean::class)
+                    .build(),
+            )
+            .addProperty(PropertySpec.builder("isStream", Boolean::class, KModifier.PRIVATE).initializer("isStream").build())
+            .addProperty(
+                PropertySpec.builder("ptr", MEMORY_SEGMENT, KModifier.PRIVATE)
+                    .initializer("handle.get(java.lang.foreign.ValueLayout.ADDRESS, 0L)")
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("nextFn", MethodHandle::class, KModifier.PRIVATE)
+                    .initializer(
+                        "java.lang.foreign.Linker.nativeLinker().downcallHandle(\n" +
+                            "    handle.get(java.lang.foreign.ValueLayout.ADDRESS, 8L),\n" +
+                            "    java.lang.foreign.FunctionDescriptor.of(\n" +
+                            "        if (isStream) java.lang.foreign.ValueLayout.JAVA_INT else java.lang.foreign.ValueLayout.JAVA_BOOLEAN,\n" +
+                            "        java.lang.foreign.ValueLayout.ADDRESS,\n" +
+                            "        java.lang.foreign.ValueLayout.ADDRESS,\n" +
+                            "    ),\n" +
+                            ")",
+                    )
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("lock", Any::class, KModifier.PRIVATE).initializer("Any()").build(),
+            )
+            .addProperty(
+                PropertySpec.builder("_isOpen", ClassName("java.util.concurrent.atomic", "AtomicBoolean"), KModifier.PRIVATE)
+                    .initializer("java.util.concurrent.atomic.AtomicBoolean(true)").build(),
+            )
+            .addProperty(
+                PropertySpec.builder("cleanable", CLEANABLE, KModifier.PRIVATE)
+                    .initializer(
+                        CodeBlock.builder()
+                            .add("run {\n")
+                            .indent()
+                            .add(
+                                "val p = ptr\n" +
+                                    "val o = _isOpen\n" +
+                                    "val closeFn = java.lang.foreign.Linker.nativeLinker().downcallHandle(\n" +
+                                    "    handle.get(java.lang.foreign.ValueLayout.ADDRESS, 16L),\n" +
+                                    "    java.lang.foreign.FunctionDescriptor.ofVoid(java.lang.foreign.ValueLayout.ADDRESS),\n" +
+                                    ")\n" +
+                                    "%T.registerCleaner(this) {\n" +
+                                    "    if (o.compareAndSet(true, false)) {\n" +
+                                    "        try { closeFn.invoke(p) } catch (e: Throwable) { e.printStackTrace() }\n" +
+                                    "    }\n" +
+                                    "}\n",
+                                ClassName(pkg, "XrossRuntime"),
+                            )
+                            .unindent()
+                            .add("}")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("pull")
+                    .addKdoc("Returns the next item mapped by [mapper], [PENDING] if a stream has none yet, or [DONE].")
+                    .addTypeVariable(TypeVariableName("T"))
+                    .addParameter("mapper", LambdaTypeName.get(null, MEMORY_SEGMENT, returnType = TypeVariableName("T")))
+                    .returns(Any::class.asTypeName().copy(nullable = true))
+                    .addCode(
+                        "return synchronized(lock) {\n" +
+                            "    if (!_isOpen.get()) return DONE\n" +
+                            "    java.lang.foreign.Arena.ofConfined().use { arena ->\n" +
+                            "        val out = arena.allocate(16L, 8L)\n" +
+                            "        val state = if (isStream) {\n" +
+                            "            nextFn.invokeExact(ptr, out) as Int\n" +
+                            "        } else {\n" +
+                            "            if (nextFn.invokeExact(ptr, out) as Boolean) 1 else 2\n" +
+                            "        }\n" +
+                            "        when (state) {\n" +
+                            "            0 -> PENDING\n" +
+                            "            1 -> {\n" +
+                            "                val isOk = out.get(java.lang.foreign.ValueLayout.JAVA_BYTE, 0L) != (0).toByte()\n" +
+                            "                val itemPtr = out.get(java.lang.foreign.ValueLayout.ADDRESS, 8L)\n" +
+                            "                if (!isOk) throw XrossException(XrossString(itemPtr.reinterpret(24)).toString())\n" +
+                            "                mapper(itemPtr)\n" +
+                            "            }\n" +
+                            "            else -> {\n" +
+                            "                cleanable.clean()\n" +
+                            "                DONE\n" +
+                            "            }\n" +
+                            "        }\n" +
+                            "    }\n" +
+                            "}\n",
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("close")
+                    .addModifiers(KModifier.OVERRIDE)
+                    .addStatement("synchronized(lock) { cleanable.clean() }")
+                    .build(),
+            )
+            .addType(
+                TypeSpec.companionObjectBuilder()
+                    .addProperty(PropertySpec.builder("PENDING", Any::class).initializer("Any()").build())
+                    .addProperty(PropertySpec.builder("DONE", Any::class).initializer("Any()").build())
+                    .build(),
+            )
+            .build()
+
+        // --- XrossSequence ---
+        val itemType = TypeVariableName("T")
+        val xrossSequence = TypeSpec.classBuilder("XrossSequence")
+            .addKdoc(
+                "The items of an `Iterator` return, pulled from native code as they are consumed. " +
+                    "It can be iterated once; close it to stop early.",
+            )
+            .addTypeVariable(itemType)
+            .addSuperinterface(ClassName("kotlin.sequences", "Sequence").parameterizedBy(itemType))
+            .addSuperinterface(AutoCloseable::class)
+            .primaryConstructor(
+                FunSpec.constructorBuilder()
+                    .addParameter("handle", MEMORY_SEGMENT)
+                    .addParameter("mapper", LambdaTypeName.get(null, MEMORY_SEGMENT, returnType = itemType))
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("mapper", LambdaTypeName.get(null, MEMORY_SEGMENT, returnType = itemType), KModifier.PRIVATE)
+                    .initializer("mapper").build(),
+            )
+            .addProperty(
+                PropertySpec.builder("source", ClassName(pkg, "XrossPullHandle"), KModifier.PRIVATE)
+                    .initializer("XrossPullHandle(handle, isStream = false)").build(),
+            )
+            .addProperty(
+                PropertySpec.builder("iterated", ClassName("java.util.concurrent.atomic", "AtomicBoolean"), KModifier.PRIVATE)
+                    .initializer("java.util.concurrent.atomic.AtomicBoolean(false)").build(),
+            )
+            .addFunction(
+                FunSpec.builder("iterator")
+                    .addModifiers(KModifier.OVERRIDE)
+                    .returns(ClassName("kotlin.collections", "Iterator").parameterizedBy(itemType))
+                    .addCode(
+                        "check(iterated.compareAndSet(false, true)) { \"A native sequence can only be iterated once\" }\n" +
+                            "return object : Iterator<T> {\n" +
+                            "    private var buffered: Any? = XrossPullHandle.PENDING\n" +
+                            "\n" +
+                            "    override fun hasNext(): Boolean {\n" +
+                            "        if (buffered === XrossPullHandle.PENDING) buffered = source.pull(mapper)\n" +
+                            "        return buffered !== XrossPullHandle.DONE\n" +
+                            "    }\n" +
+                            "\n" +
+                            "    override fun next(): T {\n" +
+                            "        if (!hasNext()) throw NoSuchElementException()\n" +
+                            "        @Suppress(\"UNCHECKED_CAST\")\n" +
+                            "        val item = buffered as T\n" +
+                            "        buffered = XrossPullHandle.PENDING\n" +
+                            "        return item\n" +
+                            "    }\n" +
+                            "}\n",
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("close")
+                    .addModifiers(KModifier.OVERRIDE)
+                    .addStatement("source.close()")
+                    .build(),
+            )
+            .build()
+
+        // --- XrossCancellation ---
+        val xrossCancellation = TypeSpec.objectBuilder("XrossCancellation")
+            .addKdoc(
+                "Flags passed to exports taking a `XrossCancelToken`. A flag is set once the calling thread is " +
+                    "interrupted during the call, so `runInterruptible` also forwards coroutine cancellation. " +
+                    "Flags of `blocking` exports are set when the awaiting coroutine is cancelled.",
+            )
+            .addProperty(
+                PropertySpec.builder(
+                    "watched",
+                    ClassName("java.util.concurrent", "ConcurrentHashMap").parameterizedBy(MEMORY_SEGMENT, Thread::class.asTypeName()),
+                    KModifier.PRIVATE,
+                )
+                    .initializer("java.util.concurrent.ConcurrentHashMap()")
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("FLAG", ClassName("java.lang.invoke", "VarHandle"), KModifier.PRIVATE)
+                    .initializer("java.lang.foreign.ValueLayout.JAVA_BYTE.varHandle()")
+                    .build(),
+            )
+            .addProperty(
+                PropertySpec.builder("watcher", Thread::class, KModifier.PRIVATE)
+                    .delegate(
+                        "lazy {\n" +
+                            "    Thread.ofPlatform().daemon().name(\"xross-cancel-watcher\").start {\n" +
+                            "        while (true) {\n" +
+                            "            if (watched.isEmpty()) java.util.concurrent.locks.LockSupport.park()\n" +
+                            "            watched.forEach { (flag, thread) ->\n" +
+                            "                if (thread.isInterrupted) FLAG.setRelease(flag, 0L, 1.toByte())\n" +
+                            "            }\n" +
+                            "            Thread.sleep(10)\n" +
+                            "        }\n" +
+                            "    }\n" +
+                            "}",
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("interruptible")
+                    .addKdoc("Runs [block] with a flag that is set if the calling thread is interrupted before [block] returns.")
+                    .addModifiers(KModifier.INLINE)
+                    .addTypeVariable(TypeVariableName("T"))
+                    .addParameter("block", LambdaTypeName.get(null, MEMORY_SEGMENT, returnType = TypeVariableName("T")))
+                    .returns(TypeVariableName("T"))
+                    .addCode(
+                        "val flag = watch()\n" +
+                            "try {\n" +
+                            "    return block(flag)\n" +
+                            "} finally {\n" +
+                            "    unwatch(flag)\n" +
+                            "}\n",
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("watch")
+                    .addAnnotation(PublishedApi::class)
+                    .addModifiers(KModifier.INTERNAL)
+                    .returns(MEMORY_SEGMENT)
+                    .addCode(
+                        "val thread = Thread.currentThread()\n" +
+                            "// An automatic arena: the watcher may still write the flag after the call returned\n" +
+                            "val flag = java.lang.foreign.Arena.ofAuto().allocate(1L)\n" +
+                            "if (thread.isInterrupted) FLAG.setRelease(flag, 0L, 1.toByte())\n" +
+                            "watched[flag] = thread\n" +
+                            "java.util.concurrent.locks.LockSupport.unpark(watcher)\n" +
+                            "return flag\n",
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("unwatch")
+                    .addAnnotation(PublishedApi::class)
+                    .addModifiers(KModifier.INTERNAL)
+                    .addParameter("flag", MEMORY_SEGMENT)
+                    .addStatement("watched.remove(flag)")
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("flag")
+                    .addKdoc("A flag for a `blocking` export, set through [cancel] when the awaiting coroutine is cancelled.")
+                    .returns(MEMORY_SEGMENT)
+                    .addStatement("return java.lang.foreign.Arena.ofAuto().allocate(1L)")
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("cancel")
+                    .addParameter("flag", MEMORY_SEGMENT)
+                    .addStatement("FLAG.setRelease(flag, 0L, 1.toByte())")
+                    .build(),
+            )
+            .build()
+
         // --- XrossRuntime ---
         val xrossRuntime = TypeSpec.objectBuilder("XrossRuntime")
             .addProperty(
@@ -178,6 +555,52 @@ object RuntimeGenerator {
                     )
                     .build(),
             )
+            .addProperty(
+                PropertySpec.builder("abiVerified", ClassName("java.util.concurrent.atomic", "AtomicBoolean"), KModifier.PRIVATE)
+                    .initializer("java.util.concurrent.atomic.AtomicBoolean(false)")
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("verifyAbi")
+                    .addKdoc("Fails fast if the native library was built for another xross ABI than these bindings.")
+                    .addParameter("lookup", ClassName("java.lang.foreign", "SymbolLookup"))
+                    .addParameter("linker", ClassName("java.lang.foreign", "Linker"))
+                    .addCode(
+                        "if (abiVerified.get()) return\n" +
+                            "val symbol = lookup.find(\"xross_abi_version\").orElseThrow {\n" +
+                            "    IllegalStateException(\"[Xross] The native library does not export xross_abi_version; it was built with an older xross than these bindings.\")\n" +
+                            "}\n" +
+                            "val handle = linker.downcallHandle(symbol, java.lang.foreign.FunctionDescriptor.of(java.lang.foreign.ValueLayout.JAVA_INT))\n" +
+                            "val found = handle.invokeExact() as Int\n" +
+                            "if (found != %L) {\n" +
+                            "    throw IllegalStateException(\"[Xross] The native library uses ABI version \$found, but these bindings were generated for version %L. Regenerate the bindings or load the matching library.\")\n" +
+                            "}\n" +
+                            "abiVerified.set(true)\n",
+                        AbiFingerprint.ABI_VERSION,
+                        AbiFingerprint.ABI_VERSION,
+                    )
+                    .build(),
+            )
+            .addFunction(
+                FunSpec.builder("verifyLayout")
+                    .addKdoc("Fails fast if the fields of [typeName] in the native library differ from those the bindings were generated from.")
+                    .addParameter("lookup", ClassName("java.lang.foreign", "SymbolLookup"))
+                    .addParameter("linker", ClassName("java.lang.foreign", "Linker"))
+                    .addParameter("symbol", String::class)
+                    .addParameter("expected", Long::class)
+                    .addParameter("typeName", String::class)
+                    .addCode(
+                        "val fingerprint = lookup.find(symbol).orElseThrow {\n" +
+                            "    IllegalStateException(\"[Xross] \$typeName: \$symbol is missing; the native library is older than these bindings.\")\n" +
+                            "}\n" +
+                            "val handle = linker.downcallHandle(fingerprint, java.lang.foreign.FunctionDescriptor.of(java.lang.foreign.ValueLayout.JAVA_LONG))\n" +
+                            "val found = handle.invokeExact() as Long\n" +
+                            "if (found != expected) {\n" +
+                            "    throw IllegalStateException(\"[Xross] \$typeName: the fields in the native library do not match these bindings (layout fingerprint \${found.toULong().toString(16)}, expected \${expected.toULong().toString(16)}). Regenerate the bindings or load the matching library.\")\n" +
+                            "}\n",
+                    )
+                    .build(),
+            )
             .addFunction(
                 FunSpec.builder("ofSmart")
                     .returns(Arena::class)
@@ -262,6 +685,11 @@ object RuntimeGenerator {
                     .addParameter("taskPtr", MEMORY_SEGMENT)
                     .addParameter("pollFn", MethodHandle::class)
                     .addParameter("dropFn", MethodHandle::class)
+                    .addParameter(
+                        ParameterSpec.builder("cancelFlags", List::class.asClassName().parameterizedBy(MEMORY_SEGMENT))
+                            .defaultValue("emptyList()")
+                            .build(),
+                    )
                     .addParameter(
                         "mapper",
                         LambdaTypeName.get(null, MEMORY_SEGMENT, returnType = TypeVariableName("T")),
@@ -270,26 +698,65 @@ object RuntimeGenerator {
                     .addCode(
                         "try {\n" +
                             "    java.lang.foreign.Arena.ofConfined().use { arena ->\n" +
-                            "        while (true) {\n" +
-                            "            val resultRaw = pollFn.invokeExact(arena as java.lang.foreign.SegmentAllocator, taskPtr) as MemorySegment\n" +
-                            "            val isOk = resultRaw.get(java.lang.foreign.ValueLayout.JAVA_BYTE, 0L) != (0).toByte()\n" +
-                            "            val ptr = resultRaw.get(java.lang.foreign.ValueLayout.ADDRESS, 8L)\n" +
-                            "            if (ptr != MemorySegment.NULL) {\n" +
-                            "                if (!isOk) {\n" +
-                            "                    val errXs = XrossString(ptr.reinterpret(24))\n" +
-                            "                    throw XrossException(errXs.toString())\n" +
-                            "                }\n" +
-                            "                return mapper(ptr)\n" +
-                            "            }\n" +
+                            "        val out = arena.allocate(16L, 8L)\n" +
+                            "        // XrossPoll: 0 = pending, 1 = ready with the result written to `out`\n" +
+                            "        while (pollFn.invokeExact(taskPtr, out) as Int != 1) {\n" +
                             "            kotlinx.coroutines.delay(1)\n" +
                             "        }\n" +
+                            "        val isOk = out.get(java.lang.foreign.ValueLayout.JAVA_BYTE, 0L) != (0).toByte()\n" +
+                            "        val ptr = out.get(java.lang.foreign.ValueLayout.ADDRESS, 8L)\n" +
+                            "        if (!isOk) {\n" +
+                            "            val errXs = XrossString(ptr.reinterpret(24))\n" +
+                            "            // A task rejected by a concurrency limit has a null `taskPtr`.\n" +
+                            "            if (taskPtr == java.lang.foreign.MemorySegment.NULL) throw XrossRejectedException(errXs.toString())\n" +
+                            "            throw XrossException(errXs.toString())\n" +
+                            "        }\n" +
+                            "        return mapper(ptr)\n" +
                             "    }\n" +
+                            "} catch (e: kotlinx.coroutines.CancellationException) {\n" +
+                            "    // A started blocking job keeps running and `dropFn` waits for it; ask it to stop first.\n" +
+                            "    cancelFlags.forEach { XrossCancellation.cancel(it) }\n" +
+                            "    throw e\n" +
                             "} finally {\n" +
                             "    dropFn.invoke(taskPtr)\n" +
                             "}\n",
                     )
                     .build(),
             )
+            .addFunction(
+                FunSpec.builder("streamFlow")
+                    .addKdoc(
+                        "The items of a `Stream` return. The native producer runs from the call on; " +
+                            "the flow can be collected once and closes the stream when collection ends.",
+                    )
+                    .addTypeVariable(TypeVariableName("T"))
+                    .addParameter("handle", MEMORY_SEGMENT)
+                    .addParameter(
+                        "mapper",
+                        LambdaTypeName.get(null, MEMORY_SEGMENT, returnType = TypeVariableName("T")),
+                    )
+                    .returns(ClassName("kotlinx.coroutines.flow", "Flow").parameterizedBy(TypeVariableName("T")))
+                    .addCode(
+                        "val source = XrossPullHandle(handle, isStream = true)\n" +
+                            "val collected = java.util.concurrent.atomic.AtomicBoolean(false)\n" +
+                            "return kotlinx.coroutines.flow.flow {\n" +
+                            "    check(collected.compareAndSet(false, true)) { \"A native stream can only be collected once\" }\n" +
+                            "    source.use {\n" +
+                            "        while (true) {\n" +
+                            "            val next = it.pull(mapper)\n" +
+                            "            if (next === XrossPullHandle.DONE) break\n" +
+                            "            if (next === XrossPullHandle.PENDING) {\n" +
+                            "                kotlinx.coroutines.delay(1)\n" +
+                            "                continue\n" +
+                            "            }\n" +
+                            "            @Suppress(\"UNCHECKED_CAST\")\n" +
+                            "            emit(next as T)\n" +
+                            "        }\n" +
+                            "    }\n" +
+                            "}\n",
+                    )
+                    .build(),
+            )
             .build()
 
         // --- XrossAsyncLock ---
@@ -451,8 +918,13 @@ object RuntimeGenerator {
             .addImport("java.util.concurrent.locks", "ReentrantReadWriteLock")
             .addImport("java.lang.foreign", "ValueLayout", "SegmentAllocator", "Arena", "Linker", "SymbolLookup", "FunctionDescriptor")
             .addType(xrossException)
+            .addType(xrossRejectedException)
             .addType(xrossObject)
             .addType(xrossNativeObject)
+            .addType(xrossContiguousList)
+            .addType(xrossPullHandle)
+            .addType(xrossSequence)
+            .addType(xrossCancellation)
             .addType(xrossRuntime)
             .addType(xrossAsync)
             .addType(xrossAsyncLock)
@@ -8,16 +8,18 @@ class TypeResolver(
     metadataDir: File,
 ) {
     private val shortNameToFqn = mutableMapOf<String, MutableSet<String>>()
+    private val byValueSignatures = mutableSetOf<String>()
     private val json = Json { ignoreUnknownKeys = true }
 
     init {
         if (metadataDir.exists()) {
-            metadataDir.walkTopDown().filter { it.extension == "json" }.forEach { file ->
+            definitionFiles(metadataDir).forEach { file ->
                 try {
                     val def = json.decodeFromString<XrossDefinition>(file.readText())
                     val name = def.name
                     val fqn = def.signature
                     shortNameToFqn.getOrPut(name) { mutableSetOf() }.add(fqn)
+                    if (def is XrossDefinition.Struct && def.byValue) byValueSignatures.add(fqn)
                 } catch (e: Exception) {
                     println("Failed to parse $file: ${e.message}")
                     // Ignore malformed JSON during scanning
@@ -57,4 +59,34 @@ class TypeResolver(
             }
         }
     }
+
+    /**
+     * Whether [signature] (already resolved) names a struct passed as its C layout.
+     */
+    fun isByValue(signature: String): Boolean = signature in byValueSignatures
+
+    companion object {
+        /** The compilation unit whose directory describes the built library. */
+        const val LIBRARY_UNIT = "lib"
+
+        /**
+         * Lists the definition files below [metadataDir] that belong to the library.
+         * Each compilation unit of a crate writes to its own directory and records its `unit`
+         * in `session.manifest`; directories of other units (test harnesses, binaries) are skipped.
+         */
+        fun definitionFiles(metadataDir: File): List<File> =
+            metadataDir
+                .walkTopDown()
+                .onEnter { dir ->
+                    val manifest = File(dir, "session.manifest")
+                    val unit =
+                        if (manifest.isFile) {
+                            manifest.readLines().firstOrNull { it.startsWith("unit ") }?.removePrefix("unit ")
+                        } else {
+                            null
+                        }
+                    unit == null || unit.trim() == LIBRARY_UNIT
+                }.filter { it.isFile && it.extension == "json" && !it.name.startsWith(".") }
+                .toList()
+    }
 }
@@ -3,6 +3,7 @@ package org.xross.generator
 import com.squareup.kotlinpoet.*
 import org.xross.generator.util.GeneratorUtils
 import org.xross.helper.StringHelper.toCamelCase
+import org.xross.structures.HandleMode
 import org.xross.structures.XrossDefinition
 import org.xross.structures.XrossMethod
 import org.xross.structures.XrossType
@@ -16,6 +17,8 @@ object XrossGenerator {
 
     data class XrossGeneratorProperty(
         var useUnsignedTypes: Boolean = false,
+        // usize/isize の幅。ビルドする JVM ではなくターゲットに合わせる
+        var pointerWidth: Int = java.lang.foreign.ValueLayout.ADDRESS.byteSize().toInt() * 8,
     )
 
     fun generate(
@@ -60,7 +63,7 @@ object XrossGenerator {
                 v.copy(
                     fields = v.fields.map {
                         it.copy(
-                            ty = resolveType(it.ty, resolver, "${meta.name}.${v.name}"),
+                            ty = markByValue(resolveType(it.ty, resolver, "${meta.name}.${v.name}"), resolver),
                         )
                     },
                 )
@@ -78,13 +81,33 @@ object XrossGenerator {
         )
     }
 
-    private fun resolveMethods(methods: List<XrossMethod>, resolver: TypeResolver, context: String): List<XrossMethod> = methods.map { m ->
+    private fun resolveMethods(methods: List<XrossMethod>, resolver: TypeResolver, context: String): List<XrossMethod> = methods.map(::normalizeSequence).map { m ->
+        // Constructors hand back a handle; panicable, async and blocking returns travel inside a result.
+        val retByValue = !m.isConstructor && !m.isAsync && (m.handleMode is HandleMode.Normal || m.handleMode is HandleMode.Critical)
         m.copy(
-            args = m.args.map { it.copy(ty = resolveType(it.ty, resolver, "$context.${m.name}")) },
-            ret = resolveType(m.ret, resolver, "$context.${m.name}"),
+            args = m.args.map { it.copy(ty = markByValue(resolveType(it.ty, resolver, "$context.${m.name}"), resolver)) },
+            ret = resolveType(m.ret, resolver, "$context.${m.name}").let { if (retByValue) markByValue(it, resolver) else it },
         )
     }
 
+    /**
+     * A sequence is handed out synchronously whatever the export's declared mode: an `async fn`
+     * returning a stream only starts it, and the export has no panicable or blocking form.
+     */
+    private fun normalizeSequence(m: XrossMethod): XrossMethod = if (m.ret.isSequence) {
+        m.copy(isAsync = false, handleMode = HandleMode.Normal)
+    } else {
+        m
+    }
+
+    private fun markByValue(type: XrossType, resolver: TypeResolver): XrossType = if (
+        type is XrossType.Object && type.ownership == XrossType.Ownership.Owned && resolver.isByValue(type.signature)
+    ) {
+        type.copy(byValue = true)
+    } else {
+        type
+    }
+
     private fun resolveType(type: XrossType, resolver: TypeResolver, context: String): XrossType = when (type) {
         is XrossType.Object -> type.copy(signature = resolver.resolve(type.signature, context))
         is XrossType.Optional -> type.copy(inner = resolveType(type.inner, resolver, context))
@@ -94,6 +117,8 @@ object XrossGenerator {
         )
 
         is XrossType.Async -> type.copy(inner = resolveType(type.inner, resolver, context))
+        is XrossType.Stream -> type.copy(inner = resolveType(type.inner, resolver, context))
+        is XrossType.Iterator -> type.copy(inner = resolveType(type.inner, resolver, context))
         else -> type
     }
 
@@ -0,0 +1,65 @@
+package org.xross.generator.util
+
+import org.xross.structures.XrossDefinition
+import org.xross.structures.XrossField
+import org.xross.structures.XrossType
+
+/**
+ * Mirrors the fingerprints of `xross-metadata` so generated code can verify at load time
+ * that it matches the native library.
+ */
+object AbiFingerprint {
+    /** Must equal `xross_core::XROSS_ABI_VERSION`. */
+    const val ABI_VERSION = 6
+
+    /** 64-bit FNV-1a over UTF-8, as `xross_metadata::fnv1a64`. */
+    fun fnv1a64(text: String): Long {
+        var hash = -0x340d631b7bdddcdbL // 0xcbf29ce484222325
+        for (b in text.toByteArray(Charsets.UTF_8)) {
+            hash = hash xor (b.toLong() and 0xff)
+            hash *= 0x100000001b3L
+        }
+        return hash
+    }
+
+    /** Canonical text of a type, as `XrossType::abi_tag`. */
+    fun abiTag(ty: XrossType): String = when (ty) {
+        XrossType.Void -> "Void"
+        XrossType.Bool -> "Bool"
+        XrossType.I8 -> "I8"
+        XrossType.U8 -> "U8"
+        XrossType.I16 -> "I16"
+        XrossType.U16 -> "U16"
+        XrossType.I32 -> "I32"
+        XrossType.U32 -> "U32"
+        XrossType.I64 -> "I64"
+        XrossType.U64 -> "U64"
+        XrossType.ISize -> "ISize"
+        XrossType.USize -> "USize"
+        XrossType.F32 -> "F32"
+        XrossType.F64 -> "F64"
+        XrossType.Pointer -> "Pointer"
+        XrossType.RustString -> "String"
+        XrossType.CancelToken -> "CancelToken"
+        is XrossType.Slice -> "Slice<${abiTag(ty.inner)}>"
+        is XrossType.Vec -> "Vec<${abiTag(ty.inner)}>"
+        is XrossType.Optional -> "Option<${abiTag(ty.inner)}>"
+        is XrossType.Async -> "Async<${abiTag(ty.inner)}>"
+        is XrossType.Stream -> "Stream<${abiTag(ty.inner)}>"
+        is XrossType.Iterator -> "Iterator<${abiTag(ty.inner)}>"
+        is XrossType.Result -> "Result<${abiTag(ty.ok)},${abiTag(ty.err)}>"
+        is XrossType.Object -> "Object<${ty.signature.substringAfterLast('.')}:${ty.ownership.name}>"
+    }
+
+    private fun fieldsText(fields: List<XrossField>): String = fields.joinToString(";") { "${it.name}:${abiTag(it.ty)}" }
+
+    /** Fingerprint exported by `{prefix}_layout_fingerprint`, as `XrossDefinition::layout_fingerprint`. */
+    fun layoutFingerprint(meta: XrossDefinition): Long = fnv1a64(
+        when (meta) {
+            is XrossDefinition.Struct -> fieldsText(meta.fields)
+            is XrossDefinition.Opaque -> fieldsText(meta.fields)
+            is XrossDefinition.Enum -> meta.variants.joinToString(";") { "${it.name}{${fieldsText(it.fields)}}" }
+            is XrossDefinition.Function -> ""
+        },
+    )
+}
@@ -1,6 +1,8 @@
 package org.xross.generator.util
 
 import com.squareup.kotlinpoet.*
+import org.xross.generator.HandleResolver
+import org.xross.generator.XrossGenerator
 import org.xross.generator.util.FFMConstants.MEMORY_SEGMENT
 import org.xross.structures.XrossType
 
@@ -105,10 +107,13 @@ fun CodeBlock.Builder.addResultAllocation(
     )
     nextControlFlow("else")
     addStatement("$targetMemoryName.set(%M, 0L, 0.toByte())", FFMConstants.JAVA_BYTE)
+    // The error is passed as a string view of the exception message
+    addStatement("val ${targetMemoryName}Message = $valueName.exceptionOrNull()?.message")
     addStatement(
-        "$targetMemoryName.set(%M, 8L, %T.NULL)",
+        "$targetMemoryName.set(%M, 8L, if (${targetMemoryName}Message == null) %T.NULL else %L)",
         FFMConstants.ADDRESS,
-        ClassName("java.lang.foreign", "MemorySegment"),
+        MEMORY_SEGMENT,
+        GeneratorUtils.generateStringView("${targetMemoryName}Message", arenaName),
     )
     endControlFlow()
     return this
@@ -169,7 +174,22 @@ fun CodeBlock.Builder.addResultVariantResolution(
     basePackage: String,
     dropHandleName: String = "dropHandle",
 ) {
-    when (type) {
+    val isBoxedWide = XrossGenerator.property.pointerWidth < 64 &&
+        (type is XrossType.I64 || type is XrossType.U64 || type is XrossType.F64)
+    if (isBoxedWide) {
+        // 64-bit values do not fit a narrower pointer and come boxed
+        beginControlFlow("run")
+        addStatement("val boxed = (%L).reinterpret(8L)", ptrName)
+        val converter = GeneratorUtils.getUnsignedConverter(type)
+        if (converter.startsWith(" as")) {
+            addStatement("val value = (boxed.get(%M, 0L)$converter", type.layoutMember)
+        } else {
+            addStatement("val value = boxed.get(%M, 0L)", type.layoutMember)
+        }
+        addStatement("${HandleResolver.FREE_SCALAR_HANDLE}.invoke(boxed, 8L)")
+        addStatement("value")
+        endControlFlow()
+    } else when (type) {
         is XrossType.Object -> {
             beginControlFlow("run")
             val (sizeExpr, dropExpr, fromPointerExpr) = GeneratorUtils.compareExprs(
@@ -250,8 +270,8 @@ fun CodeBlock.Builder.addResultVariantResolution(
                 )
             }
             endControlFlow()
-            // Always free Buffer return
-            addStatement("if (%L != %T.NULL) xrossFreeBufferHandle.invoke(%L)", ptrName, MEMORY_SEGMENT, ptrName)
+            // Always free Buffer return, with the release function of its element width
+            addStatement("if (%L != %T.NULL) %L.invoke(%L)", ptrName, MEMORY_SEGMENT, type.bufferReleaseHandle, ptrName)
             addStatement("resArr")
             endControlFlow()
         }
@@ -278,6 +298,29 @@ fun CodeBlock.Builder.addResultVariantResolution(
     this.add("\n")
 }
 
+/**
+ * Wraps the XrossBuffer of a `contiguous` return in an XrossContiguousList of element views.
+ */
+fun CodeBlock.Builder.addContiguousResolution(
+    type: XrossType,
+    bufName: Any,
+    selfType: ClassName,
+    basePackage: String,
+) {
+    val innerType = if (type is XrossType.Vec) type.inner else (type as XrossType.Slice).inner
+    val elementName = GeneratorUtils.getClassName((innerType as XrossType.Object).signature, basePackage)
+    val (sizeExpr, _, fromPointerExpr) = GeneratorUtils.compareExprs(elementName, selfType)
+    val dropExpr = if (elementName == selfType) CodeBlock.of("dropVecHandle") else CodeBlock.of("%T.dropVecHandle", elementName)
+    addStatement(
+        "%T(%L, %L, %L) { seg, owner -> %L(seg, parent = owner, isPersistent = false) }",
+        ClassName("$basePackage.xross.runtime", "XrossContiguousList"),
+        bufName,
+        sizeExpr,
+        dropExpr,
+        fromPointerExpr,
+    )
+}
+
 fun CodeBlock.Builder.addOptionalResolution(
     inner: XrossType,
     resRaw: Any,
@@ -289,8 +332,26 @@ fun CodeBlock.Builder.addOptionalResolution(
     beginControlFlow("if ((%L) == %T.NULL)", resRaw, MEMORY_SEGMENT)
         .addStatement("null")
     nextControlFlow("else")
-    val innerType = GeneratorUtils.resolveReturnType(inner, basePackage)
-    addResultVariantResolution(inner, resRaw, innerType, selfType, basePackage, dropHandleName)
+    if (inner.isBoxedInOption) {
+        // The `Some` of a scalar is a box of the value, released once it has been read
+        addStatement("val boxed = (%L).reinterpret(%L)", resRaw, inner.kotlinSize)
+        when (inner) {
+            is XrossType.Bool -> addStatement("val value = boxed.get(%M, 0L) != (0).toByte()", inner.layoutMember)
+            else -> {
+                val converter = GeneratorUtils.getUnsignedConverter(inner)
+                if (converter.startsWith(" as")) {
+                    addStatement("val value = (boxed.get(%M, 0L)$converter", inner.layoutMember)
+                } else {
+                    addStatement("val value = boxed.get(%M, 0L)", inner.layoutMember)
+                }
+            }
+        }
+        addStatement("${HandleResolver.FREE_SCALAR_HANDLE}.invoke(boxed, %LL)", inner.kotlinSize)
+        addStatement("value")
+    } else {
+        val innerType = GeneratorUtils.resolveReturnType(inner, basePackage)
+        addResultVariantResolution(inner, resRaw, innerType, selfType, basePackage, dropHandleName)
+    }
     endControlFlow()
     endControlFlow()
 }
@@ -401,6 +462,7 @@ fun CodeBlock.Builder.addArgumentPreparation(
                     )
                     endControlFlow()
                 }
+                // A by-value struct is copied out of the segment; the object keeps its memory
                 callArgs.add(CodeBlock.of("$name.segment"))
             }
         }
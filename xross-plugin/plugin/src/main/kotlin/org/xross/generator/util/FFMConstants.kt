@@ -44,6 +44,18 @@ object FFMConstants {
         "dropFn",
     )
 
+    /** `XrossIterator` and `XrossStream`: the handle, then its next (or poll) and close functions. */
+    val XROSS_SEQUENCE_LAYOUT_CODE = com.squareup.kotlinpoet.CodeBlock.of(
+        "%T.structLayout(%M.withName(%S), %M.withName(%S), %M.withName(%S))",
+        MEMORY_LAYOUT,
+        ADDRESS,
+        "ptr",
+        ADDRESS,
+        "nextFn",
+        ADDRESS,
+        "closeFn",
+    )
+
     val XROSS_STRING_LAYOUT_CODE = com.squareup.kotlinpoet.CodeBlock.of(
         "%T.structLayout(%M.withName(%S), %M.withName(%S), %M.withName(%S))",
         MEMORY_LAYOUT,
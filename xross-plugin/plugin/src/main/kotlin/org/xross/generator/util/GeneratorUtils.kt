@@ -5,11 +5,11 @@ import com.squareup.kotlinpoet.ParameterizedTypeName.Companion.parameterizedBy
 import org.xross.generator.XrossGenerator
 import org.xross.helper.StringHelper.escapeKotlinKeyword
 import org.xross.helper.StringHelper.toCamelCase
+import org.xross.structures.HandleMode
 import org.xross.structures.XrossDefinition
 import org.xross.structures.XrossThreadSafety
 import org.xross.structures.XrossType
 import java.io.File
-import java.lang.foreign.ValueLayout
 
 /**
  * Utility functions for code generation.
@@ -210,6 +210,8 @@ object GeneratorUtils {
         is XrossType.Object -> getClassName(type.signature, basePackage)
         is XrossType.Optional -> resolveReturnType(type.inner, basePackage).copy(nullable = true)
         is XrossType.Result -> ClassName("kotlin", "Result").parameterizedBy(resolveReturnType(type.ok, basePackage))
+        is XrossType.Iterator -> ClassName("kotlin.sequences", "Sequence").parameterizedBy(resolveReturnType(type.inner, basePackage))
+        is XrossType.Stream -> ClassName("kotlinx.coroutines.flow", "Flow").parameterizedBy(resolveReturnType(type.inner, basePackage))
 
         // --- ここを修正 ---
         is XrossType.Slice, is XrossType.Vec -> {
@@ -301,21 +303,61 @@ object GeneratorUtils {
 
     /**
      * Returns an allocation expression based on the XrossType.
+     * Scalars are written to the arena and passed as a pointer to the value.
      */
     fun generateAllocMsg(
         ty: XrossType,
         valueName: String,
         arenaName: String = "java.lang.foreign.Arena.ofAuto()",
-    ): CodeBlock = when (ty) {
-        is XrossType.Object -> CodeBlock.of("$valueName.segment")
-        is XrossType.RustString -> CodeBlock.of("$arenaName.allocateFrom($valueName)")
-        is XrossType.F32 -> CodeBlock.of("MemorySegment.ofAddress(%L.toRawBits().toLong())", valueName)
-        is XrossType.F64 -> CodeBlock.of("MemorySegment.ofAddress(%L.toRawBits())", valueName)
-        is XrossType.Bool -> CodeBlock.of("MemorySegment.ofAddress(if (%L) 1L else 0L)", valueName)
-        else -> {
-            // Integer types <= 8 bytes
-            CodeBlock.of("MemorySegment.ofAddress(%L.toLong())", valueName)
-        }
+    ): CodeBlock = when {
+        ty is XrossType.Object -> CodeBlock.of("$valueName.segment")
+        ty is XrossType.RustString -> generateStringView(valueName, arenaName)
+        ty is XrossType.Bool -> CodeBlock.of(
+            "$arenaName.allocateFrom(%M, if (%L) 1.toByte() else 0.toByte())",
+            ty.layoutMember,
+            valueName,
+        )
+        ty.isBoxedInOption -> CodeBlock.of(
+            "$arenaName.allocateFrom(%M, %L%L)",
+            ty.layoutMember,
+            valueName,
+            getSignedConverter(ty),
+        )
+        else -> CodeBlock.of("MemorySegment.ofAddress(%L.toLong())", valueName)
+    }
+
+    /**
+     * Allocates an XrossStringView of the UTF-16 chars of a string, as a string behind a
+     * pointer (the `Some` of an `Option`, either side of a `Result`) is passed.
+     */
+    fun generateStringView(
+        valueName: String,
+        arenaName: String = "java.lang.foreign.Arena.ofAuto()",
+    ): CodeBlock {
+        val is64 = XrossGenerator.property.pointerWidth == 64
+        val wordSize = if (is64) 8L else 4L
+        val lenLayout = if (is64) FFMConstants.JAVA_LONG else FFMConstants.JAVA_INT
+        val lenValue = if (is64) "$valueName.length.toLong()" else "$valueName.length"
+        // A plain format rather than statements, as the block is embedded in another statement.
+        // One arena keeps the chars alive as long as the view; encoding 1 is UTF-16.
+        return CodeBlock.of(
+            "run {\n⇥" +
+                "val arena = $arenaName\n" +
+                "val view = arena.allocate(%LL, %LL)\n" +
+                "view.set(%M, 0L, arena.allocateFrom(%M, *$valueName.toCharArray()))\n" +
+                "view.set(%M, %LL, $lenValue)\n" +
+                "view.set(%M, %LL, 1.toByte())\n" +
+                "view\n" +
+                "⇤}",
+            wordSize * 3,
+            wordSize,
+            FFMConstants.ADDRESS,
+            FFMConstants.JAVA_CHAR,
+            lenLayout,
+            wordSize,
+            FFMConstants.JAVA_BYTE,
+            wordSize * 2,
+        )
     }
 
     /**
@@ -429,6 +471,34 @@ object GeneratorUtils {
         builder.addFunction(clearCache)
     }
 
+    /**
+     * Returns the arguments that appear in the Kotlin signature. `CancelToken` arguments are supplied by the call itself.
+     */
+    fun kotlinArgs(args: List<org.xross.structures.XrossField>): List<org.xross.structures.XrossField> = args.filter { it.ty !is XrossType.CancelToken }
+
+    /**
+     * Kotlin names of the `CancelToken` arguments of [method].
+     */
+    fun cancelTokenNames(method: org.xross.structures.XrossMethod): List<String> = method.args.filter { it.ty is XrossType.CancelToken }.map { it.name.toCamelCase().escapeKotlinKeyword() }
+
+    /**
+     * Binds each `CancelToken` argument of [method] to a cancellation flag under its own name.
+     * A sync call opens an `XrossCancellation.interruptible` block per token; returns the number of blocks to close.
+     * A blocking call gets a flag that `XrossAsync.awaitFuture` sets when the coroutine is cancelled, as the job
+     * keeps running once started. An async call passes a null token, as cancelling the coroutine already drops the future.
+     */
+    fun beginCancellationScopes(method: org.xross.structures.XrossMethod, body: CodeBlock.Builder, basePackage: String): Int {
+        val cancellation = ClassName("$basePackage.xross.runtime", "XrossCancellation")
+        cancelTokenNames(method).forEach { name ->
+            when {
+                method.isAsync && method.handleMode is HandleMode.Blocking -> body.addStatement("val %L = %T.flag()", name, cancellation)
+                method.isAsync -> body.addStatement("val %L = %T.NULL", name, MEMORY_SEGMENT)
+                else -> body.beginControlFlow("%T.interruptible { %L ->", cancellation, name)
+            }
+        }
+        return if (method.isAsync) 0 else cancelTokenNames(method).size
+    }
+
     /**
      * Prepares arguments and optionally an Arena if needed by the arguments.
      * Returns the name of the arena to use.
@@ -500,7 +570,7 @@ object GeneratorUtils {
             is XrossType.U32 -> " as Int).toUInt()"
             is XrossType.U64 -> " as Long).toULong()"
             is XrossType.USize -> {
-                if (ValueLayout.ADDRESS.byteSize() == 8L) " as Long).toULong()" else " as Int).toUInt()"
+                if (XrossGenerator.property.pointerWidth == 64) " as Long).toULong()" else " as Int).toUInt()"
             }
 
             else -> ""
@@ -516,7 +586,7 @@ object GeneratorUtils {
             is XrossType.U32 -> ".toInt()"
             is XrossType.U64 -> ".toLong()"
             is XrossType.USize -> getSignedConverter(
-                if (ValueLayout.ADDRESS.byteSize() == 8L) XrossType.U64 else XrossType.U32,
+                if (XrossGenerator.property.pointerWidth == 64) XrossType.U64 else XrossType.U32,
             )
 
             else -> ""
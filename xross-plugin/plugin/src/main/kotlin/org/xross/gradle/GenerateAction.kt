@@ -32,6 +32,7 @@ abstract class GenerateAction : WorkAction<GenerateParameters> {
         val outputBaseDir = parameters.outputDir.get().asFile
         val resolver = TypeResolver(parameters.metadataDir.get())
         XrossGenerator.property.useUnsignedTypes = parameters.useUnsignedTypes.get()
+        parameters.pointerWidth.orNull?.let { XrossGenerator.property.pointerWidth = it }
         XrossGenerator.generate(
             meta,
             outputBaseDir, // ここで掘り進めない
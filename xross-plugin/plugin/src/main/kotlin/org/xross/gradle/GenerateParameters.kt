@@ -11,4 +11,5 @@ interface GenerateParameters : WorkParameters {
     val packageName: Property<String>
     val metadataDir: Property<File>
     val useUnsignedTypes: Property<Boolean>
+    val pointerWidth: Property<Int>
 }
@@ -1,6 +1,7 @@
 package org.xross.gradle
 
 import org.gradle.api.DefaultTask
+import org.gradle.api.GradleException
 import org.gradle.api.file.DirectoryProperty
 import org.gradle.api.provider.Property
 import org.gradle.api.tasks.Input
@@ -9,6 +10,7 @@ import org.gradle.api.tasks.Optional
 import org.gradle.api.tasks.OutputDirectory
 import org.gradle.api.tasks.TaskAction
 import org.gradle.workers.WorkerExecutor
+import org.xross.generator.TypeResolver
 import javax.inject.Inject
 
 // --- 並列実行タスク ---
@@ -31,12 +33,17 @@ constructor(
     @get:Optional
     abstract val useUnsignedTypes: Property<Boolean>
 
+    @get:Input
+    @get:Optional
+    abstract val pointerWidth: Property<Int>
+
     @TaskAction
     fun execute() {
         val outDir = outputDir.get().asFile
         outDir.deleteRecursively()
         outDir.mkdirs()
-        val jsonFiles = metadataDir.asFileTree.files.filter { it.extension == "json" }
+        checkFormatVersion()
+        val jsonFiles = metadataDir.orNull?.asFile?.let(TypeResolver::definitionFiles).orEmpty()
         val queue = workerExecutor.noIsolation() // プロセス分離が必要なら classLoaderIsolation()
         jsonFiles.forEach { file ->
             queue.submit(GenerateAction::class.java) { params ->
@@ -45,8 +52,29 @@ constructor(
                 params.packageName.set(packageName)
                 params.metadataDir.set(metadataDir.get().asFile)
                 params.useUnsignedTypes.set(useUnsignedTypes.get())
+                params.pointerWidth.set(pointerWidth)
             }
         }
         queue.await()
     }
+
+    // 各クレートの session.manifest に記録されたフォーマットを確認する
+    private fun checkFormatVersion() {
+        metadataDir.asFileTree.files.filter { it.name == "session.manifest" }.forEach { manifest ->
+            val format =
+                manifest.readLines().firstOrNull { it.startsWith("format ") }?.removePrefix("format ")
+                    ?: return@forEach
+            val found = format.trim().toIntOrNull()
+            if (found == null || found > SUPPORTED_FORMAT_VERSION) {
+                throw GradleException(
+                    "${manifest.path} uses metadata format $format, but this plugin supports " +
+                        "format $SUPPORTED_FORMAT_VERSION. Update the xross plugin.",
+                )
+            }
+        }
+    }
+
+    companion object {
+        const val SUPPORTED_FORMAT_VERSION = 3
+    }
 }
@@ -35,6 +35,12 @@ abstract class XrossExtension {
      */
     var useUnsignedTypes: Boolean = false
 
+    /**
+     * Pointer width in bits of the target the bindings are generated for (32 or 64).
+     * Decides how `usize`/`isize` are mapped. Defaults to the JVM running the build.
+     */
+    var pointerWidth: Int? = null
+
     /**
      * Automatically set sources
      */
@@ -25,6 +25,7 @@ constructor() : Plugin<Project> {
                 task.outputDir.set(outputDir)
                 task.packageName.set(extension.packageName)
                 task.useUnsignedTypes.set(extension.useUnsignedTypes)
+                task.pointerWidth.set(extension.pointerWidth)
             }
 
         project.afterEvaluate {
@@ -26,4 +26,9 @@ sealed class HandleMode {
     @Serializable
     @SerialName("panicable")
     data object Panicable : HandleMode()
+
+    /** Synchronous body offloaded to a native worker pool. Invoked through the same XrossTask contract as async functions. */
+    @Serializable
+    @SerialName("blocking")
+    data object Blocking : HandleMode()
 }
@@ -34,6 +34,8 @@ sealed class XrossDefinition {
         override val methods: List<XrossMethod> = emptyList(),
         override val docs: List<String> = emptyList(),
         override val isCopy: Boolean = false,
+        /** Passed and returned as its C layout instead of a handle. */
+        val byValue: Boolean = false,
     ) : XrossDefinition()
 
     /**
@@ -15,8 +15,21 @@ data class XrossMethod(
     val isDefault: Boolean = false,
     val isRaw: Boolean = false,
     val isAsync: Boolean = false,
+    val concurrency: ConcurrencyLimit? = null,
+    val timeoutMs: Long? = null,
+    val contiguous: Boolean = false,
     val args: List<XrossField>,
     val ret: XrossType,
     val safety: XrossThreadSafety,
     val docs: List<String> = emptyList(),
 )
+
+/**
+ * Concurrency limit of an async export. Calls beyond [maxConcurrency] + [queue] are rejected by Rust,
+ * and generated callers throw `XrossRejectedException`.
+ */
+@Serializable
+data class ConcurrencyLimit(
+    val maxConcurrency: Int,
+    val queue: Int? = null,
+)
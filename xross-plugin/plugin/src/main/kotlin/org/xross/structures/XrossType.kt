@@ -3,6 +3,7 @@ package org.xross.structures
 import com.squareup.kotlinpoet.*
 import com.squareup.kotlinpoet.ParameterizedTypeName.Companion.parameterizedBy
 import kotlinx.serialization.Serializable
+import org.xross.generator.HandleResolver
 import org.xross.generator.XrossGenerator
 import org.xross.generator.util.FFMConstants
 import org.xross.generator.util.FFMConstants.MEMORY_SEGMENT
@@ -29,6 +30,12 @@ sealed class XrossType {
     object Pointer : XrossType()
     object RustString : XrossType()
 
+    /**
+     * A cooperative cancellation flag (`XrossCancelToken`), passed as a pointer to one byte.
+     * It is not part of the Kotlin signature: the generated call sets it when the calling thread is interrupted.
+     */
+    object CancelToken : XrossType()
+
     /**
      * A slice of values (&[*]).
      */
@@ -46,8 +53,17 @@ sealed class XrossType {
 
     /**
      * A user-defined object type.
+     * [crateName] and [symbolPrefix] are set when the type's owning crate is known,
+     * which may differ from the crate that references it.
+     * [byValue] is set by the generator for a direct argument or return of a by-value struct.
      */
-    data class Object(val signature: String, val ownership: Ownership = Ownership.Owned) : XrossType()
+    data class Object(
+        val signature: String,
+        val ownership: Ownership = Ownership.Owned,
+        val crateName: String? = null,
+        val symbolPrefix: String? = null,
+        val byValue: Boolean = false,
+    ) : XrossType()
 
     /**
      * An optional type.
@@ -64,6 +80,17 @@ sealed class XrossType {
      */
     data class Async(val inner: XrossType) : XrossType()
 
+    /**
+     * A pull-based synchronous sequence (`impl Iterator<Item = T>`), returned as an `XrossIterator` handle.
+     */
+    data class Iterator(val inner: XrossType) : XrossType()
+
+    /**
+     * A pull-based asynchronous sequence (`impl Stream<Item = T>` or `mpsc::Receiver<T>`),
+     * returned as an `XrossStream` handle.
+     */
+    data class Stream(val inner: XrossType) : XrossType()
+
     /**
      * Returns the KotlinPoet [TypeName] for this type.
      */
@@ -73,12 +100,12 @@ sealed class XrossType {
             U32 -> if (XrossGenerator.property.useUnsignedTypes) U_INT else INT
             I64 -> LONG
             U64 -> if (XrossGenerator.property.useUnsignedTypes) U_LONG else LONG
-            ISize -> if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) INT else LONG
+            ISize -> if (XrossGenerator.property.pointerWidth <= 32) INT else LONG
             USize -> {
                 if (XrossGenerator.property.useUnsignedTypes) {
-                    if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) U_INT else U_LONG
+                    if (XrossGenerator.property.pointerWidth <= 32) U_INT else U_LONG
                 } else {
-                    if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) INT else LONG
+                    if (XrossGenerator.property.pointerWidth <= 32) INT else LONG
                 }
             }
             F32 -> FLOAT
@@ -104,13 +131,13 @@ sealed class XrossType {
                 U16 -> if (XrossGenerator.property.useUnsignedTypes) U_SHORT_ARRAY else SHORT_ARRAY
                 Bool -> BOOLEAN_ARRAY
                 ISize ->
-                    if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) INT_ARRAY else LONG_ARRAY
+                    if (XrossGenerator.property.pointerWidth <= 32) INT_ARRAY else LONG_ARRAY
 
                 USize ->
                     if (XrossGenerator.property.useUnsignedTypes) {
-                        if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) U_INT_ARRAY else U_LONG_ARRAY
+                        if (XrossGenerator.property.pointerWidth <= 32) U_INT_ARRAY else U_LONG_ARRAY
                     } else {
-                        if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) INT_ARRAY else LONG_ARRAY
+                        if (XrossGenerator.property.pointerWidth <= 32) INT_ARRAY else LONG_ARRAY
                     }
 
                 else -> LIST.parameterizedBy(inner.kotlinType)
@@ -129,13 +156,13 @@ sealed class XrossType {
                 U16 -> if (XrossGenerator.property.useUnsignedTypes) U_SHORT_ARRAY else SHORT_ARRAY
                 Bool -> BOOLEAN_ARRAY
                 ISize ->
-                    if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) INT_ARRAY else LONG_ARRAY
+                    if (XrossGenerator.property.pointerWidth <= 32) INT_ARRAY else LONG_ARRAY
 
                 USize ->
                     if (XrossGenerator.property.useUnsignedTypes) {
-                        if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) U_INT_ARRAY else U_LONG_ARRAY
+                        if (XrossGenerator.property.pointerWidth <= 32) U_INT_ARRAY else U_LONG_ARRAY
                     } else {
-                        if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) INT_ARRAY else LONG_ARRAY
+                        if (XrossGenerator.property.pointerWidth <= 32) INT_ARRAY else LONG_ARRAY
                     }
 
                 else -> LIST.parameterizedBy(inner.kotlinType)
@@ -144,7 +171,9 @@ sealed class XrossType {
             is Optional -> inner.kotlinType.copy(nullable = true)
             is Result -> ok.kotlinType
             is Async -> inner.kotlinType
-            Pointer, is Object -> MEMORY_SEGMENT
+            is Iterator -> SEQUENCE.parameterizedBy(inner.kotlinType)
+            is Stream -> FLOW.parameterizedBy(inner.kotlinType)
+            Pointer, CancelToken, is Object -> MEMORY_SEGMENT
         }
     val viewClassName: String?
         get() = when (this) {
@@ -153,7 +182,7 @@ sealed class XrossType {
             F32 -> "XrossFloatArrayView"
             I64, U64 -> "XrossLongArrayView"
             ISize, USize ->
-                if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) "XrossIntArrayView" else "XrossLongArrayView"
+                if (XrossGenerator.property.pointerWidth <= 32) "XrossIntArrayView" else "XrossLongArrayView"
 
             I8, U8 -> "XrossByteArrayView"
             I16, U16 -> "XrossShortArrayView"
@@ -161,6 +190,34 @@ sealed class XrossType {
             else -> null
         }
 
+    /**
+     * Native function releasing an `XrossBuffer` that holds this type, as in
+     * `XrossType::buffer_release_symbol`. Each element width has its own function.
+     */
+    val bufferReleaseSymbol: String?
+        get() {
+            val elem = when (this) {
+                RustString -> return "xross_free_buffer"
+                is Vec -> this.inner
+                is Slice -> this.inner
+                else -> return null
+            }
+            return when (elem) {
+                Bool, I8, U8 -> "xross_free_buffer"
+                I16, U16 -> "xross_free_buffer_16"
+                I32, U32, F32 -> "xross_free_buffer_32"
+                I64, U64, F64 -> "xross_free_buffer_64"
+                ISize, USize, Pointer, is Object -> "xross_free_buffer_ptr"
+                else -> null
+            }
+        }
+
+    /**
+     * Companion property holding the handle of [bufferReleaseSymbol].
+     */
+    val bufferReleaseHandle: String
+        get() = HandleResolver.BUFFER_RELEASE_HANDLES.getValue(bufferReleaseSymbol ?: "xross_free_buffer")
+
     /**
      * Returns the [MemberName] for the Java FFM ValueLayout of this type.
      */
@@ -168,7 +225,7 @@ sealed class XrossType {
         get() = when (this) {
             I32, U32 -> FFMConstants.JAVA_INT
             I64, U64 -> FFMConstants.JAVA_LONG
-            ISize, USize -> if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) FFMConstants.JAVA_INT else FFMConstants.JAVA_LONG
+            ISize, USize -> if (XrossGenerator.property.pointerWidth <= 32) FFMConstants.JAVA_INT else FFMConstants.JAVA_LONG
             F32 -> FFMConstants.JAVA_FLOAT
             F64 -> FFMConstants.JAVA_DOUBLE
             Bool -> FFMConstants.JAVA_BYTE
@@ -190,6 +247,7 @@ sealed class XrossType {
             is Result -> FFMConstants.XROSS_RESULT_LAYOUT_CODE
             is RustString -> FFMConstants.XROSS_STRING_LAYOUT_CODE
             is Async -> FFMConstants.XROSS_TASK_LAYOUT_CODE
+            is Iterator, is Stream -> FFMConstants.XROSS_SEQUENCE_LAYOUT_CODE
             is Vec, is Slice -> CodeBlock.of("%M", FFMConstants.ADDRESS)
             else -> CodeBlock.of("%M", layoutMember)
         }
@@ -213,7 +271,22 @@ sealed class XrossType {
             else -> false
         }
 
-    val isComplex: Boolean get() = this is Object || this is Optional || this is Result || this is RustString || this is Async || this is Slice || this is Vec
+    /**
+     * Returns true for `Iterator` and `Stream`, which are returned as a pull-based handle.
+     */
+    val isSequence: Boolean get() = this is Iterator || this is Stream
+
+    /**
+     * Returns true for the scalars whose `Some` is a box of the value, released with `xross_free_scalar`.
+     * As arguments, they are passed as a pointer to the value.
+     */
+    val isBoxedInOption: Boolean
+        get() = when (this) {
+            Bool, I8, U8, I16, U16, I32, U32, I64, U64, ISize, USize, F32, F64 -> true
+            else -> false
+        }
+
+    val isComplex: Boolean get() = this is Object || this is Optional || this is Result || this is RustString || this is Async || this is Slice || this is Vec || isSequence
     val isPrimitive: Boolean get() = !isComplex
 
     /**
@@ -223,7 +296,7 @@ sealed class XrossType {
         get() = when (this) {
             is I32, is U32, is F32 -> 4L
             is I64, is U64, is F64, is Pointer, is RustString -> 8L
-            is ISize, is USize -> if (java.lang.foreign.ValueLayout.ADDRESS.byteSize() <= 4L) 4L else 8L
+            is ISize, is USize -> if (XrossGenerator.property.pointerWidth <= 32) 4L else 8L
             is Result -> 16L
             is Async -> 24L
             is Slice, is Vec -> 16L
@@ -235,6 +308,8 @@ sealed class XrossType {
         }
 }
 
+private val SEQUENCE = ClassName("kotlin.sequences", "Sequence")
+private val FLOW = ClassName("kotlinx.coroutines.flow", "Flow")
 private val U_BYTE = ClassName("kotlin", "UByte")
 private val U_SHORT = ClassName("kotlin", "UShort")
 private val U_INT = ClassName("kotlin", "UInt")
@@ -32,6 +32,7 @@ object XrossTypeSerializer : KSerializer<XrossType> {
         "F64" to XrossType.F64,
         "Pointer" to XrossType.Pointer,
         "String" to XrossType.RustString,
+        "CancelToken" to XrossType.CancelToken,
     )
 
     override fun deserialize(decoder: Decoder): XrossType {
@@ -52,7 +53,9 @@ object XrossTypeSerializer : KSerializer<XrossType> {
                         val signature = obj["signature"]?.jsonPrimitive?.content ?: ""
                         val ownershipStr = obj["ownership"]?.jsonPrimitive?.content ?: "Owned"
                         val ownership = XrossType.Ownership.valueOf(ownershipStr)
-                        XrossType.Object(signature, ownership)
+                        val crateName = obj["crate_name"]?.jsonPrimitive?.content
+                        val symbolPrefix = obj["symbol_prefix"]?.jsonPrimitive?.content
+                        XrossType.Object(signature, ownership, crateName, symbolPrefix)
                     }
                     "Option" -> XrossType.Optional(deserializeRecursive(body))
                     "Slice" -> XrossType.Slice(deserializeRecursive(body))
@@ -65,6 +68,8 @@ object XrossTypeSerializer : KSerializer<XrossType> {
                         )
                     }
                     "Async" -> XrossType.Async(deserializeRecursive(body))
+                    "Stream" -> XrossType.Stream(deserializeRecursive(body))
+                    "Iterator" -> XrossType.Iterator(deserializeRecursive(body))
                     else -> throw IllegalArgumentException("Unknown complex type: $typeKey")
                 }
             }
@@ -82,6 +87,8 @@ object XrossTypeSerializer : KSerializer<XrossType> {
                 putJsonObject("Object") {
                     put("signature", value.signature)
                     put("ownership", value.ownership.name)
+                    value.crateName?.let { put("crate_name", it) }
+                    value.symbolPrefix?.let { put("symbol_prefix", it) }
                 }
             }
             is XrossType.Optional -> buildJsonObject { put("Option", serializeRecursive(value.inner)) }
@@ -94,6 +101,8 @@ object XrossTypeSerializer : KSerializer<XrossType> {
                 }
             }
             is XrossType.Async -> buildJsonObject { put("Async", serializeRecursive(value.inner)) }
+            is XrossType.Stream -> buildJsonObject { put("Stream", serializeRecursive(value.inner)) }
+            is XrossType.Iterator -> buildJsonObject { put("Iterator", serializeRecursive(value.inner)) }
             else -> {
                 val name = nameToPrimitive.entries.find { it.value == value }?.key
                     ?: throw IllegalArgumentException("Unknown type instance: $value")
@@ -0,0 +1,126 @@
+//! Test fixture of the ABI conformance suite in `tests/conformance.rs`: one echo export per
+//! type and handle mode. Every echo export returns its argument unchanged; the
+//! `probes_contiguous` exports return `contiguous` vectors of probes.
+use xross_core::{XrossClass, xross_function, xross_methods};
+
+#[derive(XrossClass, Clone, Debug, PartialEq)]
+#[xross_package("conformance")]
+#[xross(clonable)]
+pub struct Probe {
+    #[xross_field]
+    pub id: i32,
+}
+
+#[xross_methods]
+impl Probe {
+    #[xross_new]
+    pub fn new(id: i32) -> Self {
+        Self { id }
+    }
+}
+
+/// Passed by value: `#[repr(C)]`, `Copy` and only scalar fields. `flag` leaves padding
+/// before `id`, and `id` before `value`.
+#[derive(XrossClass, Clone, Copy, Debug, PartialEq)]
+#[xross_package("conformance")]
+#[xross(copy)]
+#[repr(C)]
+pub struct Sample {
+    #[xross_field]
+    pub flag: bool,
+    #[xross_field]
+    pub id: i32,
+    #[xross_field]
+    pub value: f64,
+}
+
+macro_rules! echo_exports {
+    ($($ty:ty => $plain:ident, $critical:ident, $panicable:ident $(, $blocking:ident, $async_:ident)?;)*) => {$(
+        #[xross_function(package = "conformance")]
+        pub fn $plain(v: $ty) -> $ty {
+            v
+        }
+
+        #[xross_function(package = "conformance", critical)]
+        pub fn $critical(v: $ty) -> $ty {
+            v
+        }
+
+        #[xross_function(package = "conformance", panicable)]
+        pub fn $panicable(v: $ty) -> $ty {
+            v
+        }
+
+        $(
+            #[xross_function(package = "conformance", blocking)]
+            pub fn $blocking(v: $ty) -> $ty {
+                v
+            }
+
+            #[xross_function(package = "conformance")]
+            pub async fn $async_(v: $ty) -> $ty {
+                v
+            }
+        )?
+    )*};
+}
+
+echo_exports! {
+    bool => echo_bool, echo_bool_critical, echo_bool_panicable, echo_bool_blocking, echo_bool_async;
+    i8 => echo_i8, echo_i8_critical, echo_i8_panicable, echo_i8_blocking, echo_i8_async;
+    u8 => echo_u8, echo_u8_critical, echo_u8_panicable, echo_u8_blocking, echo_u8_async;
+    i16 => echo_i16, echo_i16_critical, echo_i16_panicable, echo_i16_blocking, echo_i16_async;
+    u16 => echo_u16, echo_u16_critical, echo_u16_panicable, echo_u16_blocking, echo_u16_async;
+    i32 => echo_i32, echo_i32_critical, echo_i32_panicable, echo_i32_blocking, echo_i32_async;
+    u32 => echo_u32, echo_u32_critical, echo_u32_panicable, echo_u32_blocking, echo_u32_async;
+    i64 => echo_i64, echo_i64_critical, echo_i64_panicable, echo_i64_blocking, echo_i64_async;
+    u64 => echo_u64, echo_u64_critical, echo_u64_panicable, echo_u64_blocking, echo_u64_async;
+    isize => echo_isize, echo_isize_critical, echo_isize_panicable, echo_isize_blocking,
+        echo_isize_async;
+    usize => echo_usize, echo_usize_critical, echo_usize_panicable, echo_usize_blocking,
+        echo_usize_async;
+    f32 => echo_f32, echo_f32_critical, echo_f32_panicable, echo_f32_blocking, echo_f32_async;
+    f64 => echo_f64, echo_f64_critical, echo_f64_panicable, echo_f64_blocking, echo_f64_async;
+    String => echo_string, echo_string_critical, echo_string_panicable, echo_string_blocking,
+        echo_string_async;
+    Vec<i32> => echo_vec_i32, echo_vec_i32_critical, echo_vec_i32_panicable,
+        echo_vec_i32_blocking, echo_vec_i32_async;
+    Vec<f64> => echo_vec_f64, echo_vec_f64_critical, echo_vec_f64_panicable,
+        echo_vec_f64_blocking, echo_vec_f64_async;
+    Probe => echo_probe, echo_probe_critical, echo_probe_panicable, echo_probe_blocking,
+        echo_probe_async;
+    Sample => echo_sample, echo_sample_critical, echo_sample_panicable, echo_sample_blocking,
+        echo_sample_async;
+    Option<bool> => echo_option_bool, echo_option_bool_critical, echo_option_bool_panicable,
+        echo_option_bool_blocking, echo_option_bool_async;
+    Option<i32> => echo_option_i32, echo_option_i32_critical, echo_option_i32_panicable,
+        echo_option_i32_blocking, echo_option_i32_async;
+    Option<f64> => echo_option_f64, echo_option_f64_critical, echo_option_f64_panicable,
+        echo_option_f64_blocking, echo_option_f64_async;
+    Option<String> => echo_option_string, echo_option_string_critical,
+        echo_option_string_panicable, echo_option_string_blocking, echo_option_string_async;
+    Option<Probe> => echo_option_probe, echo_option_probe_critical, echo_option_probe_panicable,
+        echo_option_probe_blocking, echo_option_probe_async;
+    // A `Result` cannot complete an `XrossTask`.
+    Result<i32, String> => echo_result_i32, echo_result_i32_critical, echo_result_i32_panicable;
+    Result<String, String> => echo_result_string, echo_result_string_critical,
+        echo_result_string_panicable;
+    Result<Probe, String> => echo_result_probe, echo_result_probe_critical,
+        echo_result_probe_panicable;
+}
+
+/// Probes with ids `0..count`, in one allocation.
+#[xross_function(package = "conformance", contiguous)]
+pub fn probes_contiguous(count: i32) -> Vec<Probe> {
+    (0..count).map(Probe::new).collect()
+}
+
+#[xross_function(package = "conformance", contiguous, critical)]
+pub fn probes_contiguous_critical(count: i32) -> Vec<Probe> {
+    (0..count).map(Probe::new).collect()
+}
+
+#[xross_function(package = "conformance", contiguous, panicable)]
+pub fn probes_contiguous_panicable(count: i32) -> Vec<Probe> {
+    (0..count).map(Probe::new).collect()
+}
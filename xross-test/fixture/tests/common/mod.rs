@@ -0,0 +1,38 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use xross_test::XrossTestLibrary;
+
+/// Integration tests are not given the path of the package's cdylib, so it is built once with
+/// the profile and target directory of the running test and loaded from there.
+pub fn library() -> XrossTestLibrary {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    XrossTestLibrary::open(PATH.get_or_init(build_cdylib)).unwrap()
+}
+
+fn build_cdylib() -> PathBuf {
+    // `CARGO_TARGET_TMPDIR` is `<target-dir>/tmp`, and the test runs from `<target-dir>/<profile>/`.
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).parent().unwrap();
+    let exe = std::env::current_exe().unwrap();
+    let profile_dir = exe
+        .strip_prefix(target_dir)
+        .ok()
+        .and_then(|rel| rel.iter().next())
+        .and_then(|dir| dir.to_str())
+        .expect("the test executable is not in the target directory");
+    let profile = if profile_dir == "debug" { "dev" } else { profile_dir };
+
+    let status = std::process::Command::new(env!("CARGO"))
+        .args(["build", "-p", env!("CARGO_PKG_NAME"), "--profile", profile])
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "failed to build the cdylib");
+
+    target_dir.join(profile_dir).join(format!(
+        "{}{}{}",
+        std::env::consts::DLL_PREFIX,
+        env!("CARGO_PKG_NAME").replace('-', "_"),
+        std::env::consts::DLL_SUFFIX
+    ))
+}
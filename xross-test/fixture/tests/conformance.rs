@@ -0,0 +1,200 @@
+//! ABI conformance suite: every type of `xross_core::abi` is echoed through one export per
+//! handle mode (see `src/lib.rs`) and must come back unchanged.
+
+mod common;
+
+use common::library;
+use xross_metadata::XrossDefinition;
+use xross_test::{XrossStructValue, XrossTestLibrary, XrossValue};
+
+const ALL_MODES: &[&str] = &["", "_critical", "_panicable", "_blocking", "_async"];
+/// A `Result` cannot complete an `XrossTask`.
+const SYNC_MODES: &[&str] = &["", "_critical", "_panicable"];
+
+fn echo(lib: &XrossTestLibrary, name: &str, mode: &str, value: XrossValue) -> XrossValue {
+    let method = format!("echo_{}{}", name, mode);
+    lib.invoke(&format!("conformance.{}", method), &method, &[value])
+        .unwrap_or_else(|e| panic!("{}: {}", method, e))
+}
+
+fn some(value: XrossValue) -> XrossValue {
+    XrossValue::Option(Some(Box::new(value)))
+}
+
+fn ok(value: XrossValue) -> XrossValue {
+    XrossValue::Result(Ok(Box::new(value)))
+}
+
+fn err(message: &str) -> XrossValue {
+    XrossValue::Result(Err(Box::new(message.into())))
+}
+
+fn probe(lib: &XrossTestLibrary, id: i32) -> XrossValue {
+    lib.invoke("conformance.Probe", "new", &[id.into()]).unwrap()
+}
+
+/// Replaces every returned `Probe` by its `id` and drops it.
+fn resolve(lib: &XrossTestLibrary, value: XrossValue) -> XrossValue {
+    match value {
+        XrossValue::Object(obj) => {
+            let id = lib.get(&obj, "id").unwrap();
+            lib.drop(&obj).unwrap();
+            id
+        }
+        XrossValue::Option(opt) => XrossValue::Option(opt.map(|v| Box::new(resolve(lib, *v)))),
+        XrossValue::Result(res) => XrossValue::Result(match res {
+            Ok(v) => Ok(Box::new(resolve(lib, *v))),
+            Err(e) => Err(Box::new(resolve(lib, *e))),
+        }),
+        value => value,
+    }
+}
+
+fn assert_round_trip(lib: &XrossTestLibrary, name: &str, modes: &[&str], values: &[XrossValue]) {
+    for mode in modes {
+        for value in values {
+            assert_eq!(&echo(lib, name, mode, value.clone()), value, "echo_{}{}", name, mode);
+        }
+    }
+}
+
+#[test]
+fn test_scalars() {
+    let lib = library();
+    assert_round_trip(&lib, "bool", ALL_MODES, &[true.into(), false.into()]);
+    assert_round_trip(&lib, "i8", ALL_MODES, &[i8::MIN.into(), (-1i8).into(), i8::MAX.into()]);
+    assert_round_trip(&lib, "u8", ALL_MODES, &[0u8.into(), u8::MAX.into()]);
+    assert_round_trip(&lib, "i16", ALL_MODES, &[i16::MIN.into(), (-1i16).into(), i16::MAX.into()]);
+    assert_round_trip(&lib, "u16", ALL_MODES, &[0u16.into(), u16::MAX.into()]);
+    assert_round_trip(&lib, "i32", ALL_MODES, &[i32::MIN.into(), (-1i32).into(), i32::MAX.into()]);
+    assert_round_trip(&lib, "u32", ALL_MODES, &[0u32.into(), u32::MAX.into()]);
+    assert_round_trip(&lib, "i64", ALL_MODES, &[i64::MIN.into(), (-1i64).into(), i64::MAX.into()]);
+    assert_round_trip(&lib, "u64", ALL_MODES, &[0u64.into(), u64::MAX.into()]);
+    assert_round_trip(&lib, "isize", ALL_MODES, &[isize::MIN.into(), isize::MAX.into()]);
+    assert_round_trip(&lib, "usize", ALL_MODES, &[0usize.into(), usize::MAX.into()]);
+    assert_round_trip(&lib, "f32", ALL_MODES, &[0.0f32.into(), (-1.5f32).into(), f32::MAX.into()]);
+    assert_round_trip(
+        &lib,
+        "f64",
+        ALL_MODES,
+        &[0.0f64.into(), (-1.5f64).into(), f64::MIN_POSITIVE.into(), f64::INFINITY.into()],
+    );
+}
+
+#[test]
+fn test_strings_and_vectors() {
+    let lib = library();
+    // Latin-1 and UTF-16 views, and the empty string.
+    assert_round_trip(&lib, "string", ALL_MODES, &["".into(), "héllo".into(), "世界 🌍".into()]);
+    let ints = |v: &[i32]| XrossValue::Vec(v.iter().copied().map(XrossValue::I32).collect());
+    assert_round_trip(&lib, "vec_i32", ALL_MODES, &[ints(&[]), ints(&[i32::MIN, 0, 7])]);
+    let floats = XrossValue::Vec(vec![XrossValue::F64(-0.25), XrossValue::F64(1e300)]);
+    assert_round_trip(&lib, "vec_f64", ALL_MODES, &[floats]);
+}
+
+#[test]
+fn test_options_and_results() {
+    let lib = library();
+    let none = XrossValue::Option(None);
+    assert_round_trip(&lib, "option_bool", ALL_MODES, &[some(true.into()), none.clone()]);
+    assert_round_trip(&lib, "option_i32", ALL_MODES, &[some((-3i32).into()), none.clone()]);
+    assert_round_trip(&lib, "option_f64", ALL_MODES, &[some(2.5f64.into()), none.clone()]);
+    assert_round_trip(
+        &lib,
+        "option_string",
+        ALL_MODES,
+        &[some("héllo".into()), some("世界".into()), none.clone()],
+    );
+    assert_round_trip(&lib, "result_i32", SYNC_MODES, &[ok((-3i32).into()), err("bad")]);
+    assert_round_trip(&lib, "result_string", SYNC_MODES, &[ok("世界".into()), err("bad")]);
+}
+
+#[test]
+fn test_objects() {
+    let lib = library();
+    for mode in ALL_MODES {
+        // Owned arguments are moved out of the handle, so each call gets a fresh probe.
+        let out = echo(&lib, "probe", mode, probe(&lib, 7));
+        assert_eq!(resolve(&lib, out), XrossValue::I32(7));
+        let out = echo(&lib, "option_probe", mode, some(probe(&lib, 8)));
+        assert_eq!(resolve(&lib, out), some(XrossValue::I32(8)));
+        let out = echo(&lib, "option_probe", mode, XrossValue::Option(None));
+        assert_eq!(out, XrossValue::Option(None));
+    }
+    for mode in SYNC_MODES {
+        let out = echo(&lib, "result_probe", mode, ok(probe(&lib, 9)));
+        assert_eq!(resolve(&lib, out), ok(XrossValue::I32(9)));
+        assert_eq!(echo(&lib, "result_probe", mode, err("bad")), err("bad"));
+    }
+}
+
+#[test]
+fn test_by_value_structs() {
+    let lib = library();
+    let XrossDefinition::Struct(def) = lib.definition("conformance.Sample").unwrap() else {
+        panic!("Sample is a struct");
+    };
+    assert!(def.by_value);
+    assert_eq!(def.c_layout(usize::BITS).as_ref(), def.layout.as_ref());
+
+    let sample = XrossValue::Struct(XrossStructValue {
+        signature: "conformance.Sample".into(),
+        fields: vec![
+            ("flag".into(), true.into()),
+            ("id".into(), (-7i32).into()),
+            ("value".into(), 2.5f64.into()),
+        ],
+    });
+    for mode in ALL_MODES {
+        match echo(&lib, "sample", mode, sample.clone()) {
+            // Panicable exports and tasks box the struct like any other object.
+            XrossValue::Object(obj) => {
+                for (name, value) in &sample.as_struct().unwrap().fields {
+                    assert_eq!(&lib.get(&obj, name).unwrap(), value, "echo_sample{}", mode);
+                }
+                // A handle is accepted where the struct is expected; the struct is copied.
+                let copied = echo(&lib, "sample", "", XrossValue::Object(obj.clone()));
+                assert_eq!(copied, sample, "echo_sample{}", mode);
+                lib.drop(&obj).unwrap();
+            }
+            out => assert_eq!(out, sample, "echo_sample{}", mode),
+        }
+    }
+}
+
+#[test]
+fn test_contiguous_objects() {
+    let lib = library();
+    let stride = lib.definition("conformance.Probe").unwrap().layout().unwrap().size as usize;
+    for mode in SYNC_MODES {
+        let method = format!("probes_contiguous{}", mode);
+        let signature = format!("conformance.{}", method);
+        let out = lib.invoke(&signature, &method, &[3i32.into()]).unwrap();
+        let collection = out.as_collection().unwrap();
+        let ids: Vec<XrossValue> =
+            collection.items.iter().map(|item| lib.get(item, "id").unwrap()).collect();
+        assert_eq!(ids, (0..3).map(XrossValue::I32).collect::<Vec<_>>(), "{}", method);
+        let base = collection.items[0].ptr as usize;
+        for (i, item) in collection.items.iter().enumerate() {
+            assert_eq!(item.ptr as usize, base + i * stride, "{}", method);
+        }
+        lib.drop_collection(collection).unwrap();
+
+        let empty = lib.invoke(&signature, &method, &[0i32.into()]).unwrap();
+        assert!(empty.as_collection().unwrap().items.is_empty());
+        lib.drop_collection(empty.as_collection().unwrap()).unwrap();
+    }
+}
+
+/// Values whose encoding is all zero bits still come back as themselves, from options and
+/// from tasks alike.
+#[test]
+fn test_zero_values() {
+    let lib = library();
+    assert_round_trip(&lib, "option_bool", ALL_MODES, &[some(false.into())]);
+    assert_round_trip(&lib, "option_i32", ALL_MODES, &[some(0i32.into())]);
+    assert_round_trip(&lib, "option_f64", ALL_MODES, &[some(0.0f64.into())]);
+    assert_round_trip(&lib, "i32", ALL_MODES, &[0i32.into()]);
+    assert_round_trip(&lib, "bool", ALL_MODES, &[false.into()]);
+    assert_round_trip(&lib, "result_i32", SYNC_MODES, &[ok(0i32.into())]);
+}
@@ -0,0 +1,632 @@
+use crate::value::{XrossObject, XrossStructValue, XrossValue};
+use libffi::middle::{Arg, Cif, CodePtr, Type};
+use std::any::Any;
+use std::ffi::c_void;
+use xross_core::{XrossBuffer, XrossResult, XrossStringView};
+use xross_metadata::{XrossStruct, XrossType};
+
+/// Storage for one native argument.
+enum Slot {
+    Bool(bool),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    ISize(isize),
+    USize(usize),
+    F32(f32),
+    F64(f64),
+    Ptr(*const c_void),
+    Result(XrossResult),
+    /// A by-value struct laid out in `u64` words, which keep every field aligned.
+    Struct(Vec<u64>),
+}
+
+impl Slot {
+    fn arg(&self) -> Arg {
+        match self {
+            Slot::Bool(v) => Arg::new(v),
+            Slot::I8(v) => Arg::new(v),
+            Slot::U8(v) => Arg::new(v),
+            Slot::I16(v) => Arg::new(v),
+            Slot::U16(v) => Arg::new(v),
+            Slot::I32(v) => Arg::new(v),
+            Slot::U32(v) => Arg::new(v),
+            Slot::I64(v) => Arg::new(v),
+            Slot::U64(v) => Arg::new(v),
+            Slot::ISize(v) => Arg::new(v),
+            Slot::USize(v) => Arg::new(v),
+            Slot::F32(v) => Arg::new(v),
+            Slot::F64(v) => Arg::new(v),
+            Slot::Ptr(v) => Arg::new(v),
+            Slot::Result(v) => Arg::new(v),
+            Slot::Struct(v) => Arg::new(&v[0]),
+        }
+    }
+
+    /// Native bytes of a scalar.
+    fn to_ne_bytes(&self) -> Vec<u8> {
+        match *self {
+            Slot::Bool(v) => vec![v as u8],
+            Slot::I8(v) => v.to_ne_bytes().to_vec(),
+            Slot::U8(v) => v.to_ne_bytes().to_vec(),
+            Slot::I16(v) => v.to_ne_bytes().to_vec(),
+            Slot::U16(v) => v.to_ne_bytes().to_vec(),
+            Slot::I32(v) => v.to_ne_bytes().to_vec(),
+            Slot::U32(v) => v.to_ne_bytes().to_vec(),
+            Slot::I64(v) => v.to_ne_bytes().to_vec(),
+            Slot::U64(v) => v.to_ne_bytes().to_vec(),
+            Slot::ISize(v) => v.to_ne_bytes().to_vec(),
+            Slot::USize(v) => v.to_ne_bytes().to_vec(),
+            Slot::F32(v) => v.to_ne_bytes().to_vec(),
+            Slot::F64(v) => v.to_ne_bytes().to_vec(),
+            Slot::Ptr(v) => (v as usize).to_ne_bytes().to_vec(),
+            Slot::Result(_) | Slot::Struct(_) => unreachable!("not a scalar"),
+        }
+    }
+}
+
+pub(crate) fn result_type() -> Type {
+    Type::structure([Type::u8(), Type::pointer()])
+}
+
+pub(crate) fn buffer_type() -> Type {
+    Type::structure([Type::usize(), Type::usize(), Type::pointer()])
+}
+
+/// C type of a by-value struct: its scalar fields in declaration order.
+fn struct_type(def: &XrossStruct) -> Type {
+    Type::structure(
+        def.fields.iter().map(|f| scalar_type(&f.ty).expect("by-value fields are scalars")),
+    )
+}
+
+/// C type of a value passed as is. `None` for everything that is flattened or boxed.
+pub(crate) fn scalar_type(ty: &XrossType) -> Option<Type> {
+    Some(match ty {
+        XrossType::Bool | XrossType::U8 => Type::u8(),
+        XrossType::I8 => Type::i8(),
+        XrossType::I16 => Type::i16(),
+        XrossType::U16 => Type::u16(),
+        XrossType::I32 => Type::i32(),
+        XrossType::U32 => Type::u32(),
+        XrossType::I64 => Type::i64(),
+        XrossType::U64 => Type::u64(),
+        XrossType::ISize => Type::isize(),
+        XrossType::USize => Type::usize(),
+        XrossType::F32 => Type::f32(),
+        XrossType::F64 => Type::f64(),
+        XrossType::Pointer => Type::pointer(),
+        _ => return None,
+    })
+}
+
+fn scalar_size(ty: &XrossType) -> Option<usize> {
+    Some(match ty {
+        XrossType::Bool | XrossType::I8 | XrossType::U8 => 1,
+        XrossType::I16 | XrossType::U16 => 2,
+        XrossType::I32 | XrossType::U32 | XrossType::F32 => 4,
+        XrossType::I64 | XrossType::U64 | XrossType::F64 => 8,
+        XrossType::ISize | XrossType::USize | XrossType::Pointer => size_of::<usize>(),
+        XrossType::Object { .. } => size_of::<*mut c_void>(),
+        _ => return None,
+    })
+}
+
+fn mismatch(ty: &XrossType, value: &XrossValue) -> String {
+    format!("expected a value of type {:?}, got {:?}", ty, value)
+}
+
+/// Arguments of one native call, encoded the way the JVM bindings pass them.
+/// Owns everything the arguments point to until the call returns.
+#[derive(Default)]
+pub(crate) struct NativeArgs {
+    types: Vec<Type>,
+    slots: Vec<Slot>,
+    keep: Vec<Box<dyn Any>>,
+}
+
+impl NativeArgs {
+    fn push(&mut self, ty: Type, slot: Slot) {
+        self.types.push(ty);
+        self.slots.push(slot);
+    }
+
+    pub(crate) fn push_ptr(&mut self, ptr: *const c_void) {
+        self.push(Type::pointer(), Slot::Ptr(ptr));
+    }
+
+    /// Inserts the `out` pointer, which precedes every other argument.
+    pub(crate) fn prepend_ptr(&mut self, ptr: *const c_void) {
+        self.types.insert(0, Type::pointer());
+        self.slots.insert(0, Slot::Ptr(ptr));
+    }
+
+    fn scalar_slot(ty: &XrossType, value: &XrossValue) -> Result<Slot, String> {
+        Ok(match (ty, value) {
+            (XrossType::Bool, XrossValue::Bool(v)) => Slot::Bool(*v),
+            (XrossType::I8, XrossValue::I8(v)) => Slot::I8(*v),
+            (XrossType::U8, XrossValue::U8(v)) => Slot::U8(*v),
+            (XrossType::I16, XrossValue::I16(v)) => Slot::I16(*v),
+            (XrossType::U16, XrossValue::U16(v)) => Slot::U16(*v),
+            (XrossType::I32, XrossValue::I32(v)) => Slot::I32(*v),
+            (XrossType::U32, XrossValue::U32(v)) => Slot::U32(*v),
+            (XrossType::I64, XrossValue::I64(v)) => Slot::I64(*v),
+            (XrossType::U64, XrossValue::U64(v)) => Slot::U64(*v),
+            (XrossType::ISize, XrossValue::ISize(v)) => Slot::ISize(*v),
+            (XrossType::USize, XrossValue::USize(v)) => Slot::USize(*v),
+            (XrossType::F32, XrossValue::F32(v)) => Slot::F32(*v),
+            (XrossType::F64, XrossValue::F64(v)) => Slot::F64(*v),
+            (XrossType::Pointer, XrossValue::Pointer(v)) => Slot::Ptr(*v),
+            _ => return Err(mismatch(ty, value)),
+        })
+    }
+
+    /// Pushes a value of a type passed as is (integers, floats, bool and raw pointers).
+    pub(crate) fn push_scalar(&mut self, ty: &XrossType, value: &XrossValue) -> Result<(), String> {
+        let c_ty = scalar_type(ty).ok_or_else(|| format!("{:?} is not a scalar type", ty))?;
+        let slot = Self::scalar_slot(ty, value)?;
+        self.push(c_ty, slot);
+        Ok(())
+    }
+
+    /// Pushes one argument, flattened as specified in `xross_core::abi`.
+    pub(crate) fn push_arg(&mut self, ty: &XrossType, value: &XrossValue) -> Result<(), String> {
+        match (ty, value) {
+            (XrossType::String, XrossValue::String(s)) => {
+                let (ptr, len, enc) = self.keep_string_view(s);
+                self.push_ptr(ptr);
+                self.push(Type::usize(), Slot::USize(len));
+                self.push(Type::u8(), Slot::U8(enc));
+            }
+            (XrossType::Slice(inner) | XrossType::Vec(inner), XrossValue::Vec(items)) => {
+                let ptr = self.keep_elements(inner, items)?;
+                self.push_ptr(ptr);
+                self.push(Type::usize(), Slot::USize(items.len()));
+            }
+            (XrossType::Object { .. }, XrossValue::Object(obj)) => self.push_ptr(obj.ptr),
+            (XrossType::Option(inner), XrossValue::Option(opt)) => {
+                let ptr = match opt {
+                    Some(v) => self.value_to_ptr(inner, v)?,
+                    None => std::ptr::null(),
+                };
+                self.push_ptr(ptr);
+            }
+            (XrossType::Result { ok, .. }, XrossValue::Result(res)) => {
+                let res = match res {
+                    Ok(v) => XrossResult { is_ok: true, ptr: self.value_to_ptr(ok, v)? as *mut _ },
+                    // Error arguments are always read as strings.
+                    Err(v) => XrossResult {
+                        is_ok: false,
+                        ptr: self.value_to_ptr(&XrossType::String, v)? as *mut _,
+                    },
+                };
+                self.push(result_type(), Slot::Result(res));
+            }
+            (XrossType::CancelToken, XrossValue::CancelToken(flag)) => {
+                let ptr = match flag {
+                    Some(flag) => {
+                        self.keep.push(Box::new(flag.clone()));
+                        std::sync::Arc::as_ptr(&flag.0) as *const c_void
+                    }
+                    None => std::ptr::null(),
+                };
+                self.push_ptr(ptr);
+            }
+            (ty, value) if scalar_type(ty).is_some() => self.push_scalar(ty, value)?,
+            (ty, value) => return Err(mismatch(ty, value)),
+        }
+        Ok(())
+    }
+
+    /// Pushes an owned by-value struct as its C layout: the fields of a `Struct` value, or a
+    /// copy of the struct behind an `Object` handle.
+    pub(crate) fn push_struct(
+        &mut self,
+        def: &XrossStruct,
+        value: &XrossValue,
+    ) -> Result<(), String> {
+        let layout = def.c_layout(usize::BITS).expect("not a by-value struct");
+        let mut words = vec![0u64; (layout.size as usize).div_ceil(8)];
+        let base = words.as_mut_ptr() as *mut u8;
+        match value {
+            XrossValue::Struct(s) if s.signature == def.signature => {
+                for (field, at) in def.fields.iter().zip(&layout.fields) {
+                    let v =
+                        s.field(&field.name).ok_or_else(|| format!("missing {}", field.name))?;
+                    let bytes = Self::scalar_slot(&field.ty, v)?.to_ne_bytes();
+                    let dst = unsafe { base.add(at.offset as usize) };
+                    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), dst, bytes.len()) };
+                }
+            }
+            XrossValue::Object(obj) if obj.signature == def.signature => unsafe {
+                std::ptr::copy_nonoverlapping(obj.ptr as *const u8, base, layout.size as usize)
+            },
+            _ => return Err(format!("expected a {} value, got {:?}", def.signature, value)),
+        }
+        self.push(struct_type(def), Slot::Struct(words));
+        Ok(())
+    }
+
+    /// Encodes a string like the JVM's compact strings: Latin-1 when every character fits,
+    /// UTF-16 otherwise. Returns `(ptr, len, encoding)`, `len` counting code units.
+    fn keep_string_view(&mut self, s: &str) -> (*const c_void, usize, u8) {
+        if s.chars().all(|c| (c as u32) <= 0xFF) {
+            let bytes: Vec<u8> = s.chars().map(|c| c as u8).collect();
+            let view = (bytes.as_ptr() as *const c_void, bytes.len(), 0);
+            self.keep.push(Box::new(bytes));
+            view
+        } else {
+            let units: Vec<u16> = s.encode_utf16().collect();
+            let view = (units.as_ptr() as *const c_void, units.len(), 1);
+            self.keep.push(Box::new(units));
+            view
+        }
+    }
+
+    /// Lays out the elements of a slice argument. Returns null for an empty slice.
+    fn keep_elements(
+        &mut self,
+        inner: &XrossType,
+        items: &[XrossValue],
+    ) -> Result<*const c_void, String> {
+        if items.is_empty() {
+            return Ok(std::ptr::null());
+        }
+        let size =
+            scalar_size(inner).ok_or_else(|| format!("slices of {:?} are not supported", inner))?;
+        // u64 words keep every element type aligned.
+        let mut words = vec![0u64; (size * items.len()).div_ceil(8)];
+        let base = words.as_mut_ptr() as *mut u8;
+        for (i, item) in items.iter().enumerate() {
+            let dst = unsafe { base.add(i * size) };
+            let bytes = match (inner, item) {
+                (XrossType::Object { .. }, XrossValue::Object(obj)) => {
+                    (obj.ptr as usize).to_ne_bytes().to_vec()
+                }
+                _ => Self::scalar_slot(inner, item)?.to_ne_bytes(),
+            };
+            unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), dst, size) };
+        }
+        let ptr = words.as_ptr() as *const c_void;
+        self.keep.push(Box::new(words));
+        Ok(ptr)
+    }
+
+    /// Encodes the value of an `Option` or `Ok` argument into a pointer: strings as a pointer
+    /// to an `XrossStringView`, objects as their handle and scalars as a pointer to the value.
+    fn value_to_ptr(
+        &mut self,
+        ty: &XrossType,
+        value: &XrossValue,
+    ) -> Result<*const c_void, String> {
+        Ok(match (ty, value) {
+            (XrossType::String, XrossValue::String(s)) => {
+                let (ptr, len, encoding) = self.keep_string_view(s);
+                let view = Box::new(XrossStringView { ptr: ptr as *const u8, len, encoding });
+                let ptr = &*view as *const XrossStringView as *const c_void;
+                self.keep.push(view);
+                ptr
+            }
+            (XrossType::Object { .. }, XrossValue::Object(obj)) => obj.ptr,
+            _ => match Self::scalar_slot(ty, value)? {
+                Slot::Ptr(v) => v,
+                slot => {
+                    let bytes = slot.to_ne_bytes();
+                    let ptr = bytes.as_ptr() as *const c_void;
+                    self.keep.push(Box::new(bytes));
+                    ptr
+                }
+            },
+        })
+    }
+
+    /// Calls `fun` with these arguments, returning `R` (which must match `ret`).
+    /// Integer returns narrower than a register must be read as `usize` (libffi widens them).
+    pub(crate) unsafe fn call<R>(self, fun: *const c_void, ret: Type) -> R {
+        let cif = Cif::new(self.types, ret);
+        let args: Vec<Arg> = self.slots.iter().map(Slot::arg).collect();
+        let result = unsafe { cif.call::<R>(CodePtr::from_ptr(fun), &args) };
+        drop(self.keep);
+        result
+    }
+
+    /// Calls `fun`, which returns the by-value struct `def`, and decodes its fields.
+    pub(crate) unsafe fn call_struct(self, fun: *const c_void, def: &XrossStruct) -> XrossValue {
+        let layout = def.c_layout(usize::BITS).expect("not a by-value struct");
+        // Whole words, as libffi may write a small struct a register at a time.
+        let mut words = vec![0u64; (layout.size as usize).div_ceil(8)];
+        let cif = Cif::new(self.types, struct_type(def));
+        let args: Vec<Arg> = self.slots.iter().map(Slot::arg).collect();
+        unsafe {
+            libffi::raw::ffi_call(
+                cif.as_raw_ptr(),
+                Some(*CodePtr::from_ptr(fun).as_fun()),
+                words.as_mut_ptr() as *mut c_void,
+                args.as_ptr() as *mut *mut c_void,
+            );
+        }
+        drop(self.keep);
+        let base = words.as_ptr() as *const u8;
+        let fields = def
+            .fields
+            .iter()
+            .zip(&layout.fields)
+            .map(|(field, at)| {
+                let ptr = unsafe { base.add(at.offset as usize) };
+                let value = unsafe { read_scalar(&field.ty, ptr, None) };
+                (field.name.clone(), value.expect("by-value fields are scalars"))
+            })
+            .collect();
+        XrossValue::Struct(XrossStructValue { signature: def.signature.clone(), fields })
+    }
+}
+
+/// Reads a scalar of type `ty` at `ptr`.
+unsafe fn read_scalar(
+    ty: &XrossType,
+    ptr: *const u8,
+    signature: Option<&str>,
+) -> Option<XrossValue> {
+    unsafe {
+        Some(match ty {
+            XrossType::Bool => XrossValue::Bool(*ptr != 0),
+            XrossType::I8 => XrossValue::I8(ptr.cast::<i8>().read_unaligned()),
+            XrossType::U8 => XrossValue::U8(*ptr),
+            XrossType::I16 => XrossValue::I16(ptr.cast::<i16>().read_unaligned()),
+            XrossType::U16 => XrossValue::U16(ptr.cast::<u16>().read_unaligned()),
+            XrossType::I32 => XrossValue::I32(ptr.cast::<i32>().read_unaligned()),
+            XrossType::U32 => XrossValue::U32(ptr.cast::<u32>().read_unaligned()),
+            XrossType::I64 => XrossValue::I64(ptr.cast::<i64>().read_unaligned()),
+            XrossType::U64 => XrossValue::U64(ptr.cast::<u64>().read_unaligned()),
+            XrossType::ISize => XrossValue::ISize(ptr.cast::<isize>().read_unaligned()),
+            XrossType::USize => XrossValue::USize(ptr.cast::<usize>().read_unaligned()),
+            XrossType::F32 => XrossValue::F32(ptr.cast::<f32>().read_unaligned()),
+            XrossType::F64 => XrossValue::F64(ptr.cast::<f64>().read_unaligned()),
+            XrossType::Pointer => XrossValue::Pointer(ptr.cast::<*mut c_void>().read_unaligned()),
+            XrossType::Object { .. } => XrossValue::Object(XrossObject {
+                signature: signature?.to_string(),
+                ptr: ptr.cast::<*mut c_void>().read_unaligned(),
+            }),
+            _ => return None,
+        })
+    }
+}
+
+/// Converts a register-sized integer return into a value of type `ty`.
+pub(crate) fn from_register(ty: &XrossType, raw: u64) -> Option<XrossValue> {
+    Some(match ty {
+        XrossType::Bool => XrossValue::Bool(raw as u8 != 0),
+        XrossType::I8 => XrossValue::I8(raw as i8),
+        XrossType::U8 => XrossValue::U8(raw as u8),
+        XrossType::I16 => XrossValue::I16(raw as i16),
+        XrossType::U16 => XrossValue::U16(raw as u16),
+        XrossType::I32 => XrossValue::I32(raw as i32),
+        XrossType::U32 => XrossValue::U32(raw as u32),
+        XrossType::I64 => XrossValue::I64(raw as i64),
+        XrossType::U64 => XrossValue::U64(raw),
+        XrossType::ISize => XrossValue::ISize(raw as isize),
+        XrossType::USize => XrossValue::USize(raw as usize),
+        XrossType::Pointer => XrossValue::Pointer(raw as usize as *mut c_void),
+        _ => return None,
+    })
+}
+
+/// Decodes values that Rust handed out, releasing their buffers with the library's
+/// `XROSS_BUFFER_RELEASE_SYMBOLS` and boxed scalars with `xross_free_scalar`.
+pub(crate) struct Decoder {
+    pub(crate) releases: Vec<(&'static str, unsafe extern "C" fn(XrossBuffer))>,
+    pub(crate) free_scalar: unsafe extern "C" fn(*mut c_void, usize),
+}
+
+impl Decoder {
+    /// Frees `buf`, which holds a value of type `ty`.
+    unsafe fn release(&self, ty: &XrossType, buf: XrossBuffer) {
+        let symbol = ty.buffer_release_symbol().expect("not a buffer type");
+        let (_, release) = self.releases.iter().find(|(name, _)| *name == symbol).unwrap();
+        unsafe { release(buf) };
+    }
+
+    fn object(ty: &XrossType, ptr: *mut c_void) -> XrossValue {
+        let XrossType::Object { signature, .. } = ty else { unreachable!() };
+        XrossValue::Object(XrossObject { signature: signature.clone(), ptr })
+    }
+
+    /// Takes the string out of a buffer and frees it.
+    pub(crate) unsafe fn string(&self, buf: XrossBuffer) -> String {
+        let s = if buf.ptr.is_null() {
+            String::new()
+        } else {
+            let bytes = unsafe { std::slice::from_raw_parts(buf.ptr as *const u8, buf.len) };
+            String::from_utf8_lossy(bytes).into_owned()
+        };
+        unsafe { self.release(&XrossType::String, buf) };
+        s
+    }
+
+    /// Decodes a `String`, `Vec` or `Slice` written to an `XrossBuffer` and frees it.
+    /// Elements of object vectors are owned handles.
+    pub(crate) unsafe fn buffer(&self, ty: &XrossType, buf: XrossBuffer) -> Option<XrossValue> {
+        match ty {
+            XrossType::String => Some(XrossValue::String(unsafe { self.string(buf) })),
+            XrossType::Vec(inner) | XrossType::Slice(inner) => {
+                let size = scalar_size(inner)?;
+                let signature = match &**inner {
+                    XrossType::Object { signature, .. } => Some(signature.as_str()),
+                    _ => None,
+                };
+                let mut items = Vec::with_capacity(buf.len);
+                for i in 0..buf.len {
+                    let ptr = unsafe { (buf.ptr as *const u8).add(i * size) };
+                    items.push(unsafe { read_scalar(inner, ptr, signature) }?);
+                }
+                unsafe { self.release(ty, buf) };
+                Some(XrossValue::Vec(items))
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes the pointer of an `XrossResult` (`xross_core::abi::XrossPtr`): strings and
+    /// vectors are a boxed `XrossBuffer`, objects an owned handle, options a returned `Option`,
+    /// floats their bits, other scalars the value. 64-bit values are boxed where pointers are
+    /// narrower.
+    pub(crate) unsafe fn boxed(&self, ty: &XrossType, ptr: *mut c_void) -> Option<XrossValue> {
+        Some(match ty {
+            XrossType::Void => XrossValue::Void,
+            XrossType::String => {
+                if ptr.is_null() {
+                    return Some(XrossValue::String(String::new()));
+                }
+                // The box itself is left alone, as the JVM bindings do; only its buffer is freed.
+                let buf = unsafe { std::ptr::read(ptr as *const XrossBuffer) };
+                XrossValue::String(unsafe { self.string(buf) })
+            }
+            XrossType::Vec(_) | XrossType::Slice(_) => {
+                let buf = unsafe { std::ptr::read(ptr as *const XrossBuffer) };
+                unsafe { self.buffer(ty, buf) }?
+            }
+            XrossType::Object { .. } => Self::object(ty, ptr),
+            XrossType::Option(inner) => unsafe { self.option(inner, ptr) }?,
+            XrossType::I64 | XrossType::U64 | XrossType::F64 if usize::BITS < 64 => {
+                let value = unsafe { read_scalar(ty, ptr as *const u8, None) }?;
+                unsafe { (self.free_scalar)(ptr, 8) };
+                value
+            }
+            XrossType::F32 => XrossValue::F32(f32::from_bits(ptr as usize as u32)),
+            XrossType::F64 => XrossValue::F64(f64::from_bits(ptr as usize as u64)),
+            ty => from_register(ty, ptr as usize as u64)?,
+        })
+    }
+
+    /// Decodes an `Option` returned as a pointer (`xross_core::abi::option_into_ptr`): null is
+    /// `None`, scalars are a box of the value and everything else is encoded as by `boxed`.
+    pub(crate) unsafe fn option(&self, inner: &XrossType, ptr: *mut c_void) -> Option<XrossValue> {
+        if ptr.is_null() {
+            return Some(XrossValue::Option(None));
+        }
+        let value = match inner {
+            XrossType::Pointer => unsafe { self.boxed(inner, ptr) }?,
+            inner if scalar_type(inner).is_some() => {
+                let value = unsafe { read_scalar(inner, ptr as *const u8, None) }?;
+                unsafe { (self.free_scalar)(ptr, scalar_size(inner)?) };
+                value
+            }
+            inner => unsafe { self.boxed(inner, ptr) }?,
+        };
+        Some(XrossValue::Option(Some(Box::new(value))))
+    }
+
+    /// Decodes an `XrossResult` returned by a `Result` export or getter.
+    pub(crate) unsafe fn result(&self, ty: &XrossType, res: XrossResult) -> Option<XrossValue> {
+        let XrossType::Result { ok, err } = ty else { return None };
+        Some(XrossValue::Result(if res.is_ok {
+            Ok(Box::new(unsafe { self.boxed(ok, res.ptr) }?))
+        } else {
+            Err(Box::new(unsafe { self.boxed(err, res.ptr) }?))
+        }))
+    }
+
+    /// Message of a failed `XrossResult`: a boxed `XrossString`, or none for a null pointer.
+    pub(crate) unsafe fn error_message(&self, res: &XrossResult) -> String {
+        if res.ptr.is_null() {
+            return "unknown error".to_string();
+        }
+        let buf = unsafe { std::ptr::read(res.ptr as *const XrossBuffer) };
+        unsafe { self.string(buf) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xross_core::abi;
+
+    /// A decoder over the runtime functions of this process, which encodes values the same
+    /// way a loaded library does.
+    fn decoder() -> Decoder {
+        let releases: [unsafe extern "C" fn(XrossBuffer); 5] = [
+            xross_core::xross_free_buffer,
+            xross_core::xross_free_buffer_16,
+            xross_core::xross_free_buffer_32,
+            xross_core::xross_free_buffer_64,
+            xross_core::xross_free_buffer_ptr,
+        ];
+        let releases =
+            xross_metadata::XROSS_BUFFER_RELEASE_SYMBOLS.iter().copied().zip(releases).collect();
+        Decoder { releases, free_scalar: xross_core::xross_free_scalar }
+    }
+
+    fn some(value: impl Into<XrossValue>) -> Option<XrossValue> {
+        Some(XrossValue::Option(Some(Box::new(value.into()))))
+    }
+
+    #[test]
+    fn test_option() {
+        let d = decoder();
+        unsafe {
+            assert_eq!(d.option(&XrossType::I32, abi::option_into_ptr(Some(0i32))), some(0i32));
+            assert_eq!(d.option(&XrossType::Bool, abi::option_into_ptr(Some(false))), some(false));
+            assert_eq!(d.option(&XrossType::F64, abi::option_into_ptr(Some(1.5f64))), some(1.5f64));
+            assert_eq!(
+                d.option(&XrossType::U8, abi::option_into_ptr(Some(u8::MAX))),
+                some(u8::MAX)
+            );
+            let none = abi::option_into_ptr::<i64>(None);
+            assert_eq!(d.option(&XrossType::I64, none), Some(XrossValue::Option(None)));
+            let s = abi::option_into_ptr(Some("hi".to_string()));
+            assert_eq!(d.option(&XrossType::String, s), some("hi"));
+        }
+    }
+
+    #[test]
+    fn test_buffer() {
+        let d = decoder();
+        let ints = XrossType::Vec(Box::new(XrossType::I32));
+        let expected = XrossValue::Vec(vec![1i32.into(), (-2i32).into(), 3i32.into()]);
+        unsafe {
+            assert_eq!(d.buffer(&ints, XrossBuffer::from(vec![1i32, -2, 3])), Some(expected));
+            let s = d.buffer(&XrossType::String, XrossBuffer::from("日本".to_string()));
+            assert_eq!(s, Some("日本".into()));
+            assert_eq!(d.buffer(&XrossType::I32, XrossBuffer::from(vec![1i32])), None);
+        }
+    }
+
+    #[test]
+    fn test_result() {
+        let d = decoder();
+        let ty =
+            XrossType::Result { ok: Box::new(XrossType::I64), err: Box::new(XrossType::String) };
+        unsafe {
+            let ok = d.result(&ty, abi::result::<i64, String>(Ok(-7)));
+            assert_eq!(ok, Some(XrossValue::Result(Ok(Box::new((-7i64).into())))));
+            let err = d.result(&ty, abi::result::<i64, String>(Err("bad".to_string())));
+            assert_eq!(err, Some(XrossValue::Result(Err(Box::new("bad".into())))));
+            assert_eq!(d.error_message(&abi::error("boom".to_string())), "boom");
+            let null = XrossResult { is_ok: false, ptr: std::ptr::null_mut() };
+            assert_eq!(d.error_message(&null), "unknown error");
+        }
+    }
+
+    #[test]
+    fn test_from_register() {
+        assert_eq!(from_register(&XrossType::I8, 0xff), Some(XrossValue::I8(-1)));
+        assert_eq!(from_register(&XrossType::Bool, 0x100), Some(XrossValue::Bool(false)));
+        assert_eq!(from_register(&XrossType::U64, u64::MAX), Some(XrossValue::U64(u64::MAX)));
+        assert_eq!(from_register(&XrossType::String, 0), None);
+    }
+
+    #[test]
+    fn test_argument_mismatch() {
+        let mut args = NativeArgs::default();
+        let error = args.push_arg(&XrossType::I32, &XrossValue::I64(1)).unwrap_err();
+        assert_eq!(error, "expected a value of type I32, got I64(1)");
+        assert!(args.push_scalar(&XrossType::String, &"s".into()).is_err());
+        args.push_arg(&XrossType::String, &"abc".into()).unwrap();
+        assert_eq!(args.types.len(), 3, "strings are passed as ptr, len, enc");
+    }
+}
@@ -0,0 +1,649 @@
+//! Calls the exports of a compiled xross library from Rust, without a JVM.
+//!
+//! `XrossTestLibrary` loads a cdylib with `libloading`, reads its embedded metadata and
+//! calls the generated symbols through libffi with the same convention as the generated
+//! Kotlin bindings: strings are flattened to `ptr, len, enc`, `String`/`Vec` returns are
+//! written to an `out` buffer, `panicable` exports to an `out` `XrossResult`, and async or
+//! blocking exports hand out an `XrossTask` that is polled to completion.
+//!
+//! ```ignore
+//! let lib = XrossTestLibrary::find("xross_example")?;
+//! let obj = lib.invoke("fast.FastStruct", "new", &[1i32.into(), "name".into()])?;
+//! let n = lib.invoke("fast.FastStruct", "count_chars", &[obj.clone(), "abc".into()])?;
+//! lib.drop(obj.as_object().unwrap())?;
+//! ```
+
+mod call;
+mod value;
+
+pub use value::{
+    XrossCancelFlag, XrossCollection, XrossInvokeError, XrossObject, XrossStructValue, XrossValue,
+};
+
+use call::{Decoder, NativeArgs, buffer_type, from_register, result_type, scalar_type};
+use libffi::middle::Type;
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use xross_core::{XrossBuffer, XrossIterator, XrossPoll, XrossResult, XrossStream, XrossTask};
+use xross_metadata::{
+    HandleMode, XROSS_BUFFER_RELEASE_SYMBOLS, XrossDefinition, XrossField, XrossMetadataBundle,
+    XrossMetadataSet, XrossMethod, XrossMethodType, XrossStruct, XrossType,
+};
+
+/// How long tasks and streams are polled before `XrossInvokeError::Timeout`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A loaded xross library and the definitions embedded in it.
+pub struct XrossTestLibrary {
+    library: libloading::Library,
+    set: XrossMetadataSet,
+    decoder: Decoder,
+    timeout: Duration,
+}
+
+fn load_error(path: &Path, e: impl std::fmt::Display) -> XrossInvokeError {
+    XrossInvokeError::Load(format!("{}: {}", path.display(), e))
+}
+
+impl XrossTestLibrary {
+    /// Loads `path` and reads its metadata with `xross_metadata`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, XrossInvokeError> {
+        let path = path.as_ref();
+        // SAFETY: loading runs the library's initializers; the runtime symbols are exported
+        // by xross-core with these signatures.
+        let (library, decoder, json) = unsafe {
+            let library = libloading::Library::new(path).map_err(|e| load_error(path, e))?;
+            let metadata = *library
+                .get::<unsafe extern "C" fn(*mut XrossBuffer)>(b"xross_metadata")
+                .map_err(|e| load_error(path, e))?;
+            let mut releases = Vec::new();
+            for &name in XROSS_BUFFER_RELEASE_SYMBOLS {
+                let release = *library
+                    .get::<unsafe extern "C" fn(XrossBuffer)>(name.as_bytes())
+                    .map_err(|e| load_error(path, e))?;
+                releases.push((name, release));
+            }
+            let free_scalar = *library
+                .get::<unsafe extern "C" fn(*mut c_void, usize)>(b"xross_free_scalar")
+                .map_err(|e| load_error(path, e))?;
+            let decoder = Decoder { releases, free_scalar };
+            let mut out = std::mem::MaybeUninit::<XrossBuffer>::uninit();
+            metadata(out.as_mut_ptr());
+            let json = decoder.string(out.assume_init());
+            (library, decoder, json)
+        };
+        let bundle = XrossMetadataBundle::from_json(&json).map_err(|e| load_error(path, e))?;
+        let mut set = XrossMetadataSet::new();
+        for def in bundle.definitions {
+            set.insert(def).map_err(|e| load_error(path, e))?;
+        }
+        Ok(Self { library, set, decoder, timeout: DEFAULT_TIMEOUT })
+    }
+
+    /// Loads the cdylib of crate `name` (e.g. `xross_example`) from the target directory of
+    /// the running test, i.e. the parent of its `deps` directory.
+    pub fn find(name: &str) -> Result<Self, XrossInvokeError> {
+        let file = format!(
+            "{}{}{}",
+            std::env::consts::DLL_PREFIX,
+            name.replace('-', "_"),
+            std::env::consts::DLL_SUFFIX
+        );
+        let exe = std::env::current_exe().map_err(|e| XrossInvokeError::Load(e.to_string()))?;
+        let path = exe
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join(&file))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| PathBuf::from(&file));
+        Self::open(path)
+    }
+
+    /// Sets how long tasks and streams are polled before giving up.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The definitions embedded in the library.
+    pub fn metadata(&self) -> &XrossMetadataSet {
+        &self.set
+    }
+
+    pub fn definition(&self, signature: &str) -> Result<&XrossDefinition, XrossInvokeError> {
+        self.set
+            .get(signature)
+            .ok_or_else(|| XrossInvokeError::UnknownDefinition { signature: signature.to_string() })
+    }
+
+    fn symbol(&self, name: &str) -> Result<*const c_void, XrossInvokeError> {
+        // SAFETY: the address is only called through a `Cif` built from the metadata.
+        let symbol = unsafe { self.library.get::<unsafe extern "C" fn()>(name.as_bytes()) }
+            .map_err(|e| XrossInvokeError::Load(format!("{}: {}", name, e)))?;
+        Ok(*symbol as *const c_void)
+    }
+
+    /// Calls method `method` of the definition `signature` (a function's own signature for
+    /// standalone functions). Instance methods take their receiver as the first argument.
+    ///
+    /// Returns the decoded value: `Void` for no value, `Vec` for drained iterators and
+    /// streams, `Collection` for `contiguous` returns. Objects returned by value are owned
+    /// handles that should be dropped with [`drop`](Self::drop), and collections with
+    /// [`drop_collection`](Self::drop_collection). A caught panic is `XrossInvokeError::Panic`.
+    pub fn invoke(
+        &self,
+        signature: &str,
+        method: &str,
+        args: &[XrossValue],
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let def = self.definition(signature)?;
+        let m = def.methods().iter().find(|m| m.name == method).ok_or_else(|| {
+            XrossInvokeError::UnknownMember {
+                signature: signature.to_string(),
+                member: method.to_string(),
+            }
+        })?;
+        let prefix = def.symbol_prefix();
+        if m.symbol == format!("{}_drop", prefix) || m.symbol == format!("{}_clone", prefix) {
+            return self.drop_or_clone(def, m, args);
+        }
+
+        let arguments =
+            |message: String| XrossInvokeError::Arguments { symbol: m.symbol.clone(), message };
+        let has_receiver = m.method_type != XrossMethodType::Static;
+        let expected = m.args.len() + has_receiver as usize;
+        if args.len() != expected {
+            return Err(arguments(format!("expected {} arguments, got {}", expected, args.len())));
+        }
+
+        let mut native = NativeArgs::default();
+        let mut args = args.iter();
+        if has_receiver {
+            let receiver = args.next().and_then(XrossValue::as_object);
+            let receiver =
+                receiver.ok_or_else(|| arguments("the receiver must be an object".to_string()))?;
+            native.push_ptr(receiver.ptr);
+        }
+        let fun = self.symbol(&m.symbol)?;
+        if m.is_raw {
+            for (field, value) in m.args.iter().zip(args) {
+                native.push_scalar(&field.ty, value).map_err(arguments)?;
+            }
+            return self.call_raw(m, fun, native);
+        }
+        for (field, value) in m.args.iter().zip(args) {
+            self.push_arg(&mut native, &field.ty, value).map_err(arguments)?;
+        }
+        self.call(m, fun, native)
+    }
+
+    /// Pushes one argument of a method or variant export. Owned by-value structs are passed
+    /// as their C layout.
+    fn push_arg(
+        &self,
+        native: &mut NativeArgs,
+        ty: &XrossType,
+        value: &XrossValue,
+    ) -> Result<(), String> {
+        match self.set.by_value_struct(ty) {
+            Some(def) => native.push_struct(def, value),
+            None => native.push_arg(ty, value),
+        }
+    }
+
+    /// Returns the struct that `m` returns by value. Constructors always return a handle.
+    fn by_value_return(&self, m: &XrossMethod) -> Option<&XrossStruct> {
+        if m.is_constructor { None } else { self.set.by_value_struct(&m.ret) }
+    }
+
+    fn unsupported(symbol: &str, ty: &XrossType) -> XrossInvokeError {
+        XrossInvokeError::Unsupported {
+            symbol: symbol.to_string(),
+            message: format!("cannot decode {:?}", ty),
+        }
+    }
+
+    /// Prepends an `out` pointer to `native`, calls `fun` and returns what it wrote.
+    unsafe fn call_out<T>(fun: *const c_void, mut native: NativeArgs) -> T {
+        let mut out = std::mem::MaybeUninit::<T>::uninit();
+        native.prepend_ptr(out.as_mut_ptr() as *const c_void);
+        unsafe {
+            native.call::<()>(fun, Type::void());
+            out.assume_init()
+        }
+    }
+
+    fn call_raw(
+        &self,
+        m: &XrossMethod,
+        fun: *const c_void,
+        native: NativeArgs,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        if m.handle_mode == HandleMode::Panicable {
+            let res: XrossResult = unsafe { Self::call_out(fun, native) };
+            return self.panicable(&m.symbol, &m.ret, res);
+        }
+        unsafe { self.call_direct(&m.symbol, &m.ret, fun, native) }
+    }
+
+    /// Calls an export that returns its value directly.
+    unsafe fn call_direct(
+        &self,
+        symbol: &str,
+        ret: &XrossType,
+        fun: *const c_void,
+        native: NativeArgs,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        unsafe {
+            Ok(match ret {
+                XrossType::Void => {
+                    native.call::<()>(fun, Type::void());
+                    XrossValue::Void
+                }
+                XrossType::F32 => XrossValue::F32(native.call::<f32>(fun, Type::f32())),
+                XrossType::F64 => XrossValue::F64(native.call::<f64>(fun, Type::f64())),
+                XrossType::Object { .. } => {
+                    let ptr = native.call::<*mut c_void>(fun, Type::pointer());
+                    self.decoder.boxed(ret, ptr).ok_or_else(|| Self::unsupported(symbol, ret))?
+                }
+                XrossType::Option(inner) => {
+                    let ptr = native.call::<*mut c_void>(fun, Type::pointer());
+                    self.decoder.option(inner, ptr).ok_or_else(|| Self::unsupported(symbol, ret))?
+                }
+                XrossType::Result { .. } => {
+                    let res = native.call::<XrossResult>(fun, result_type());
+                    self.decoder.result(ret, res).ok_or_else(|| Self::unsupported(symbol, ret))?
+                }
+                ret => {
+                    let c_ty = scalar_type(ret).ok_or_else(|| Self::unsupported(symbol, ret))?;
+                    // libffi widens integer returns to a full register.
+                    let raw = if matches!(ret, XrossType::I64 | XrossType::U64) {
+                        native.call::<u64>(fun, c_ty)
+                    } else {
+                        native.call::<usize>(fun, c_ty) as u64
+                    };
+                    from_register(ret, raw).ok_or_else(|| Self::unsupported(symbol, ret))?
+                }
+            })
+        }
+    }
+
+    /// Calls a method export, following `write_ffi_function`.
+    fn call(
+        &self,
+        m: &XrossMethod,
+        fun: *const c_void,
+        native: NativeArgs,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let symbol = &m.symbol;
+        match &m.ret {
+            XrossType::Iterator(item) => {
+                let iter: XrossIterator = unsafe { Self::call_out(fun, native) };
+                self.drain_iterator(symbol, item, iter)
+            }
+            XrossType::Stream(item) => {
+                let stream: XrossStream = unsafe { Self::call_out(fun, native) };
+                self.drain_stream(symbol, item, stream)
+            }
+            ret if m.is_async || m.handle_mode == HandleMode::Blocking => {
+                let task: XrossTask = unsafe { Self::call_out(fun, native) };
+                self.await_task(symbol, ret, task)
+            }
+            ret if m.contiguous => {
+                let buf = if m.handle_mode == HandleMode::Panicable {
+                    let res: XrossResult = unsafe { Self::call_out(fun, native) };
+                    if !res.is_ok {
+                        let message = unsafe { self.decoder.error_message(&res) };
+                        return Err(XrossInvokeError::Panic(message));
+                    }
+                    unsafe { std::ptr::read(res.ptr as *const XrossBuffer) }
+                } else {
+                    unsafe { Self::call_out(fun, native) }
+                };
+                self.collection(symbol, ret, buf)
+            }
+            ret if m.handle_mode == HandleMode::Panicable => {
+                let res: XrossResult = unsafe { Self::call_out(fun, native) };
+                self.panicable(symbol, ret, res)
+            }
+            ret @ (XrossType::String | XrossType::Vec(_) | XrossType::Slice(_)) => {
+                let buf: XrossBuffer = unsafe { Self::call_out(fun, native) };
+                unsafe { self.decoder.buffer(ret, buf) }
+                    .ok_or_else(|| Self::unsupported(symbol, ret))
+            }
+            ret => match self.by_value_return(m) {
+                Some(def) => Ok(unsafe { native.call_struct(fun, def) }),
+                None => unsafe { self.call_direct(symbol, ret, fun, native) },
+            },
+        }
+    }
+
+    /// Splits the buffer of a `contiguous` return into views of its elements, which are
+    /// the `size` of the element type's layout apart.
+    fn collection(
+        &self,
+        symbol: &str,
+        ret: &XrossType,
+        buf: XrossBuffer,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let (XrossType::Vec(inner) | XrossType::Slice(inner)) = ret else {
+            return Err(Self::unsupported(symbol, ret));
+        };
+        let XrossType::Object { signature, .. } = &**inner else {
+            return Err(Self::unsupported(symbol, ret));
+        };
+        let layout = self.definition(signature)?.layout();
+        let stride = layout.ok_or_else(|| Self::unsupported(symbol, ret))?.size as usize;
+        let items = (0..buf.len)
+            .map(|i| XrossObject {
+                signature: signature.clone(),
+                ptr: buf.ptr.wrapping_byte_add(i * stride),
+            })
+            .collect();
+        Ok(XrossValue::Collection(XrossCollection {
+            signature: signature.clone(),
+            items,
+            ptr: buf.ptr,
+            cap: buf.cap,
+        }))
+    }
+
+    /// Decodes the `out` result of a `panicable` export. `String`, `Vec` and `Slice` values
+    /// are a boxed `XrossBuffer`, and a `Result` return is passed through unchanged.
+    fn panicable(
+        &self,
+        symbol: &str,
+        ret: &XrossType,
+        res: XrossResult,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let unsupported = || Self::unsupported(symbol, ret);
+        match ret {
+            XrossType::Result { .. } => {
+                // A caught panic and an `Err` share the same shape; only the message tells.
+                unsafe { self.decoder.result(ret, res) }.ok_or_else(unsupported)
+            }
+            _ if !res.is_ok => {
+                Err(XrossInvokeError::Panic(unsafe { self.decoder.error_message(&res) }))
+            }
+            XrossType::Vec(_) | XrossType::Slice(_) => {
+                let buf = unsafe { std::ptr::read(res.ptr as *const XrossBuffer) };
+                unsafe { self.decoder.buffer(ret, buf) }.ok_or_else(unsupported)
+            }
+            XrossType::Option(inner) => {
+                unsafe { self.decoder.option(inner, res.ptr) }.ok_or_else(unsupported)
+            }
+            ret => unsafe { self.decoder.boxed(ret, res.ptr) }.ok_or_else(unsupported),
+        }
+    }
+
+    /// Polls `task` until it is ready, then drops it.
+    fn await_task(
+        &self,
+        symbol: &str,
+        ret: &XrossType,
+        task: XrossTask,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let deadline = Instant::now() + self.timeout;
+        let outcome = loop {
+            let mut res = std::mem::MaybeUninit::<XrossResult>::uninit();
+            if unsafe { (task.poll_fn)(task.task_ptr, res.as_mut_ptr()) } == XrossPoll::Ready {
+                let res = unsafe { res.assume_init() };
+                break if res.is_ok {
+                    unsafe { self.decoder.boxed(ret, res.ptr) }
+                        .ok_or_else(|| Self::unsupported(symbol, ret))
+                } else {
+                    Err(XrossInvokeError::Task(unsafe { self.decoder.error_message(&res) }))
+                };
+            }
+            if Instant::now() > deadline {
+                break Err(XrossInvokeError::Timeout { symbol: symbol.to_string() });
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        unsafe { (task.drop_fn)(task.task_ptr) };
+        outcome
+    }
+
+    fn drain_iterator(
+        &self,
+        symbol: &str,
+        item: &XrossType,
+        iter: XrossIterator,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let mut items = Vec::new();
+        let mut res = std::mem::MaybeUninit::<XrossResult>::uninit();
+        let outcome = loop {
+            if !unsafe { (iter.next_fn)(iter.iter_ptr, res.as_mut_ptr()) } {
+                break Ok(XrossValue::Vec(items));
+            }
+            let res = unsafe { res.assume_init_read() };
+            match unsafe { self.decoder.boxed(item, res.ptr) } {
+                Some(value) => items.push(value),
+                None => break Err(Self::unsupported(symbol, item)),
+            }
+        };
+        unsafe { (iter.close_fn)(iter.iter_ptr) };
+        outcome
+    }
+
+    fn drain_stream(
+        &self,
+        symbol: &str,
+        item: &XrossType,
+        stream: XrossStream,
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let deadline = Instant::now() + self.timeout;
+        let mut items = Vec::new();
+        let mut res = std::mem::MaybeUninit::<XrossResult>::uninit();
+        let outcome = loop {
+            match unsafe { (stream.poll_next_fn)(stream.stream_ptr, res.as_mut_ptr()) } {
+                XrossPoll::Ready => {
+                    let res = unsafe { res.assume_init_read() };
+                    match unsafe { self.decoder.boxed(item, res.ptr) } {
+                        Some(value) => items.push(value),
+                        None => break Err(Self::unsupported(symbol, item)),
+                    }
+                    continue;
+                }
+                XrossPoll::Done => break Ok(XrossValue::Vec(items)),
+                XrossPoll::Pending if Instant::now() > deadline => {
+                    break Err(XrossInvokeError::Timeout { symbol: symbol.to_string() });
+                }
+                XrossPoll::Pending => std::thread::sleep(Duration::from_millis(1)),
+            }
+        };
+        unsafe { (stream.close_fn)(stream.stream_ptr) };
+        outcome
+    }
+
+    /// `_drop` and `_clone` take only the receiver and return an `XrossResult` directly
+    /// when panicable.
+    fn drop_or_clone(
+        &self,
+        def: &XrossDefinition,
+        m: &XrossMethod,
+        args: &[XrossValue],
+    ) -> Result<XrossValue, XrossInvokeError> {
+        let [XrossValue::Object(obj)] = args else {
+            return Err(XrossInvokeError::Arguments {
+                symbol: m.symbol.clone(),
+                message: "expected the object as the only argument".to_string(),
+            });
+        };
+        let fun = self.symbol(&m.symbol)?;
+        let mut native = NativeArgs::default();
+        native.push_ptr(obj.ptr);
+        let is_clone = m.name == "clone";
+        if m.handle_mode == HandleMode::Panicable {
+            let res: XrossResult = unsafe { native.call(fun, result_type()) };
+            if !res.is_ok {
+                return Err(XrossInvokeError::Panic(unsafe { self.decoder.error_message(&res) }));
+            }
+            return Ok(if is_clone { Self::handle(def, res.ptr) } else { XrossValue::Void });
+        }
+        if is_clone {
+            let ptr: *mut c_void = unsafe { native.call(fun, Type::pointer()) };
+            Ok(Self::handle(def, ptr))
+        } else {
+            unsafe { native.call::<()>(fun, Type::void()) };
+            Ok(XrossValue::Void)
+        }
+    }
+
+    fn handle(def: &XrossDefinition, ptr: *mut c_void) -> XrossValue {
+        XrossValue::Object(XrossObject { signature: def.signature().to_string(), ptr })
+    }
+
+    /// Drops an owned object through its `_drop` export.
+    pub fn drop(&self, obj: &XrossObject) -> Result<(), XrossInvokeError> {
+        self.invoke(&obj.signature, "drop", &[XrossValue::Object(obj.clone())]).map(|_| ())
+    }
+
+    /// Drops the elements of a `contiguous` return and frees their allocation through the
+    /// element type's `_drop_vec` export. The views in `collection` must not be used after.
+    pub fn drop_collection(&self, collection: &XrossCollection) -> Result<(), XrossInvokeError> {
+        let def = self.definition(&collection.signature)?;
+        let symbol = format!("{}_drop_vec", def.symbol_prefix());
+        // SAFETY: `_drop_vec` is generated with this signature for every class.
+        let drop_vec =
+            unsafe { self.library.get::<unsafe extern "C" fn(XrossBuffer)>(symbol.as_bytes()) }
+                .map_err(|e| XrossInvokeError::Load(format!("{}: {}", symbol, e)))?;
+        let items =
+            XrossBuffer { cap: collection.cap, len: collection.items.len(), ptr: collection.ptr };
+        unsafe { drop_vec(items) };
+        Ok(())
+    }
+
+    fn field(
+        &self,
+        obj: &XrossObject,
+        name: &str,
+    ) -> Result<(&XrossDefinition, &XrossField), XrossInvokeError> {
+        let def = self.definition(&obj.signature)?;
+        let fields = match def {
+            XrossDefinition::Struct(s) => &s.fields[..],
+            XrossDefinition::Opaque(o) => &o.fields[..],
+            _ => &[],
+        };
+        let field = fields.iter().find(|f| f.name == name).ok_or_else(|| {
+            XrossInvokeError::UnknownMember {
+                signature: obj.signature.clone(),
+                member: name.to_string(),
+            }
+        })?;
+        Ok((def, field))
+    }
+
+    fn property_symbol(def: &XrossDefinition, field: &XrossField, accessor: &str) -> String {
+        let suffix = match field.ty {
+            XrossType::String => "_str",
+            XrossType::Option(_) => "_opt",
+            XrossType::Result { .. } => "_res",
+            _ => "",
+        };
+        format!("{}_property_{}{}_{}", def.symbol_prefix(), field.name, suffix, accessor)
+    }
+
+    /// Reads a field through its `_get` accessor. `String` getters return the buffer by
+    /// value, and `Option` getters box their value like an `XrossResult`.
+    pub fn get(&self, obj: &XrossObject, field: &str) -> Result<XrossValue, XrossInvokeError> {
+        let (def, field) = self.field(obj, field)?;
+        let symbol = Self::property_symbol(def, field, "get");
+        let fun = self.symbol(&symbol)?;
+        let mut native = NativeArgs::default();
+        native.push_ptr(obj.ptr);
+        let ty = &field.ty;
+        let unsupported = || Self::unsupported(&symbol, ty);
+        unsafe {
+            match ty {
+                XrossType::String => {
+                    let buf: XrossBuffer = native.call(fun, buffer_type());
+                    Ok(XrossValue::String(self.decoder.string(buf)))
+                }
+                // A string field is a boxed `XrossBuffer`, unlike a returned `Option<String>`.
+                XrossType::Option(inner) if **inner == XrossType::String => {
+                    let ptr: *mut c_void = native.call(fun, Type::pointer());
+                    if ptr.is_null() {
+                        return Ok(XrossValue::Option(None));
+                    }
+                    let value = self.decoder.boxed(inner, ptr).ok_or_else(unsupported)?;
+                    Ok(XrossValue::Option(Some(Box::new(value))))
+                }
+                XrossType::Option(inner) => {
+                    let ptr: *mut c_void = native.call(fun, Type::pointer());
+                    self.decoder.option(inner, ptr).ok_or_else(unsupported)
+                }
+                ty => self.call_direct(&symbol, ty, fun, native),
+            }
+        }
+    }
+
+    /// Writes a field through its `_set` accessor.
+    pub fn set(
+        &self,
+        obj: &XrossObject,
+        field: &str,
+        value: &XrossValue,
+    ) -> Result<(), XrossInvokeError> {
+        let (def, field) = self.field(obj, field)?;
+        let symbol = Self::property_symbol(def, field, "set");
+        let fun = self.symbol(&symbol)?;
+        let mut native = NativeArgs::default();
+        native.push_ptr(obj.ptr);
+        native
+            .push_arg(&field.ty, value)
+            .map_err(|message| XrossInvokeError::Arguments { symbol: symbol.clone(), message })?;
+        unsafe { native.call::<()>(fun, Type::void()) };
+        Ok(())
+    }
+
+    /// Builds a variant of enum `signature` through its `_new_{variant}` export.
+    pub fn variant(
+        &self,
+        signature: &str,
+        variant: &str,
+        args: &[XrossValue],
+    ) -> Result<XrossObject, XrossInvokeError> {
+        let def = self.definition(signature)?;
+        let XrossDefinition::Enum(e) = def else {
+            return Err(XrossInvokeError::UnknownMember {
+                signature: signature.to_string(),
+                member: variant.to_string(),
+            });
+        };
+        let v = e.variants.iter().find(|v| v.name == variant).ok_or_else(|| {
+            XrossInvokeError::UnknownMember {
+                signature: signature.to_string(),
+                member: variant.to_string(),
+            }
+        })?;
+        let symbol = format!("{}_new_{}", e.symbol_prefix, v.name);
+        let arguments =
+            |message: String| XrossInvokeError::Arguments { symbol: symbol.clone(), message };
+        if args.len() != v.fields.len() {
+            return Err(arguments(format!(
+                "expected {} arguments, got {}",
+                v.fields.len(),
+                args.len()
+            )));
+        }
+        let mut native = NativeArgs::default();
+        for (field, value) in v.fields.iter().zip(args) {
+            self.push_arg(&mut native, &field.ty, value).map_err(arguments)?;
+        }
+        let fun = self.symbol(&symbol)?;
+        let ptr: *mut c_void = unsafe { native.call(fun, Type::pointer()) };
+        Ok(XrossObject { signature: e.signature.clone(), ptr })
+    }
+
+    /// Returns the variant name of an enum object, from `_get_variant_name`.
+    pub fn variant_name(&self, obj: &XrossObject) -> Result<String, XrossInvokeError> {
+        let def = self.definition(&obj.signature)?;
+        let fun = self.symbol(&format!("{}_get_variant_name", def.symbol_prefix()))?;
+        let mut native = NativeArgs::default();
+        native.push_ptr(obj.ptr);
+        let buf: XrossBuffer = unsafe { Self::call_out(fun, native) };
+        Ok(unsafe { self.decoder.string(buf) })
+    }
+}
@@ -0,0 +1,206 @@
+use std::ffi::c_void;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+/// A native object handle, as the JVM bindings hold it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XrossObject {
+    /// Signature of the object's definition, e.g. `fast.FastStruct`.
+    pub signature: String,
+    pub ptr: *mut c_void,
+}
+
+/// The elements of a `contiguous` return. `items` are views into one allocation that stay
+/// valid until it is released with
+/// [`drop_collection`](crate::XrossTestLibrary::drop_collection).
+#[derive(Debug, Clone, PartialEq)]
+pub struct XrossCollection {
+    /// Signature of the element type.
+    pub signature: String,
+    pub items: Vec<XrossObject>,
+    pub(crate) ptr: *mut c_void,
+    pub(crate) cap: usize,
+}
+
+/// An owned by-value struct, passed and returned as its C layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XrossStructValue {
+    /// Signature of the struct's definition, e.g. `fast.Point`.
+    pub signature: String,
+    /// Values of the exposed fields in declaration order.
+    pub fields: Vec<(String, XrossValue)>,
+}
+
+impl XrossStructValue {
+    /// Returns the value of field `name`.
+    pub fn field(&self, name: &str) -> Option<&XrossValue> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+/// A cancellation flag shared with an export that takes an `XrossCancelToken`.
+/// Set it from any thread to cancel the call.
+#[derive(Debug, Clone, Default)]
+pub struct XrossCancelFlag(pub Arc<AtomicBool>);
+
+impl XrossCancelFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Release);
+    }
+}
+
+impl PartialEq for XrossCancelFlag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A value passed to or returned from a generated export.
+///
+/// Arguments must use the variant matching the `XrossType` of the parameter.
+/// Integers are not converted, so an `i32` parameter takes `XrossValue::I32`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XrossValue {
+    Void,
+    Bool(bool),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    ISize(isize),
+    USize(usize),
+    F32(f32),
+    F64(f64),
+    Pointer(*mut c_void),
+    String(String),
+    /// Elements of a `Slice` or `Vec`, and the items of a drained `Iterator` or `Stream`.
+    Vec(Vec<XrossValue>),
+    Object(XrossObject),
+    /// An owned by-value struct. Arguments of such a type also take an `Object`, whose
+    /// struct is copied.
+    Struct(XrossStructValue),
+    /// A `Vec` or slice of objects returned by a `contiguous` export.
+    Collection(XrossCollection),
+    Option(Option<Box<XrossValue>>),
+    Result(Result<Box<XrossValue>, Box<XrossValue>>),
+    /// The flag behind an `XrossCancelToken`. `None` passes a null token.
+    CancelToken(Option<XrossCancelFlag>),
+}
+
+impl XrossValue {
+    /// Returns the object of an `Object` value.
+    pub fn as_object(&self) -> Option<&XrossObject> {
+        match self {
+            XrossValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Returns the struct of a `Struct` value.
+    pub fn as_struct(&self) -> Option<&XrossStructValue> {
+        match self {
+            XrossValue::Struct(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the collection of a `Collection` value.
+    pub fn as_collection(&self) -> Option<&XrossCollection> {
+        match self {
+            XrossValue::Collection(collection) => Some(collection),
+            _ => None,
+        }
+    }
+
+    /// Returns the string of a `String` value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            XrossValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl From<XrossObject> for XrossValue {
+    fn from(obj: XrossObject) -> Self {
+        XrossValue::Object(obj)
+    }
+}
+
+impl From<&str> for XrossValue {
+    fn from(s: &str) -> Self {
+        XrossValue::String(s.to_string())
+    }
+}
+
+macro_rules! impl_from_scalar {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(impl From<$ty> for XrossValue {
+            fn from(v: $ty) -> Self {
+                XrossValue::$variant(v)
+            }
+        })*
+    };
+}
+
+impl_from_scalar! {
+    bool => Bool, i8 => I8, u8 => U8, i16 => I16, u16 => U16, i32 => I32, u32 => U32,
+    i64 => I64, u64 => U64, isize => ISize, usize => USize, f32 => F32, f64 => F64,
+    String => String,
+}
+
+/// Why an `invoke` did not produce a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XrossInvokeError {
+    /// The library or its metadata could not be loaded.
+    Load(String),
+    /// No definition has this signature.
+    UnknownDefinition { signature: String },
+    /// The definition has no method, field or variant of this name.
+    UnknownMember { signature: String, member: String },
+    /// The arguments do not match the parameters of the export.
+    Arguments { symbol: String, message: String },
+    /// The export uses a type the harness cannot encode or decode.
+    Unsupported { symbol: String, message: String },
+    /// A `panicable` export caught a panic. Holds the panic message.
+    Panic(String),
+    /// An `XrossTask` completed with an error: a panic on the blocking pool, a rejection by
+    /// the concurrency limit, or an expired deadline.
+    Task(String),
+    /// A task or stream did not complete within the harness timeout.
+    Timeout { symbol: String },
+}
+
+impl fmt::Display for XrossInvokeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(message) => write!(f, "cannot load library: {}", message),
+            Self::UnknownDefinition { signature } => {
+                write!(f, "unknown definition '{}'", signature)
+            }
+            Self::UnknownMember { signature, member } => {
+                write!(f, "'{}' has no member '{}'", signature, member)
+            }
+            Self::Arguments { symbol, message } => {
+                write!(f, "invalid arguments for {}: {}", symbol, message)
+            }
+            Self::Unsupported { symbol, message } => {
+                write!(f, "cannot call {}: {}", symbol, message)
+            }
+            Self::Panic(message) => write!(f, "panicked: {}", message),
+            Self::Task(message) => write!(f, "task failed: {}", message),
+            Self::Timeout { symbol } => write!(f, "{} did not complete in time", symbol),
+        }
+    }
+}
+
+impl std::error::Error for XrossInvokeError {}